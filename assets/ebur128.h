@@ -20,7 +20,9 @@ extern "C" {
 
 /** \enum channel
  *  Use these values when setting the channel map with ebur128_set_channel().
- *  See definitions in ITU R-REC-BS 1770-4
+ *  See definitions in ITU R-REC-BS 1770-4 and ITU R-REC-BS 2051-2. Upper (U), top (T) and
+ *  bottom (B) layer channels are weighted with 0 dB, middle layer channels between 60 and 120
+ *  degrees azimuth with +1.5 dB.
  */
 enum channel {
   EBUR128_UNUSED = 0,     /**< unused channel (for example LFE channel) */
@@ -48,7 +50,7 @@ enum channel {
   EBUR128_Up030,          /**< itu U+030 */
   EBUR128_Um030,          /**< itu U-030 */
   EBUR128_Up045,          /**< itu U+045 */
-  EBUR128_Um045,          /**< itu U-030 */
+  EBUR128_Um045,          /**< itu U-045 */
   EBUR128_Up090,          /**< itu U+090 */
   EBUR128_Um090,          /**< itu U-090 */
   EBUR128_Up110,          /**< itu U+110 */
@@ -59,7 +61,10 @@ enum channel {
   EBUR128_Tp000,          /**< itu T+000 */
   EBUR128_Bp000,          /**< itu B+000 */
   EBUR128_Bp045,          /**< itu B+045 */
-  EBUR128_Bm045           /**< itu B-045 */
+  EBUR128_Bm045,          /**< itu B-045 */
+  EBUR128_DUAL_MONO_LEFT, /**< left channel of a dual-mono pair */
+  EBUR128_DUAL_MONO_RIGHT /**< right channel of a dual-mono pair, the energies
+                               of both channels are averaged */
 };
 
 /** \enum error
@@ -149,12 +154,48 @@ void ebur128_destroy(ebur128_state** st);
  *  @param value channel type from the "channel" enum.
  *  @return
  *    - EBUR128_SUCCESS on success.
- *    - EBUR128_ERROR_INVALID_CHANNEL_INDEX if invalid channel index.
+ *    - EBUR128_ERROR_INVALID_CHANNEL_INDEX if invalid channel index or if the
+ *      channel would become an unpaired or duplicated member of a dual-mono
+ *      pair, see ebur128_set_channel_map().
  */
 int ebur128_set_channel(ebur128_state* st,
                         unsigned int channel_number,
                         int value);
 
+/** \brief Set the channel types of all channels at once.
+ *
+ *  Both channels of a EBUR128_DUAL_MONO_LEFT and EBUR128_DUAL_MONO_RIGHT pair
+ *  have to be set with this, ebur128_set_channel() rejects unpaired members.
+ *
+ *  @param st library state.
+ *  @param channel_map channel types from the "channel" enum, one per channel.
+ *  @return
+ *    - EBUR128_SUCCESS on success.
+ *    - EBUR128_ERROR_INVALID_CHANNEL_INDEX if a channel type is invalid or the
+ *      map contains an unpaired or duplicated member of a dual-mono pair.
+ */
+int ebur128_set_channel_map(ebur128_state* st, const int* channel_map);
+
+/** \brief Set a custom energy weight for a channel.
+ *
+ *  The energy of the channel is multiplied by the weight instead of the weight
+ *  of the channel type when summing up the channels, e.g. 1.41 for
+ *  EBUR128_LEFT_SURROUND. A weight of 0.0 excludes the channel. Channels set
+ *  to EBUR128_UNUSED are not measured regardless of the weight.
+ *
+ *  @param st library state.
+ *  @param channel_number zero based channel index.
+ *  @param weight linear energy weight, or a negative value to restore the
+ *         weight of the channel type.
+ *  @return
+ *    - EBUR128_SUCCESS on success.
+ *    - EBUR128_ERROR_INVALID_CHANNEL_INDEX if invalid channel index.
+ *    - EBUR128_ERROR_INVALID_MODE if the weight is not finite.
+ */
+int ebur128_set_channel_weight(ebur128_state* st,
+                               unsigned int channel_number,
+                               double weight);
+
 /** \brief Change library parameters.
  *
  *  Note that the channel map will be reset when setting a different number of
@@ -232,6 +273,43 @@ int ebur128_add_frames_double(ebur128_state* st,
                              const double* src,
                              size_t frames);
 
+/** \brief Add planar frames to be processed.
+ *
+ *  @param st library state.
+ *  @param src array of pointers to the source frames of each channel. Must
+ *         have as many elements as there are channels.
+ *  @param frames number of frames per channel.
+ *  @return
+ *    - EBUR128_SUCCESS on success.
+ *    - EBUR128_ERROR_NOMEM if src or one of the channel pointers is NULL.
+ */
+int ebur128_add_frames_planar_i16(ebur128_state* st,
+                                  const short* const* src,
+                                  size_t frames);
+/** \brief See \ref ebur128_add_frames_planar_i16 */
+int ebur128_add_frames_planar_i32(ebur128_state* st,
+                                  const int* const* src,
+                                  size_t frames);
+/** \brief See \ref ebur128_add_frames_planar_i16 */
+int ebur128_add_frames_planar_f32(ebur128_state* st,
+                                  const float* const* src,
+                                  size_t frames);
+/** \brief See \ref ebur128_add_frames_planar_i16 */
+int ebur128_add_frames_planar_f64(ebur128_state* st,
+                                  const double* const* src,
+                                  size_t frames);
+
+/** \brief Get the number of frames processed.
+ *
+ *  Only frames that were actually processed are counted, so a failed call to
+ *  one of the ebur128_add_frames_* functions does not change it. Changing the
+ *  parameters with ebur128_change_parameters() keeps the count.
+ *
+ *  @param st library state.
+ *  @return number of frames processed since initialization.
+ */
+unsigned long long ebur128_frames_processed(ebur128_state* st);
+
 /** \brief Get global integrated loudness in LUFS.
  *
  *  @param st library state.
@@ -250,6 +328,8 @@ int ebur128_loudness_global(ebur128_state* st, double* out);
  *             infinity.
  *  @return
  *    - EBUR128_SUCCESS on success.
+ *    - EBUR128_ERROR_NOMEM if sts, one of its elements or out is NULL.
+ *    - EBUR128_ERROR_NO_CHANGE if size is 0.
  *    - EBUR128_ERROR_INVALID_MODE if mode "EBUR128_MODE_I" has not been set.
  */
 int ebur128_loudness_global_multiple(ebur128_state** sts,
@@ -298,8 +378,7 @@ int ebur128_loudness_window(ebur128_state* st,
  *
  *  @param st library state.
  *  @param out loudness range (LRA) in LU. Will not be changed in case of
- *             error. EBUR128_ERROR_NOMEM or EBUR128_ERROR_INVALID_MODE will be
- *             returned in this case.
+ *             error.
  *  @return
  *    - EBUR128_SUCCESS on success.
  *    - EBUR128_ERROR_NOMEM in case of memory allocation error.
@@ -313,11 +392,11 @@ int ebur128_loudness_range(ebur128_state* st, double* out);
  *  @param sts array of library states.
  *  @param size length of sts
  *  @param out loudness range (LRA) in LU. Will not be changed in case of
- *             error. EBUR128_ERROR_NOMEM or EBUR128_ERROR_INVALID_MODE will be
- *             returned in this case.
+ *             error.
  *  @return
  *    - EBUR128_SUCCESS on success.
- *    - EBUR128_ERROR_NOMEM in case of memory allocation error.
+ *    - EBUR128_ERROR_NOMEM if sts, one of its elements or out is NULL.
+ *    - EBUR128_ERROR_NO_CHANGE if size is 0.
  *    - EBUR128_ERROR_INVALID_MODE if mode "EBUR128_MODE_LRA" has not been set.
  */
 int ebur128_loudness_range_multiple(ebur128_state** sts,
@@ -0,0 +1,70 @@
+// Minimal timing helpers shared by the benchmarks, run them with `cargo bench --bench <name>`.
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+/// Runs `f` once to warm up and then a few more times, prints the median duration and the
+/// throughput in frames per second and returns the median duration.
+pub fn bench(name: &str, frames: usize, mut f: impl FnMut()) -> Duration {
+    const RUNS: usize = 7;
+
+    f();
+    let mut durations = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect::<Vec<_>>();
+    durations.sort();
+
+    let median = durations[RUNS / 2];
+    println!(
+        "{:<56} {:>10.3} ms {:>10.2} Mframes/s",
+        name,
+        median.as_secs_f64() * 1e3,
+        frames as f64 / median.as_secs_f64() / 1e6
+    );
+
+    median
+}
+
+/// Deterministic white noise in the range -0.5 to 0.5.
+pub fn noise(len: usize) -> Vec<f32> {
+    let mut state = 0x1234_5678_u32;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1 << 24) as f32 - 0.5
+        })
+        .collect()
+}
+
+/// Splits interleaved samples into one buffer per channel.
+pub fn deinterleave(samples: &[f32], channels: usize) -> Vec<Vec<f32>> {
+    (0..channels)
+        .map(|c| samples.iter().skip(c).step_by(channels).copied().collect())
+        .collect()
+}
+
+/// Calls `f` many times in a few runs, prints the median duration of a single call and returns it.
+pub fn latency(name: &str, mut f: impl FnMut()) -> Duration {
+    const RUNS: usize = 7;
+    const CALLS: u32 = 1000;
+
+    let mut durations = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..CALLS {
+                f();
+            }
+            start.elapsed() / CALLS
+        })
+        .collect::<Vec<_>>();
+    durations.sort();
+
+    let median = durations[RUNS / 2];
+    println!("{:<56} {:>10.3} us", name, median.as_secs_f64() * 1e6);
+
+    median
+}
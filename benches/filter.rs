@@ -0,0 +1,59 @@
+// Compares the SIMD and the scalar filter implementations with many channels. Fails if the CPU
+// supports AVX2 but the filter silently falls back to a narrower implementation.
+
+mod common;
+
+use ebur128::{EbuR128, Mode};
+use std::hint::black_box;
+
+fn main() {
+    const RATE: u32 = 192_000;
+    const CHANNELS: usize = 48;
+    const SECONDS: usize = 10;
+
+    // One second of noise that is added repeatedly
+    let samples = common::noise(RATE as usize * CHANNELS);
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if std::is_x86_feature_detected!("avx2") {
+        assert_eq!(
+            ebur128::simd_filter_channels(),
+            4,
+            "AVX2 filter is not used"
+        );
+    }
+
+    let mut loudness = Vec::new();
+    let mut durations = Vec::new();
+    for simd in [true, false] {
+        ebur128::set_simd_enabled(simd);
+        let name = match ebur128::simd_filter_channels() {
+            4 => "AVX2",
+            2 => "SSE2/NEON",
+            _ => "scalar",
+        };
+        let duration = common::bench(
+            &format!(
+                "{} channels, {} Hz, {} s, {}",
+                CHANNELS, RATE, SECONDS, name
+            ),
+            RATE as usize * SECONDS,
+            || {
+                let mut ebu = EbuR128::new(CHANNELS as u32, RATE, Mode::M).unwrap();
+                for _ in 0..SECONDS {
+                    ebu.add_frames_f32(black_box(&samples)).unwrap();
+                }
+                loudness.push(black_box(ebu.loudness_momentary().unwrap()));
+            },
+        );
+        durations.push(duration);
+    }
+    ebur128::set_simd_enabled(true);
+
+    println!(
+        "SIMD speedup: {:.2}x",
+        durations[1].as_secs_f64() / durations[0].as_secs_f64()
+    );
+
+    assert!(loudness.windows(2).all(|l| l[0] == l[1]));
+}
@@ -0,0 +1,48 @@
+// Measures how long loudness_global() takes after one minute and after six hours of input. Without
+// Mode::HISTOGRAM this should not depend on the number of stored gating blocks.
+
+mod common;
+
+use ebur128::{EbuR128, Mode};
+use std::hint::black_box;
+
+fn main() {
+    const RATE: u32 = 8_000;
+
+    // One minute of noise with a level that changes over 30 dB, so that the blocks spread over
+    // many histogram bins like in a real program.
+    let minute = common::noise(60 * RATE as usize)
+        .into_iter()
+        .enumerate()
+        .map(|(i, sample)| {
+            let db = -30.0 * (i as f32 / RATE as f32 / 11.0).sin().abs();
+            sample * 10f32.powf(db / 20.0)
+        })
+        .collect::<Vec<_>>();
+
+    for (name, mode, history) in [
+        ("queue", Mode::I, None),
+        ("queue, 1 h history", Mode::I, Some(3_600_000)),
+        ("histogram", Mode::I | Mode::HISTOGRAM, None),
+    ] {
+        let mut ebu = EbuR128::new(1, RATE, mode).unwrap();
+        if let Some(history) = history {
+            ebu.set_max_history(history).unwrap();
+        }
+
+        let mut minutes = 0;
+        for total in [1, 6 * 60] {
+            while minutes < total {
+                ebu.add_frames_f32(&minute).unwrap();
+                minutes += 1;
+            }
+
+            common::latency(
+                &format!("loudness_global, {}, after {} min", name, minutes),
+                || {
+                    black_box(black_box(&ebu).loudness_global().unwrap());
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,45 @@
+// Compares the NEON (aarch64) or SSE (x86) true-peak interpolation with the scalar implementation.
+
+mod common;
+
+use ebur128::{EbuR128, Mode};
+use std::hint::black_box;
+
+fn main() {
+    const RATE: u32 = 48_000;
+    const SECONDS: usize = 10;
+
+    let simd_name = if cfg!(target_arch = "aarch64") {
+        "NEON"
+    } else if cfg!(any(target_arch = "x86", target_arch = "x86_64")) {
+        "SSE"
+    } else {
+        "SIMD (unavailable)"
+    };
+
+    for channels in [1, 2, 8] {
+        let samples = common::noise(RATE as usize * SECONDS * channels);
+
+        let mut peaks = Vec::new();
+        for (simd, name) in [(true, simd_name), (false, "scalar")] {
+            ebur128::set_simd_enabled(simd);
+            common::bench(
+                &format!("{} ch, interpolate, {}", channels, name),
+                RATE as usize * SECONDS,
+                || {
+                    let mut ebu = EbuR128::new(channels as u32, RATE, Mode::TRUE_PEAK).unwrap();
+                    ebu.add_frames_f32(black_box(&samples)).unwrap();
+                    peaks.push(
+                        (0..channels as u32)
+                            .map(|c| ebu.true_peak(c).unwrap())
+                            .collect::<Vec<_>>(),
+                    );
+                },
+            );
+        }
+        ebur128::set_simd_enabled(true);
+
+        // Each lane does the same operations as the scalar implementation
+        assert!(peaks.windows(2).all(|p| p[0] == p[1]));
+    }
+}
@@ -0,0 +1,46 @@
+// Compares filtering and true-peak interpolation in parallel with rayon to processing with a single
+// thread.
+
+mod common;
+
+use ebur128::{EbuR128, Mode};
+use std::hint::black_box;
+
+fn main() {
+    const RATE: u32 = 48_000;
+    const SECONDS: usize = 10;
+
+    let single = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+
+    for channels in [2, 8, 24] {
+        let samples = common::noise(RATE as usize * SECONDS * channels);
+
+        let all_threads = format!("global pool, {} threads", rayon::current_num_threads());
+        for (what, mode) in [("filter", Mode::I), ("true peak", Mode::TRUE_PEAK)] {
+            for (name, pool) in [
+                ("single thread pool", Some(&single)),
+                (all_threads.as_str(), None),
+            ] {
+                let run = || {
+                    common::bench(
+                        &format!("{} channels, {}, {}", channels, what, name),
+                        RATE as usize * SECONDS,
+                        || {
+                            let mut ebu = EbuR128::new(channels as u32, RATE, mode).unwrap();
+                            ebu.add_frames_f32(black_box(&samples)).unwrap();
+                            black_box(&ebu);
+                        },
+                    );
+                };
+
+                match pool {
+                    Some(pool) => pool.install(run),
+                    None => run(),
+                }
+            }
+        }
+    }
+}
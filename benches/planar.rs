@@ -0,0 +1,38 @@
+// Compares adding interleaved and planar samples of the same signal.
+
+mod common;
+
+use ebur128::{EbuR128, Mode};
+use std::hint::black_box;
+
+fn main() {
+    const RATE: u32 = 48_000;
+    const FRAMES: usize = 10 * RATE as usize;
+
+    for channels in [2, 8] {
+        let interleaved = common::noise(FRAMES * channels);
+        let planar = common::deinterleave(&interleaved, channels);
+        let planar = planar.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        for (name, mode) in [("I", Mode::I), ("I | TRUE_PEAK", Mode::I | Mode::TRUE_PEAK)] {
+            common::bench(
+                &format!("{channels} channels, {name}, interleaved"),
+                FRAMES,
+                || {
+                    let mut ebu = EbuR128::new(channels as u32, RATE, mode).unwrap();
+                    ebu.add_frames_f32(black_box(&interleaved)).unwrap();
+                    black_box(ebu.loudness_global().unwrap());
+                },
+            );
+            common::bench(
+                &format!("{channels} channels, {name}, planar"),
+                FRAMES,
+                || {
+                    let mut ebu = EbuR128::new(channels as u32, RATE, mode).unwrap();
+                    ebu.add_frames_planar_f32(black_box(&planar)).unwrap();
+                    black_box(ebu.loudness_global().unwrap());
+                },
+            );
+        }
+    }
+}
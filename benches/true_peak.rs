@@ -0,0 +1,36 @@
+// Measures the throughput of true peak measurement alone for mono and stereo at different sample
+// rates, with the SIMD and the scalar interpolation.
+
+mod common;
+
+use ebur128::{EbuR128, Mode};
+use std::hint::black_box;
+
+fn main() {
+    const SECONDS: usize = 10;
+
+    for rate in [44_100, 48_000, 96_000] {
+        for channels in [1, 2] {
+            let samples = common::noise(rate as usize * SECONDS * channels);
+
+            for simd in [true, false] {
+                ebur128::set_simd_enabled(simd);
+                common::bench(
+                    &format!(
+                        "{} Hz, {} ch, true peak, {}",
+                        rate,
+                        channels,
+                        if simd { "SIMD" } else { "scalar" }
+                    ),
+                    rate as usize * SECONDS,
+                    || {
+                        let mut ebu = EbuR128::new(channels as u32, rate, Mode::TRUE_PEAK).unwrap();
+                        ebu.add_frames_f32(black_box(&samples)).unwrap();
+                        black_box(ebu.true_peak(0).unwrap());
+                    },
+                );
+            }
+            ebur128::set_simd_enabled(true);
+        }
+    }
+}
@@ -1,4 +1,44 @@
+use std::f64::consts::PI;
+use std::fmt::Write;
+use std::{env, fs, path::Path};
+
+const ALMOST_ZERO: f64 = 0.000001;
+
+/// Hanning-windowed sinc coefficients of the true peak interpolator, must match
+/// `interp::filter_coefficients`.
+fn interp_filter_coefficients(factor: usize, taps: usize) -> Vec<f32> {
+    let mut coeffs = vec![0.0; taps];
+    for (j, coeff) in coeffs.iter_mut().enumerate() {
+        let j = j as f64;
+        let window = taps as f64;
+        let w = 0.5 * (1.0 - f64::cos(2.0 * PI * j / window));
+
+        let m = j - window / 2.0;
+        *coeff = if m.abs() > ALMOST_ZERO {
+            w * f64::sin(m * PI / factor as f64) / (m * PI / factor as f64)
+        } else {
+            w
+        } as f32;
+    }
+    coeffs
+}
+
 fn main() {
+    let mut interp_coeffs = String::new();
+    // The 8x prototype filter is twice as long so that it spans the same number of input samples
+    // as the 4x one and keeps a comparable stopband attenuation
+    for (factor, taps) in [(2, 48), (4, 48), (8, 96)] {
+        writeln!(
+            interp_coeffs,
+            "pub(crate) static INTERP_FILTER_COEFFS_{factor}: [f32; {taps}] = {:?};",
+            interp_filter_coefficients(factor, taps)
+        )
+        .unwrap();
+    }
+    let out_dir = env::var_os("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("interp_coeffs.rs"), interp_coeffs).unwrap();
+    println!("cargo:rerun-if-changed=build.rs");
+
     #[cfg(feature = "bindgen")]
     {
         csbindgen::Builder::default()
@@ -0,0 +1,29 @@
+//! Meter frames of the `dasp` crates.
+//!
+//! Run with `cargo run --example dasp --features dasp`.
+
+use dasp_frame::Frame;
+use ebur128::{EbuR128, Mode};
+
+fn main() -> Result<(), ebur128::Error> {
+    let rate = 48_000;
+    let mut ebu = EbuR128::new(2, rate, Mode::I | Mode::TRUE_PEAK)?;
+
+    // 5 seconds of a stereo 1kHz sine at -6 dBFS, as 16 bit frames
+    let frames = (0..rate * 5)
+        .map(|i| {
+            let s = (2.0 * std::f64::consts::PI * 1000.0 * f64::from(i) / f64::from(rate)).sin();
+            [s, s].scale_amp(0.5).map(dasp_sample::Sample::to_sample::<i16>)
+        })
+        .collect::<Vec<[i16; 2]>>();
+
+    // Add the frames in chunks of 100ms, e.g. as they are generated
+    for chunk in frames.chunks(rate as usize / 10) {
+        ebu.add_dasp_frames(chunk)?;
+    }
+
+    println!("Integrated loudness: {:.1} LUFS", ebu.loudness_global()?);
+    println!("True peak: {:.1} dBTP", ebu.true_peak_max_all_channels()?);
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! Meter an audio file decoded with `symphonia`.
+//!
+//! Run with `cargo run --example symphonia --features symphonia -- <file.wav|file.flac>`.
+
+use ebur128::{EbuR128, Mode};
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::args().nth(1).ok_or("Usage: symphonia <file>")?;
+
+    let file = std::fs::File::open(&path)?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &Hint::new(),
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?
+        .format;
+
+    let track = format.default_track().ok_or("No audio track")?;
+    let track_id = track.id;
+    let mut decoder =
+        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    // The analyzer is created with the parameters of the first decoded buffer
+    let mut ebu: Option<EbuR128> = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let buf = decoder.decode(&packet)?;
+        let ebu = match ebu {
+            Some(ref mut ebu) => ebu,
+            None => {
+                let spec = buf.spec();
+                ebu.insert(EbuR128::new(
+                    spec.channels.count() as u32,
+                    spec.rate,
+                    Mode::I | Mode::LRA | Mode::TRUE_PEAK,
+                )?)
+            }
+        };
+        ebu.add_audio_buffer(&buf)?;
+    }
+
+    let ebu = ebu.ok_or("No audio decoded")?;
+    println!("Integrated loudness: {:.1} LUFS", ebu.loudness_global()?);
+    println!("Loudness range: {:.1} LU", ebu.loudness_range()?);
+    println!("True peak: {:.1} dBTP", ebu.true_peak_max_all_channels()?);
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+#![no_main]
+
+use ebur128::{EbuR128, Mode};
+use libfuzzer_sys::fuzz_target;
+use std::convert::TryInto;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 6 {
+        return;
+    }
+
+    // Limit the channels and sample rate to keep the memory usage reasonable, but also allow
+    // invalid values
+    let channels = u32::from(data[0] % 9);
+    let rate = u32::from_le_bytes([data[1], data[2], data[3], 0]) % 200_000;
+    let mode = Mode::from_bits_truncate(data[4]);
+
+    let mut ebu = match EbuR128::new(channels, rate, mode) {
+        Ok(ebu) => ebu,
+        Err(_) => return,
+    };
+
+    let frames = data[5..]
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes(b.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    let frames = &frames[..frames.len() - frames.len() % channels as usize];
+
+    // Split the samples in two calls to also cover incomplete blocks
+    let (first, second) = frames.split_at(frames.len() / 2 / channels as usize * channels as usize);
+    ebu.add_frames_i16(first).unwrap();
+    ebu.add_frames_i16(second).unwrap();
+
+    // All of these may fail depending on the mode but must not panic
+    let _ = ebu.loudness_global();
+    let _ = ebu.loudness_momentary();
+    let _ = ebu.loudness_shortterm();
+    let _ = ebu.loudness_window(1000);
+    let _ = ebu.loudness_range();
+    let _ = ebu.loudness_percentile(50.0);
+    let _ = ebu.relative_threshold();
+    let _ = ebu.summary();
+    for c in 0..channels {
+        let _ = ebu.sample_peak(c);
+        let _ = ebu.true_peak(c);
+    }
+});
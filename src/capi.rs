@@ -11,6 +11,27 @@ pub struct State {
     internal: *mut ebur128::EbuR128,
 }
 
+// Applications written against libebur128 read the public fields directly, so they have to stay at
+// the same offsets
+const _: () = {
+    use std::os::raw::{c_int, c_uint, c_ulong};
+
+    assert!(mem::offset_of!(State, mode) == 0);
+    assert!(mem::offset_of!(State, channels) == mem::size_of::<c_int>());
+    assert!(mem::offset_of!(State, samplerate) % mem::align_of::<c_ulong>() == 0);
+    assert!(mem::offset_of!(State, samplerate) >= 2 * mem::size_of::<c_uint>());
+    assert!(
+        mem::offset_of!(State, samplerate)
+            < 2 * mem::size_of::<c_uint>() + mem::align_of::<c_ulong>()
+    );
+    assert!(
+        mem::offset_of!(State, internal)
+            == mem::offset_of!(State, samplerate) + mem::size_of::<c_ulong>()
+    );
+    assert!(mem::size_of::<i32>() == mem::size_of::<c_int>());
+    assert!(mem::size_of::<u32>() == mem::size_of::<c_uint>());
+};
+
 #[no_mangle]
 pub unsafe extern "C" fn ebur128_get_version(major: *mut i32, minor: *mut i32, patch: *mut i32) {
     // We're based on 1.2.6 so let's return that for now
@@ -59,12 +80,60 @@ pub unsafe extern "C" fn ebur128_destroy(state: *mut *mut State) {
     *state = ptr::null_mut();
 }
 
+// Error codes and modes with the same values as in libebur128
+pub const EBUR128_SUCCESS: i32 = 0;
+pub const EBUR128_ERROR_NOMEM: i32 = 1;
+pub const EBUR128_ERROR_INVALID_MODE: i32 = 2;
+pub const EBUR128_ERROR_INVALID_CHANNEL_INDEX: i32 = 3;
+pub const EBUR128_ERROR_NO_CHANGE: i32 = 4;
+
+pub const EBUR128_MODE_M: i32 = ebur128::Mode::M.bits() as i32;
+pub const EBUR128_MODE_S: i32 = ebur128::Mode::S.bits() as i32;
+pub const EBUR128_MODE_I: i32 = ebur128::Mode::I.bits() as i32;
+pub const EBUR128_MODE_LRA: i32 = ebur128::Mode::LRA.bits() as i32;
+pub const EBUR128_MODE_SAMPLE_PEAK: i32 = ebur128::Mode::SAMPLE_PEAK.bits() as i32;
+pub const EBUR128_MODE_TRUE_PEAK: i32 = ebur128::Mode::TRUE_PEAK.bits() as i32;
+pub const EBUR128_MODE_HISTOGRAM: i32 = ebur128::Mode::HISTOGRAM.bits() as i32;
+
+// The values from libebur128's ebur128.h
+const _: () = {
+    assert!(EBUR128_MODE_M == 1 << 0);
+    assert!(EBUR128_MODE_S == (1 << 1) | EBUR128_MODE_M);
+    assert!(EBUR128_MODE_I == (1 << 2) | EBUR128_MODE_M);
+    assert!(EBUR128_MODE_LRA == (1 << 3) | EBUR128_MODE_S);
+    assert!(EBUR128_MODE_SAMPLE_PEAK == (1 << 4) | EBUR128_MODE_M);
+    assert!(EBUR128_MODE_TRUE_PEAK == (1 << 5) | EBUR128_MODE_M | EBUR128_MODE_SAMPLE_PEAK);
+    assert!(EBUR128_MODE_HISTOGRAM == 1 << 6);
+    assert!(ebur128::Channel::Unused as i32 == 0);
+    assert!(ebur128::Channel::Left as i32 == 1);
+    assert!(ebur128::Channel::Right as i32 == 2);
+    assert!(ebur128::Channel::Center as i32 == 3);
+    assert!(ebur128::Channel::LeftSurround as i32 == 4);
+    assert!(ebur128::Channel::RightSurround as i32 == 5);
+    assert!(ebur128::Channel::DualMono as i32 == 6);
+};
+
 impl From<ebur128::Error> for i32 {
     fn from(v: ebur128::Error) -> i32 {
         match v {
-            ebur128::Error::NoMem => 1,
-            ebur128::Error::InvalidMode => 2,
-            ebur128::Error::InvalidChannelIndex => 3,
+            ebur128::Error::NoMem => EBUR128_ERROR_NOMEM,
+            ebur128::Error::InvalidMode => EBUR128_ERROR_INVALID_MODE,
+            ebur128::Error::InvalidChannelIndex => EBUR128_ERROR_INVALID_CHANNEL_INDEX,
+            ebur128::Error::NoChange => EBUR128_ERROR_NO_CHANGE,
+            ebur128::Error::InvalidChannelCount => EBUR128_ERROR_INVALID_CHANNEL_INDEX,
+            ebur128::Error::InvalidFrameCount => EBUR128_ERROR_NOMEM,
+            // Never returned by the C API
+            ebur128::Error::IncompatibleState => EBUR128_ERROR_INVALID_MODE,
+            ebur128::Error::NoDialogueBlocks => EBUR128_ERROR_NO_CHANGE,
+            ebur128::Error::RequestedWindowTooLarge { .. } => EBUR128_ERROR_INVALID_MODE,
+            ebur128::Error::OutOfRange => EBUR128_ERROR_INVALID_MODE,
+            ebur128::Error::InvalidFrameLength { .. } => EBUR128_ERROR_INVALID_CHANNEL_INDEX,
+            // Never returned by the C API
+            ebur128::Error::Io => EBUR128_ERROR_NOMEM,
+            // Never returned by the C API
+            ebur128::Error::Format => EBUR128_ERROR_INVALID_MODE,
+            // Never returned by the C API
+            ebur128::Error::InvalidSample { .. } => EBUR128_ERROR_INVALID_MODE,
         }
     }
 }
@@ -79,12 +148,65 @@ pub unsafe extern "C" fn ebur128_set_channel(
     let s = &mut *state;
     let e = &mut *s.internal;
 
+    if !(ebur128::Channel::Unused as i32..=ebur128::Channel::DualMonoRight as i32).contains(&value)
+    {
+        return EBUR128_ERROR_INVALID_CHANNEL_INDEX;
+    }
+
     match e.set_channel(
         channel_number,
         mem::transmute::<i32, ebur128::Channel>(value),
     ) {
         Err(err) => err.into(),
-        Ok(_) => 0,
+        Ok(_) => EBUR128_SUCCESS,
+    }
+}
+
+// Needed for dual-mono pairs, whose channels can't be set one by one
+#[no_mangle]
+pub unsafe extern "C" fn ebur128_set_channel_map(
+    state: *mut State,
+    channel_map: *const i32,
+) -> i32 {
+    let s = &mut *state;
+    let e = &mut *s.internal;
+
+    if channel_map.is_null() {
+        return EBUR128_ERROR_INVALID_CHANNEL_INDEX;
+    }
+
+    let values = std::slice::from_raw_parts(channel_map, e.channels() as usize);
+    let mut map = Vec::with_capacity(values.len());
+    for value in values {
+        if !(ebur128::Channel::Unused as i32..=ebur128::Channel::DualMonoRight as i32)
+            .contains(value)
+        {
+            return EBUR128_ERROR_INVALID_CHANNEL_INDEX;
+        }
+        map.push(mem::transmute::<i32, ebur128::Channel>(*value));
+    }
+
+    match e.set_channel_map(&map) {
+        Err(err) => err.into(),
+        Ok(_) => EBUR128_SUCCESS,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ebur128_set_channel_weight(
+    state: *mut State,
+    channel_number: u32,
+    weight: f64,
+) -> i32 {
+    let s = &mut *state;
+    let e = &mut *s.internal;
+
+    // A negative weight restores the weight of the channel type
+    let weight = if weight < 0.0 { None } else { Some(weight) };
+
+    match e.set_channel_weight(channel_number, weight) {
+        Err(err) => err.into(),
+        Ok(_) => EBUR128_SUCCESS,
     }
 }
 
@@ -97,7 +219,7 @@ pub unsafe extern "C" fn ebur128_change_parameters(
     let s = &mut *state;
 
     if s.channels == channels && s.samplerate == samplerate {
-        return 4; // EBUR128_ERROR_NO_CHANGE
+        return EBUR128_ERROR_NO_CHANGE;
     }
 
     let e = &mut *s.internal;
@@ -107,7 +229,7 @@ pub unsafe extern "C" fn ebur128_change_parameters(
         Ok(_) => {
             s.channels = channels;
             s.samplerate = samplerate;
-            0
+            EBUR128_SUCCESS
         }
     }
 }
@@ -125,12 +247,12 @@ pub unsafe extern "C" fn ebur128_set_max_window(
     let e = &mut *s.internal;
 
     if e.max_window() == window as usize {
-        return 4; // EBUR128_ERROR_NO_CHANGE
+        return EBUR128_ERROR_NO_CHANGE;
     }
 
     match e.set_max_window(window as u32) {
         Err(err) => err.into(),
-        Ok(_) => 0,
+        Ok(_) => EBUR128_SUCCESS,
     }
 }
 
@@ -147,12 +269,12 @@ pub unsafe extern "C" fn ebur128_set_max_history(
     let e = &mut *s.internal;
 
     if e.max_history() == history as usize {
-        return 4; // EBUR128_ERROR_NO_CHANGE
+        return EBUR128_ERROR_NO_CHANGE;
     }
 
     match e.set_max_history(history as u32) {
         Err(err) => err.into(),
-        Ok(_) => 0,
+        Ok(_) => EBUR128_SUCCESS,
     }
 }
 
@@ -174,7 +296,7 @@ pub unsafe extern "C" fn ebur128_add_frames_short(
 
     match e.add_frames_i16(slice::from_raw_parts(src, samples)) {
         Err(err) => err.into(),
-        Ok(_) => 0,
+        Ok(_) => EBUR128_SUCCESS,
     }
 }
 
@@ -196,7 +318,7 @@ pub unsafe extern "C" fn ebur128_add_frames_int(
 
     match e.add_frames_i32(slice::from_raw_parts(src, samples)) {
         Err(err) => err.into(),
-        Ok(_) => 0,
+        Ok(_) => EBUR128_SUCCESS,
     }
 }
 
@@ -206,19 +328,12 @@ pub unsafe extern "C" fn ebur128_add_frames_float(
     src: *const f32,
     frames: usize,
 ) -> i32 {
-    use std::slice;
-
     let s = &mut *state;
     let e = &mut *s.internal;
 
-    let samples = match frames.checked_mul(s.channels as usize) {
-        None => return crate::ebur128::Error::NoMem.into(),
-        Some(samples) => samples,
-    };
-
-    match e.add_frames_f32(slice::from_raw_parts(src, samples)) {
+    match e.add_frames_f32_raw(src, frames) {
         Err(err) => err.into(),
-        Ok(_) => 0,
+        Ok(_) => EBUR128_SUCCESS,
     }
 }
 
@@ -240,10 +355,94 @@ pub unsafe extern "C" fn ebur128_add_frames_double(
 
     match e.add_frames_f64(slice::from_raw_parts(src, samples)) {
         Err(err) => err.into(),
-        Ok(_) => 0,
+        Ok(_) => EBUR128_SUCCESS,
     }
 }
 
+unsafe fn add_frames_planar<T>(
+    state: *mut State,
+    src: *const *const T,
+    frames: usize,
+    func: impl FnOnce(&mut ebur128::EbuR128, &[&[T]]) -> Result<(), ebur128::Error>,
+) -> i32 {
+    use smallvec::SmallVec;
+    use std::slice;
+
+    let s = &mut *state;
+    let e = &mut *s.internal;
+
+    if src.is_null() {
+        return ebur128::Error::NoMem.into();
+    }
+
+    let channels = slice::from_raw_parts(src, s.channels as usize);
+    if channels.iter().any(|channel| channel.is_null()) {
+        return ebur128::Error::NoMem.into();
+    }
+
+    let planes = channels
+        .iter()
+        .map(|channel| slice::from_raw_parts(*channel, frames))
+        .collect::<SmallVec<[_; 16]>>();
+
+    match func(e, &planes) {
+        Err(err) => err.into(),
+        Ok(_) => EBUR128_SUCCESS,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ebur128_add_frames_planar_i16(
+    state: *mut State,
+    src: *const *const i16,
+    frames: usize,
+) -> i32 {
+    add_frames_planar(state, src, frames, |e, planes| {
+        e.add_frames_planar_i16(planes)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ebur128_add_frames_planar_i32(
+    state: *mut State,
+    src: *const *const i32,
+    frames: usize,
+) -> i32 {
+    add_frames_planar(state, src, frames, |e, planes| {
+        e.add_frames_planar_i32(planes)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ebur128_add_frames_planar_f32(
+    state: *mut State,
+    src: *const *const f32,
+    frames: usize,
+) -> i32 {
+    add_frames_planar(state, src, frames, |e, planes| {
+        e.add_frames_planar_f32(planes)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ebur128_add_frames_planar_f64(
+    state: *mut State,
+    src: *const *const f64,
+    frames: usize,
+) -> i32 {
+    add_frames_planar(state, src, frames, |e, planes| {
+        e.add_frames_planar_f64(planes)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ebur128_frames_processed(state: *mut State) -> std::os::raw::c_ulonglong {
+    let s = &*state;
+    let e = &*s.internal;
+
+    e.frames_processed()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn ebur128_loudness_global(state: *mut State, out: *mut f64) -> i32 {
     let s = &*state;
@@ -253,31 +452,58 @@ pub unsafe extern "C" fn ebur128_loudness_global(state: *mut State, out: *mut f6
         Err(err) => err.into(),
         Ok(val) => {
             *out = val;
-            0
+            EBUR128_SUCCESS
         }
     }
 }
 
-#[no_mangle]
-pub unsafe extern "C" fn ebur128_loudness_global_multiple(
+/// Validates the arguments of the `*_multiple` functions and calls `func` with all states.
+///
+/// A null `state` array, a null element or a null `out` pointer return
+/// `EBUR128_ERROR_NOMEM`, an empty array returns `EBUR128_ERROR_NO_CHANGE` and states without
+/// the mode required by `func` return `EBUR128_ERROR_INVALID_MODE`.
+unsafe fn multiple<'a>(
     state: *mut *mut State,
     size: usize,
     out: *mut f64,
+    func: impl FnOnce(&mut dyn Iterator<Item = &'a ebur128::EbuR128>) -> Result<f64, ebur128::Error>,
 ) -> i32 {
     use std::slice;
 
+    if state.is_null() || out.is_null() {
+        return EBUR128_ERROR_NOMEM;
+    }
+    if size == 0 {
+        return EBUR128_ERROR_NO_CHANGE;
+    }
+
     let s = slice::from_raw_parts(state, size);
-    let iter = s.iter().copied().map(|s: *mut State| &*(*s).internal);
+    if s.iter().any(|s| s.is_null()) {
+        return EBUR128_ERROR_NOMEM;
+    }
 
-    match ebur128::EbuR128::loudness_global_multiple(iter) {
+    let mut iter = s.iter().copied().map(|s: *mut State| &*(*s).internal);
+
+    match func(&mut iter) {
         Err(err) => err.into(),
         Ok(val) => {
             *out = val;
-            0
+            EBUR128_SUCCESS
         }
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn ebur128_loudness_global_multiple(
+    state: *mut *mut State,
+    size: usize,
+    out: *mut f64,
+) -> i32 {
+    multiple(state, size, out, |iter| {
+        ebur128::EbuR128::loudness_global_multiple(iter)
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn ebur128_loudness_momentary(state: *mut State, out: *mut f64) -> i32 {
     let s = &*state;
@@ -287,7 +513,7 @@ pub unsafe extern "C" fn ebur128_loudness_momentary(state: *mut State, out: *mut
         Err(err) => err.into(),
         Ok(val) => {
             *out = val;
-            0
+            EBUR128_SUCCESS
         }
     }
 }
@@ -301,7 +527,7 @@ pub unsafe extern "C" fn ebur128_loudness_shortterm(state: *mut State, out: *mut
         Err(err) => err.into(),
         Ok(val) => {
             *out = val;
-            0
+            EBUR128_SUCCESS
         }
     }
 }
@@ -319,11 +545,12 @@ pub unsafe extern "C" fn ebur128_loudness_window(
         return ebur128::Error::NoMem.into();
     }
 
-    match e.loudness_window(window as u32) {
+    // Like libebur128, only allow windows up to the maximum window
+    match e.loudness_window_buffered(window as u32) {
         Err(err) => err.into(),
         Ok(val) => {
             *out = val;
-            0
+            EBUR128_SUCCESS
         }
     }
 }
@@ -337,7 +564,7 @@ pub unsafe extern "C" fn ebur128_loudness_range(state: *mut State, out: *mut f64
         Err(err) => err.into(),
         Ok(val) => {
             *out = val;
-            0
+            EBUR128_SUCCESS
         }
     }
 }
@@ -348,18 +575,9 @@ pub unsafe extern "C" fn ebur128_loudness_range_multiple(
     size: usize,
     out: *mut f64,
 ) -> i32 {
-    use std::slice;
-
-    let s = slice::from_raw_parts(state, size);
-    let iter = s.iter().copied().map(|s: *mut State| &*(*s).internal);
-
-    match ebur128::EbuR128::loudness_range_multiple(iter) {
-        Err(err) => err.into(),
-        Ok(val) => {
-            *out = val;
-            0
-        }
-    }
+    multiple(state, size, out, |iter| {
+        ebur128::EbuR128::loudness_range_multiple(iter)
+    })
 }
 
 #[no_mangle]
@@ -375,7 +593,7 @@ pub unsafe extern "C" fn ebur128_sample_peak(
         Err(err) => err.into(),
         Ok(val) => {
             *out = val;
-            0
+            EBUR128_SUCCESS
         }
     }
 }
@@ -393,7 +611,7 @@ pub unsafe extern "C" fn ebur128_prev_sample_peak(
         Err(err) => err.into(),
         Ok(val) => {
             *out = val;
-            0
+            EBUR128_SUCCESS
         }
     }
 }
@@ -411,7 +629,7 @@ pub unsafe extern "C" fn ebur128_true_peak(
         Err(err) => err.into(),
         Ok(val) => {
             *out = val;
-            0
+            EBUR128_SUCCESS
         }
     }
 }
@@ -429,7 +647,7 @@ pub unsafe extern "C" fn ebur128_prev_true_peak(
         Err(err) => err.into(),
         Ok(val) => {
             *out = val;
-            0
+            EBUR128_SUCCESS
         }
     }
 }
@@ -443,7 +661,7 @@ pub unsafe extern "C" fn ebur128_relative_threshold(state: *mut State, out: *mut
         Err(err) => err.into(),
         Ok(val) => {
             *out = val;
-            0
+            EBUR128_SUCCESS
         }
     }
 }
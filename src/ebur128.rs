@@ -20,15 +20,26 @@
 // THE SOFTWARE.
 
 use crate::energy_to_loudness;
-use crate::utils::Sample;
+use crate::history::HistogramBin;
+use crate::math;
+use crate::LoudnessStats;
+use crate::LoudnessValue;
 
 use bitflags::bitflags;
+use smallvec::{smallvec, SmallVec};
 
-use std::error;
-use std::fmt;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::error;
+use core::fmt;
+use core::time::Duration;
 
 /// Error values for [`EbuR128`](struct.EbuR128.html) functions.
+///
+/// New variants may be added in minor releases, so matches need a wildcard arm.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Error {
     /// Not enough memory
     NoMem,
@@ -36,16 +47,100 @@ pub enum Error {
     InvalidMode,
     /// Invalid channel index passed
     InvalidChannelIndex,
+    /// No change or no data to report
+    NoChange,
+    /// Number of channels of the input does not match the configured number of channels
+    InvalidChannelCount,
+    /// Input channels have different numbers of frames
+    InvalidFrameCount,
+    /// Incompatible or inconsistent state, e.g. when merging differently configured instances,
+    /// deserializing the state of a different version or beginning a segment while another one is
+    /// measured
+    IncompatibleState,
+    /// No gating block was tagged as dialogue
+    NoDialogueBlocks,
+    /// Requested loudness window is longer than the retained audio
+    RequestedWindowTooLarge {
+        /// Longest window in ms that is currently available
+        available_ms: u64,
+        /// Requested window in ms
+        requested_ms: u64,
+    },
+    /// Requested value is outside of the supported range
+    OutOfRange,
+    /// A frame from an iterator does not have one sample per channel
+    InvalidFrameLength {
+        /// Number of frames before the invalid one, which were all added
+        frames_added: u64,
+    },
+    /// Reading or decoding the input failed
+    Io,
+    /// The input is malformed or its format is not supported
+    Format,
+    /// The input contains a NaN or infinite sample, see
+    /// [`InvalidSamplePolicy::Error`](enum.InvalidSamplePolicy.html#variant.Error)
+    InvalidSample {
+        /// Index of the frame within the input
+        frame_index: u64,
+        /// Channel of the sample
+        channel: u32,
+    },
 }
 
 impl error::Error for Error {}
 
+impl Error {
+    /// Make the frame index of `Error::InvalidSample` relative to `frames` earlier frames of the
+    /// same input, e.g. when adding the input in batches.
+    fn after_frames(self, frames: u64) -> Self {
+        match self {
+            Error::InvalidSample {
+                frame_index,
+                channel,
+            } => Error::InvalidSample {
+                frame_index: frames + frame_index,
+                channel,
+            },
+            err => err,
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Error::NoMem => write!(f, "NoMem"),
             Error::InvalidMode => write!(f, "Invalid Mode"),
             Error::InvalidChannelIndex => write!(f, "Invalid Channel Index"),
+            Error::NoChange => write!(f, "No Change"),
+            Error::InvalidChannelCount => write!(f, "Invalid Channel Count"),
+            Error::InvalidFrameCount => write!(f, "Invalid Frame Count"),
+            Error::IncompatibleState => write!(f, "Incompatible State"),
+            Error::NoDialogueBlocks => write!(f, "No Dialogue Blocks"),
+            Error::RequestedWindowTooLarge {
+                available_ms,
+                requested_ms,
+            } => write!(
+                f,
+                "Requested Window Too Large ({} ms requested, {} ms available)",
+                requested_ms, available_ms
+            ),
+            Error::OutOfRange => write!(f, "Out Of Range"),
+            Error::InvalidFrameLength { frames_added } => write!(
+                f,
+                "Invalid Frame Length (after {} valid frames)",
+                frames_added
+            ),
+            Error::Io => write!(f, "I/O Error"),
+            Error::Format => write!(f, "Invalid Format"),
+            Error::InvalidSample {
+                frame_index,
+                channel,
+            } => write!(
+                f,
+                "Invalid Sample (frame {}, channel {})",
+                frame_index, channel
+            ),
         }
     }
 }
@@ -55,7 +150,11 @@ bitflags! {
     ///
     /// Use these values in [`EbuR128::new`](struct.EbuR128.html#method.new). Try to use the lowest
     /// possible modes that suit your needs, as performance will be better.
+    ///
+    /// `Display` prints the names of all contained modes, e.g. `M | S | LRA` for `Mode::LRA`,
+    /// which includes `Mode::M` and `Mode::S`. This is the text format of `bitflags`.
     #[derive(Debug, Clone, Copy)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Mode: u8 {
         /// can call [`EbuR128::loudness_momentary`](struct.EbuR128.html#method.loudness_momentary)
         const M = 0b00000001;
@@ -71,16 +170,264 @@ bitflags! {
         /// can call [`EbuR128::true_peak`](struct.EbuR128.html#method.true_peak)
         const TRUE_PEAK = 0b00110001;
         /// uses histogram algorithm to calculate loudness
+        ///
+        /// The gating blocks are counted in fixed-size histogram bins instead of being stored
+        /// individually, so memory usage stays constant and processing frames does not allocate
+        /// memory after construction. This makes it suitable for embedded and real-time use, with
+        /// three exceptions: more than 16 channels together with `Mode::TRUE_PEAK` or
+        /// `Mode::CHANNEL_I` require temporary allocations, and so do true peak events and
+        /// buffers of 4096 or more frames that are processed in parallel with the `rayon`
+        /// feature.
         const HISTOGRAM = 0b01000000;
+        /// can call [`EbuR128::channel_loudness`](struct.EbuR128.html#method.channel_loudness)
+        const CHANNEL_I = 0b10000000 | Mode::I.bits();
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        bitflags::parser::to_writer(self, f)
+    }
+}
+
+/// Convert a number of frames to their duration at the given sample rate.
+fn frames_to_duration(frames: u64, rate: u32) -> Duration {
+    let rate = u64::from(rate);
+    Duration::from_secs(frames / rate)
+        + Duration::from_nanos((frames % rate) * 1_000_000_000 / rate)
+}
+
+/// Default number of frames between calls of the progress callback.
+const DEFAULT_PROGRESS_INTERVAL: u64 = 48_000;
+
+/// Size of the buffer on the stack for collecting samples from iterators.
+const ITER_BUFFER_SAMPLES: usize = 4096;
+
+/// Reference level of ReplayGain 2.0 in LUFS.
+const REPLAYGAIN_REFERENCE_LUFS: f64 = -18.0;
+
+/// Reference level of the Opus `R128_TRACK_GAIN` and `R128_ALBUM_GAIN` tags in LUFS.
+const OPUS_R128_REFERENCE_LUFS: f64 = -23.0;
+
+/// Get the ReplayGain 2.0 album gain in dB of multiple tracks.
+///
+/// This is the gain to reach -18 LUFS for the integrated loudness of all tracks together, as
+/// calculated by [`EbuR128::loudness_global_multiple`](struct.EbuR128.html#method.loudness_global_multiple).
+///
+/// Fails with `Error::InvalidMode` if `Mode::I` is not set for all tracks and with
+/// `Error::NoChange` if the input is silent.
+pub fn replaygain_album_gain(tracks: &[&EbuR128]) -> Result<f64, Error> {
+    let loudness = EbuR128::loudness_global_multiple(tracks.iter().copied())?;
+    if !loudness.is_finite() {
+        return Err(Error::NoChange);
+    }
+
+    Ok(REPLAYGAIN_REFERENCE_LUFS - loudness)
+}
+
+/// ReplayGain 2.0 track gain and peak.
+///
+/// Returned by [`EbuR128::replaygain_track_tags`](struct.EbuR128.html#method.replaygain_track_tags)
+/// with the values for the `REPLAYGAIN_TRACK_GAIN` and `REPLAYGAIN_TRACK_PEAK` tags.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplayGainTags {
+    /// Track gain in dB, see
+    /// [`EbuR128::replaygain_track_gain`](struct.EbuR128.html#method.replaygain_track_gain).
+    pub gain: f64,
+    /// Linear track peak, see
+    /// [`EbuR128::replaygain_track_peak`](struct.EbuR128.html#method.replaygain_track_peak).
+    pub peak: f64,
+}
+
+/// Statistics about the gating of the integrated loudness.
+///
+/// Returned by [`EbuR128::gating_block_stats`](struct.EbuR128.html#method.gating_block_stats). The
+/// counts saturate at `u64::MAX`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GatingStats {
+    /// Number of 400ms gating blocks measured since construction or the last reset.
+    pub blocks: u64,
+    /// Number of gating blocks above the absolute threshold of -70 LUFS.
+    pub blocks_above_absolute_threshold: u64,
+    /// Number of gating blocks above the relative threshold, i.e. the blocks
+    /// [`EbuR128::loudness_global`](struct.EbuR128.html#method.loudness_global) is currently
+    /// calculated from.
+    ///
+    /// Without `Mode::HISTOGRAM` only the blocks of the last
+    /// [`EbuR128::max_history`](struct.EbuR128.html#method.max_history) ms are considered.
+    pub blocks_above_relative_threshold: u64,
+    /// Relative threshold in LUFS.
+    pub relative_threshold: f64,
+}
+
+/// Memory used by an [`EbuR128`](struct.EbuR128.html) instance in bytes.
+///
+/// Returned by [`EbuR128::memory_usage`](struct.EbuR128.html#method.memory_usage).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryUsage {
+    /// Buffered audio of the maximum window.
+    pub audio_buffer: usize,
+    /// Histories of the gating blocks and the 3s blocks, including the per-channel, dialogue
+    /// and segment histories and the retained 100ms segment energies.
+    pub block_history: usize,
+    /// True peak interpolator state and true peak events if `Mode::TRUE_PEAK` is set.
+    pub true_peak: usize,
+    /// Everything else, including the instance itself and the filter states.
+    pub other: usize,
+}
+
+impl MemoryUsage {
+    /// Total number of bytes.
+    pub fn total(&self) -> usize {
+        self.audio_buffer + self.block_history + self.true_peak + self.other
+    }
+}
+
+/// Final values of a measurement.
+///
+/// Returned by [`EbuR128::summary`](struct.EbuR128.html#method.summary). Values that are not
+/// measured with the configured [`Mode`](struct.Mode.html) are `None`.
+///
+/// The `Display` implementation formats one value per line with one decimal place by default,
+/// e.g. `format!("{:.2}", summary)` gives two decimal places.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EbuR128Summary {
+    /// Integrated loudness in LUFS if `Mode::I` is set.
+    pub loudness_global: Option<f64>,
+    /// Loudness range in LU if `Mode::LRA` is set.
+    pub loudness_range: Option<f64>,
+    /// Maximum momentary loudness in LUFS if `Mode::M` is set and at least 400ms were processed.
+    pub loudness_momentary_max: Option<f64>,
+    /// Maximum short-term loudness in LUFS if `Mode::S` is set and at least 3s were processed.
+    pub loudness_shortterm_max: Option<f64>,
+    /// Maximum sample peak of all channels in dBFS if `Mode::SAMPLE_PEAK` is set.
+    pub sample_peak_max: Option<f64>,
+    /// Maximum true peak of all channels in dBTP if `Mode::TRUE_PEAK` is set.
+    pub true_peak_max: Option<f64>,
+}
+
+impl fmt::Display for EbuR128Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let precision = f.precision().unwrap_or(1);
+        let values = [
+            ("Integrated loudness", self.loudness_global, "LUFS"),
+            ("Loudness range", self.loudness_range, "LU"),
+            (
+                "Maximum momentary loudness",
+                self.loudness_momentary_max,
+                "LUFS",
+            ),
+            (
+                "Maximum short-term loudness",
+                self.loudness_shortterm_max,
+                "LUFS",
+            ),
+            ("Sample peak", self.sample_peak_max, "dBFS"),
+            ("True peak", self.true_peak_max, "dBTP"),
+        ];
+
+        let mut first = true;
+        for (name, value, unit) in values {
+            let value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+
+            write!(f, "{}: {:.*} {}", name, precision, value, unit)?;
+        }
+
+        Ok(())
     }
 }
 
+/// Frequency weighting applied before measuring the energy.
+///
+/// All measurements work the same with every weighting, but only `Weighting::K` gives loudness
+/// in LUFS according to ITU BS.1770. With the other weightings the values returned by e.g.
+/// [`EbuR128::loudness_global`](struct.EbuR128.html#method.loudness_global) are levels in dB with
+/// the same -0.691 dB offset, e.g. a full scale 1kHz sine measures -3.0 with `Weighting::K` and
+/// -3.7 with `Weighting::A` or `Weighting::None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Weighting {
+    /// K-weighting of ITU BS.1770: high shelving filter followed by the RLB high-pass filter.
+    #[default]
+    K,
+    /// A-weighting of IEC 61672-1, designed with the bilinear transform. Close to the Nyquist
+    /// frequency the attenuation is higher than specified, e.g. by 1.5 dB at 10kHz with a sample
+    /// rate of 44.1kHz.
+    A,
+    /// No weighting (Z-weighting), i.e. the plain RMS level.
+    None,
+}
+
+/// Handling of NaN and infinite input samples.
+///
+/// A single NaN sample makes the filter state NaN, so all following loudness values would be NaN
+/// as well. Integer samples are always valid.
+///
+/// Use these values with
+/// [`EbuR128::set_invalid_sample_policy`](struct.EbuR128.html#method.set_invalid_sample_policy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InvalidSamplePolicy {
+    /// Process all samples as they are, without checking them.
+    #[default]
+    Ignore,
+    /// Reject the input with `Error::InvalidSample` for the first invalid sample, without
+    /// processing any of its frames. Functions collecting frames from iterators in batches have
+    /// already added the batches before the one containing it.
+    Error,
+    /// Replace invalid samples with silence and count them, see
+    /// [`EbuR128::sanitized_samples`](struct.EbuR128.html#method.sanitized_samples).
+    ClampToZero,
+}
+
+/// Storage of the gating block histories.
+///
+/// Use these values with
+/// [`EbuR128::set_history_backend`](struct.EbuR128.html#method.set_history_backend).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HistoryBackend {
+    /// Store the energy of each block, 16 bytes per 100ms of audio, i.e. about 14MB per day
+    /// unless a maximum history is set. The individual blocks are available, e.g. via
+    /// [`EbuR128::gating_blocks_iter`](struct.EbuR128.html#method.gating_blocks_iter), and the
+    /// integrated loudness is not quantized.
+    Queue,
+    /// Count the blocks in histogram bins, like `Mode::HISTOGRAM`. With the default bins of 0.1 LU
+    /// this uses about 16kB per history regardless of the duration and is preferable for
+    /// measurements of unbounded duration that need the whole programme, e.g. continuous
+    /// monitoring. See
+    /// [`EbuR128::set_histogram_resolution`](struct.EbuR128.html#method.set_histogram_resolution)
+    /// for finer bins.
+    Histogram,
+}
+
 /// Channel position.
 ///
 /// Use these values when setting the channel map with
 /// [`EbuR128::set_channel`](struct.EbuR128.html#method.set_channel).
 /// See definitions in ITU R-REC-BS 1770-4 and ITU R-REC-BS 2051-2.
+///
+/// The `M` positions are in the middle layer, `U` in the upper (height) layer, `T` is top center
+/// and `B` the bottom layer, with the azimuth in degrees. This covers all loudspeakers of the
+/// BS.2051 layouts, e.g. the top front left / right of 7.1.4 are `Up045` / `Um045`, the top
+/// side channels of 22.2 are `Up090` / `Um090` and its bottom front center is `Bp000`.
+///
+/// The channels are weighted according to BS.1770-4: middle layer channels with an azimuth
+/// between 60 and 120 degrees (including `LeftSurround` and `RightSurround`) with +1.5 dB, all
+/// other channels, including the upper and bottom layers, with 0 dB.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum Channel {
     /// unused channel (for example LFE channel)
@@ -123,7 +470,7 @@ pub enum Channel {
     Um030,
     /// ITU U+045
     Up045,
-    /// ITU U-030
+    /// ITU U-045
     Um045,
     /// ITU U+090
     Up090,
@@ -147,9 +494,151 @@ pub enum Channel {
     Bp045,
     /// ITU B-045
     Bm045,
+    /// left channel of a dual-mono pair, see `DualMonoRight`
+    DualMonoLeft,
+    /// right channel of a dual-mono pair
+    ///
+    /// Both channels of the pair carry the same mono signal and are measured like a single
+    /// channel: their energies are averaged instead of summed. This avoids the +3 LU error of
+    /// measuring the pair as `Left` and `Right`, as required by EBU R128 for dual-mono content.
+    DualMonoRight,
+}
+
+impl Channel {
+    /// Channel map for the WAVE (`WAVEFORMATEXTENSIBLE`) channel order.
+    ///
+    /// Supports mono, stereo, 5.1 (`FL FR FC LFE BL BR`), 7.1 (`FL FR FC LFE BL BR SL SR`) and
+    /// 7.1.4 (7.1 followed by `TFL TFR TBL TBR`). The LFE channel is `Unused`.
+    ///
+    /// Fails with `Error::InvalidChannelCount` for other numbers of channels.
+    pub fn wave_order(channels: u32) -> Result<Vec<Channel>, Error> {
+        use Channel::*;
+
+        Ok(match channels {
+            1 => vec![Center],
+            2 => vec![Left, Right],
+            6 => vec![Left, Right, Center, Unused, LeftSurround, RightSurround],
+            8 => vec![Left, Right, Center, Unused, Mp135, Mm135, Mp090, Mm090],
+            12 => vec![
+                Left, Right, Center, Unused, Mp135, Mm135, Mp090, Mm090, Up045, Um045, Up135, Um135,
+            ],
+            _ => return Err(Error::InvalidChannelCount),
+        })
+    }
+
+    /// Channel map for the Vorbis I channel order, which is also used by Opus and FLAC.
+    ///
+    /// Supports mono, stereo, 5.1 (`FL C FR RL RR LFE`) and 7.1 (`FL C FR SL SR RL RR LFE`). The
+    /// LFE channel is `Unused`.
+    ///
+    /// Fails with `Error::InvalidChannelCount` for other numbers of channels.
+    pub fn vorbis_order(channels: u32) -> Result<Vec<Channel>, Error> {
+        use Channel::*;
+
+        Ok(match channels {
+            1 => vec![Center],
+            2 => vec![Left, Right],
+            6 => vec![Left, Center, Right, LeftSurround, RightSurround, Unused],
+            8 => vec![Left, Center, Right, Mp090, Mm090, Mp135, Mm135, Unused],
+            _ => return Err(Error::InvalidChannelCount),
+        })
+    }
+
+    /// Channel map for the SMPTE channel order.
+    ///
+    /// Supports mono, stereo, 5.1 (`L R C LFE Ls Rs`), 7.1 (`L R C LFE Lss Rss Lrs Rrs`) and
+    /// 7.1.4 (7.1 followed by `Ltf Rtf Ltr Rtr`). The LFE channel is `Unused`.
+    ///
+    /// Fails with `Error::InvalidChannelCount` for other numbers of channels.
+    pub fn smpte_order(channels: u32) -> Result<Vec<Channel>, Error> {
+        use Channel::*;
+
+        Ok(match channels {
+            1 => vec![Center],
+            2 => vec![Left, Right],
+            6 => vec![Left, Right, Center, Unused, LeftSurround, RightSurround],
+            8 => vec![Left, Right, Center, Unused, Mp090, Mm090, Mp135, Mm135],
+            12 => vec![
+                Left, Right, Center, Unused, Mp090, Mm090, Mp135, Mm135, Up045, Um045, Up135, Um135,
+            ],
+            _ => return Err(Error::InvalidChannelCount),
+        })
+    }
+}
+
+/// Sample formats that can be passed to [`EbuR128::add_frames`](struct.EbuR128.html#method.add_frames)
+/// and [`EbuR128::add_frames_planar`](struct.EbuR128.html#method.add_frames_planar).
+///
+/// Implemented for `i16`, `i32`, `f32` and `f64`. The full range of the integer formats is scaled
+/// to ±1.0. This trait is sealed and can't be implemented outside this crate, but it can be used
+/// as a bound, e.g. by decoders that are generic over the sample format.
+pub trait Sample: crate::utils::Sample {}
+
+impl Sample for i16 {}
+impl Sample for i32 {}
+impl Sample for f32 {}
+impl Sample for f64 {}
+
+/// Sample format of raw bytes passed to
+/// [`EbuR128::add_frames_bytes`](struct.EbuR128.html#method.add_frames_bytes).
+///
+/// The integer formats are scaled from their full range to ±1.0, the same as e.g. with
+/// [`EbuR128::add_frames_i16`](struct.EbuR128.html#method.add_frames_i16).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SampleFormat {
+    /// Signed 16 bit little-endian.
+    S16Le,
+    /// Signed 16 bit big-endian.
+    S16Be,
+    /// Signed 24 bit little-endian, packed into 3 bytes.
+    S24Le,
+    /// Signed 24 bit big-endian, packed into 3 bytes.
+    S24Be,
+    /// Signed 32 bit little-endian.
+    S32Le,
+    /// Signed 32 bit big-endian.
+    S32Be,
+    /// 32 bit float little-endian.
+    F32Le,
+    /// 32 bit float big-endian.
+    F32Be,
+    /// 64 bit float little-endian.
+    F64Le,
+    /// 64 bit float big-endian.
+    F64Be,
+}
+
+impl SampleFormat {
+    /// Number of bytes per sample.
+    pub fn bytes_per_sample(self) -> usize {
+        match self {
+            SampleFormat::S16Le | SampleFormat::S16Be => 2,
+            SampleFormat::S24Le | SampleFormat::S24Be => 3,
+            SampleFormat::S32Le
+            | SampleFormat::S32Be
+            | SampleFormat::F32Le
+            | SampleFormat::F32Be => 4,
+            SampleFormat::F64Le | SampleFormat::F64Be => 8,
+        }
+    }
 }
 
 /// EBU R128 loudness analyzer.
+///
+/// The analyzer is `Send` and `Sync`, so it can be moved to and shared with other threads.
+/// Synchronizing access to it, e.g. with a `Mutex`, is up to the caller.
+///
+/// With the `serde` feature enabled the complete analyzer state can be serialized and
+/// deserialized, e.g. to pause a measurement and resume it later or to transfer it to another
+/// machine. Deserializing fails with
+/// [`Error::IncompatibleState`](enum.Error.html#variant.IncompatibleState) as error message if the
+/// state was written by an incompatible version or is inconsistent.
+///
+/// The serialized format is versioned and only changes in releases that are semver-incompatible.
+/// A state serialized by one release can always be deserialized by all releases that only differ
+/// in the patch version.
+#[derive(Clone)]
 pub struct EbuR128 {
     /// The current mode.
     mode: Mode,
@@ -170,6 +659,9 @@ pub struct EbuR128 {
 
     /// The channel map. Has as many elements as there are channels.
     channel_map: Box<[Channel]>,
+    /// Custom energy weights overriding the ones of the channel types. Has as many elements as
+    /// there are channels.
+    channel_weights: Box<[Option<f64>]>,
 
     /// How many samples fit in 100ms (rounded).
     samples_in_100ms: usize,
@@ -180,6 +672,9 @@ pub struct EbuR128 {
     /// Block energy history.
     block_energy_history: crate::history::History,
 
+    /// Per-channel block energy history if enabled.
+    channel_energy_history: Option<crate::history::ChannelHistory>,
+
     /// Short term block energy history.
     short_term_block_energy_history: crate::history::History,
     short_term_frame_counter: usize,
@@ -193,6 +688,100 @@ pub struct EbuR128 {
     /// The maximum window duration in ms.
     window: usize,
     history: usize,
+
+    /// Number of frames processed since construction or the last reset.
+    frames_processed: u64,
+
+    /// Number of gating blocks measured and the number of them above the absolute threshold.
+    gating_blocks: u64,
+    gating_blocks_above_absolute_threshold: u64,
+
+    /// Energies of the last up to 30 completed 100ms segments.
+    segment_energies: VecDeque<f64>,
+    /// Energies of the completed 100ms segments within the maximum window, or the maximum history
+    /// if it was set explicitly, for the loudness histories and windows longer than the audio
+    /// buffer.
+    window_energies: VecDeque<f64>,
+    /// Number of segments that were dropped from the front of `window_energies`.
+    window_energies_dropped: u64,
+    /// Maximum momentary energy, `None` until the first momentary block was completed.
+    momentary_max: Option<f64>,
+    /// Maximum short-term energy, `None` until the first short-term block was completed.
+    shortterm_max: Option<f64>,
+
+    /// Whether `flush()` was called.
+    flushed: bool,
+
+    /// Dialogue gate if enabled.
+    dialogue_gate: Option<DialogueGate>,
+
+    /// Measurement of the segment started with `begin_segment()`, if any.
+    segment_measurement: Option<Box<SegmentMeasurement>>,
+
+    /// Progress callback if set.
+    progress_callback: Option<Arc<dyn Fn(u64, u64) + Send + Sync>>,
+    /// Number of frames between calls of the progress callback.
+    progress_interval: u64,
+    /// Expected total number of frames passed to the progress callback, or 0 if unknown.
+    total_frames_hint: u64,
+
+    /// Interleaved samples passed via `Extend` that don't form a complete frame yet.
+    pending_samples: Vec<f64>,
+    /// First error that happened while processing samples passed via `Extend`.
+    last_error: Option<Error>,
+
+    /// Handling of NaN and infinite input samples.
+    invalid_sample_policy: InvalidSamplePolicy,
+    /// Number of invalid samples replaced with silence.
+    sanitized_samples: u64,
+
+    /// Number of histogram bins per LU.
+    histogram_resolution: u32,
+}
+
+/// Callback deciding which gating blocks contain dialogue and the history of these blocks.
+#[derive(Clone)]
+struct DialogueGate {
+    callback: Arc<dyn Fn(u64) -> bool + Send + Sync>,
+    block_energy_history: crate::history::History,
+}
+
+/// Measurement of a segment of the programme, in addition to the whole programme.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct SegmentMeasurement {
+    /// Index of the first 100ms segment that starts within the measured segment.
+    first_segment: u64,
+    /// Number of frames processed before the measured segment.
+    start_frame: u64,
+    block_energy_history: crate::history::History,
+    short_term_block_energy_history: crate::history::History,
+    sample_peak: Box<[f64]>,
+    true_peak: Box<[f64]>,
+    /// Number of frames of the segment whose interpolated samples still belong to the audio
+    /// before it because of the delay of the interpolator.
+    true_peak_delay_frames: usize,
+    momentary_max: Option<f64>,
+    shortterm_max: Option<f64>,
+}
+
+/// Measured values compared by the `PartialEq` implementation of `EbuR128`.
+#[derive(PartialEq)]
+struct MeasurementSnapshot {
+    loudness_global: Result<f64, Error>,
+    loudness_range: Result<f64, Error>,
+    true_peak: Vec<Result<f64, Error>>,
+    sample_peak: Vec<Result<f64, Error>>,
+    frames_processed: u64,
+}
+
+impl fmt::Debug for DialogueGate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DialogueGate")
+            // Not callback
+            .field("block_energy_history", &self.block_energy_history)
+            .finish()
+    }
 }
 
 impl fmt::Debug for EbuR128 {
@@ -205,9 +794,11 @@ impl fmt::Debug for EbuR128 {
             .field("audio_data_index", &self.audio_data_index)
             .field("needed_frames", &self.needed_frames)
             .field("channel_map", &self.channel_map)
+            .field("channel_weights", &self.channel_weights)
             .field("samples_in_100ms", &self.samples_in_100ms)
             .field("filter", &self.filter)
             .field("block_energy_history", &self.block_energy_history)
+            .field("channel_energy_history", &self.channel_energy_history)
             .field(
                 "short_term_block_energy_history",
                 &self.short_term_block_energy_history,
@@ -217,10 +808,49 @@ impl fmt::Debug for EbuR128 {
             .field("true_peak", &self.true_peak)
             .field("window", &self.window)
             .field("history", &self.history)
+            .field("frames_processed", &self.frames_processed)
+            .field("gating_blocks", &self.gating_blocks)
+            .field(
+                "gating_blocks_above_absolute_threshold",
+                &self.gating_blocks_above_absolute_threshold,
+            )
+            .field("dialogue_gate", &self.dialogue_gate)
+            .field("segment_measurement", &self.segment_measurement)
+            // Not progress callback
+            .field("progress_interval", &self.progress_interval)
+            .field("total_frames_hint", &self.total_frames_hint)
+            .field("pending_samples", &self.pending_samples)
+            .field("last_error", &self.last_error)
+            .field("invalid_sample_policy", &self.invalid_sample_policy)
+            .field("sanitized_samples", &self.sanitized_samples)
+            .field("histogram_resolution", &self.histogram_resolution)
+            .field("segment_energies", &self.segment_energies)
+            .field("window_energies", &self.window_energies)
+            .field("window_energies_dropped", &self.window_energies_dropped)
+            .field("momentary_max", &self.momentary_max)
+            .field("shortterm_max", &self.shortterm_max)
+            .field("flushed", &self.flushed)
             .finish()
     }
 }
 
+/// Checks that `Channel::DualMono` is only used for the only channel of a mono instance and that
+/// the channels contain either none or exactly one of both members of a dual-mono pair.
+fn validate_channel_map(channel_map: impl Iterator<Item = Channel> + Clone) -> Result<(), Error> {
+    let count = |value| channel_map.clone().filter(|c| *c == value).count();
+
+    if count(Channel::DualMono) > 0 && channel_map.clone().count() != 1 {
+        return Err(Error::InvalidChannelIndex);
+    }
+
+    let left = count(Channel::DualMonoLeft);
+    if left > 1 || left != count(Channel::DualMonoRight) {
+        return Err(Error::InvalidChannelIndex);
+    }
+
+    Ok(())
+}
+
 pub(crate) fn default_channel_map(channels: u32) -> Vec<Channel> {
     match channels {
         4 => vec![
@@ -239,7 +869,7 @@ pub(crate) fn default_channel_map(channels: u32) -> Vec<Channel> {
         _ => {
             let mut v = vec![Channel::Unused; channels as usize];
 
-            let set_channels = std::cmp::min(channels as usize, 6);
+            let set_channels = core::cmp::min(channels as usize, 6);
             v[0..set_channels].copy_from_slice(
                 &[
                     Channel::Left,
@@ -256,8 +886,8 @@ pub(crate) fn default_channel_map(channels: u32) -> Vec<Channel> {
     }
 }
 
-const MAX_RATE: u32 = 2822400;
-const MAX_CHANNELS: u32 = 64;
+pub(crate) const MAX_RATE: u32 = 2822400;
+pub(crate) const MAX_CHANNELS: u32 = 64;
 
 impl EbuR128 {
     /// Allocate audio data buffer used by the filter and check if we can allocate enough memory
@@ -273,6 +903,10 @@ impl EbuR128 {
                 .ok_or(Error::NoMem)?
                 - (audio_data_frames % samples_in_100ms);
         }
+        // At very low sample rates the rounding of samples_in_100ms could otherwise leave too
+        // little space for the 100ms segments of the window
+        audio_data_frames =
+            core::cmp::max(audio_data_frames, window.div_ceil(100) * samples_in_100ms);
 
         let audio_data = vec![
             0.0;
@@ -286,7 +920,32 @@ impl EbuR128 {
     }
 
     /// Create a new instance with the given configuration.
+    ///
+    /// Use [`EbuR128Builder`](struct.EbuR128Builder.html) to also configure the channel map, the
+    /// maximum window and the maximum history at construction time.
+    ///
+    /// All modes support 1 to 64 channels, e.g. 22.2 or higher order Ambisonics. Channel counts
+    /// without a specialized true peak implementation are interpolated per channel. Fails with
+    /// `Error::NoMem` for other channel counts and for sample rates outside of 16Hz to 2.8224MHz.
+    ///
+    /// The filter coefficients are calculated for the exact sample rate, so also uncommon rates
+    /// like 11025Hz or 22050Hz are supported. Due to the frequency warping of the filter design the
+    /// loudness of a 1kHz sine deviates by up to 0.04 LU between 8kHz and 192kHz, and more at
+    /// lower sample rates where the K-weighting response is cut off by the Nyquist frequency.
+    ///
+    /// This is a shortcut for building an instance with
+    /// [`EbuR128Builder`](struct.EbuR128Builder.html) and the default maximum window and history.
     pub fn new(channels: u32, rate: u32, mode: Mode) -> Result<Self, Error> {
+        EbuR128Builder::new()
+            .channels(channels)
+            .rate(rate)
+            .mode(mode)
+            .build()
+    }
+
+    /// Create a new instance with the default configuration for the given channels, sample rate
+    /// and mode.
+    fn with_defaults(channels: u32, rate: u32, mode: Mode) -> Result<Self, Error> {
         if channels == 0 || channels > MAX_CHANNELS {
             return Err(Error::NoMem);
         }
@@ -313,16 +972,34 @@ impl EbuR128 {
         // start at the beginning of the buffer
         let audio_data_index = 0;
 
-        let block_energy_history =
-            crate::history::History::new(mode.contains(Mode::HISTOGRAM), history / 100);
+        let block_energy_history = crate::history::History::new(
+            mode.contains(Mode::HISTOGRAM),
+            crate::history::DEFAULT_HISTOGRAM_RESOLUTION,
+            history / 100,
+        );
+
+        let channel_energy_history = if mode.contains(Mode::CHANNEL_I) {
+            Some(crate::history::ChannelHistory::new(
+                mode.contains(Mode::HISTOGRAM),
+                crate::history::DEFAULT_HISTOGRAM_RESOLUTION,
+                channels as usize,
+                history / 100,
+            ))
+        } else {
+            None
+        };
 
-        let short_term_block_energy_history =
-            crate::history::History::new(mode.contains(Mode::HISTOGRAM), history / 3000);
+        let short_term_block_energy_history = crate::history::History::new(
+            mode.contains(Mode::HISTOGRAM),
+            crate::history::DEFAULT_HISTOGRAM_RESOLUTION,
+            history / 3000,
+        );
         let short_term_frame_counter = 0;
 
         let filter = crate::filter::Filter::new(
             rate,
             channels,
+            Weighting::K,
             mode.contains(Mode::SAMPLE_PEAK),
             mode.contains(Mode::TRUE_PEAK),
         );
@@ -340,18 +1017,48 @@ impl EbuR128 {
             audio_data_index,
             needed_frames,
             channel_map: channel_map.into_boxed_slice(),
+            channel_weights: vec![None; channels as usize].into_boxed_slice(),
             samples_in_100ms,
             filter,
             block_energy_history,
+            channel_energy_history,
             short_term_block_energy_history,
             short_term_frame_counter,
             sample_peak: sample_peak.into_boxed_slice(),
             true_peak: true_peak.into_boxed_slice(),
             window,
             history,
+            frames_processed: 0,
+            gating_blocks: 0,
+            gating_blocks_above_absolute_threshold: 0,
+            segment_energies: VecDeque::with_capacity(30),
+            window_energies: if mode.contains(Mode::HISTOGRAM | Mode::M) {
+                VecDeque::with_capacity(window / 100)
+            } else {
+                VecDeque::new()
+            },
+            window_energies_dropped: 0,
+            momentary_max: None,
+            shortterm_max: None,
+            flushed: false,
+            dialogue_gate: None,
+            segment_measurement: None,
+            progress_callback: None,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
+            total_frames_hint: 0,
+            pending_samples: Vec::new(),
+            last_error: None,
+            invalid_sample_policy: InvalidSamplePolicy::Ignore,
+            sanitized_samples: 0,
+            histogram_resolution: crate::history::DEFAULT_HISTOGRAM_RESOLUTION,
         })
     }
 
+    /// Create a new [`EbuR128Builder`](struct.EbuR128Builder.html) for configuring an instance.
+    pub fn builder() -> EbuR128Builder {
+        EbuR128Builder::new()
+    }
+
     /// Get the configured mode.
     pub fn mode(&self) -> Mode {
         self.mode
@@ -367,11 +1074,86 @@ impl EbuR128 {
         self.rate
     }
 
+    /// Get the number of frames added since construction or the last reset.
+    ///
+    /// Only frames that were actually processed are counted. The `add_frames` functions taking
+    /// slices validate their input before processing anything, so a call that fails leaves this
+    /// unchanged.
+    /// Merging another instance adds its frames and
+    /// [`EbuR128::reset_peaks`](struct.EbuR128.html#method.reset_peaks) keeps the count.
+    pub fn frames_processed(&self) -> u64 {
+        self.frames_processed
+    }
+
+    /// Get the duration of the frames added since construction or the last reset, see
+    /// [`EbuR128::frames_processed`](struct.EbuR128.html#method.frames_processed).
+    ///
+    /// This is calculated with the current sample rate, also for frames added before
+    /// [`EbuR128::change_parameters`](struct.EbuR128.html#method.change_parameters) changed it.
+    pub fn duration_processed(&self) -> Duration {
+        frames_to_duration(self.frames_processed, self.rate)
+    }
+
+    /// Get the number of samples of all channels added since construction or the last reset,
+    /// see [`EbuR128::frames_processed`](struct.EbuR128.html#method.frames_processed).
+    pub fn samples_processed(&self) -> u64 {
+        self.frames_processed * u64::from(self.channels)
+    }
+
     /// Get the configured channel types.
     pub fn channel_map(&self) -> &[Channel] {
         &self.channel_map
     }
 
+    /// Get the energy weight of a channel.
+    ///
+    /// This is the custom weight set with
+    /// [`EbuR128::set_channel_weight`](struct.EbuR128.html#method.set_channel_weight) or otherwise
+    /// the weight of the channel type, e.g. 1.41 for `LeftSurround`. `Unused` channels have a
+    /// weight of 0.0.
+    ///
+    /// Fails with `Error::InvalidChannelIndex` if the channel doesn't exist.
+    pub fn channel_weight(&self, channel_number: u32) -> Result<f64, Error> {
+        if channel_number >= self.channels {
+            return Err(Error::InvalidChannelIndex);
+        }
+
+        let channel = self.channel_map[channel_number as usize];
+        if channel == Channel::Unused {
+            return Ok(0.0);
+        }
+
+        Ok(self.channel_weights[channel_number as usize]
+            .unwrap_or_else(|| crate::filter::channel_weight(channel)))
+    }
+
+    /// Set a custom energy weight for a channel, or `None` to use the weight of the channel type.
+    ///
+    /// When summing up the channels, the energy of the channel is multiplied by `weight` instead
+    /// of the weight ITU BS.1770 defines for the channel type, e.g. for object-based or Ambisonics
+    /// content. A weight of 0.0 excludes the channel from the measurement. Channels of type
+    /// `Unused` are not measured regardless of the weight. Like the channel map, the weight
+    /// applies to all blocks completed afterwards, and it is kept when changing the channel type.
+    ///
+    /// Fails with `Error::InvalidChannelIndex` if the channel doesn't exist and with
+    /// `Error::OutOfRange` if `weight` is negative or not finite.
+    pub fn set_channel_weight(
+        &mut self,
+        channel_number: u32,
+        weight: Option<f64>,
+    ) -> Result<(), Error> {
+        if channel_number >= self.channels {
+            return Err(Error::InvalidChannelIndex);
+        }
+
+        if weight.is_some_and(|weight| !(weight >= 0.0 && weight.is_finite())) {
+            return Err(Error::OutOfRange);
+        }
+
+        self.channel_weights[channel_number as usize] = weight;
+        Ok(())
+    }
+
     /// Get the configured maximum window duration in ms.
     pub fn max_window(&self) -> usize {
         self.window
@@ -382,6 +1164,200 @@ impl EbuR128 {
         self.history
     }
 
+    /// Get the backend used for the gating block histories.
+    pub fn history_backend(&self) -> HistoryBackend {
+        if self.mode.contains(Mode::HISTOGRAM) {
+            HistoryBackend::Histogram
+        } else {
+            HistoryBackend::Queue
+        }
+    }
+
+    /// Select the backend for the gating block histories, see [`HistoryBackend`].
+    ///
+    /// Switching from `HistoryBackend::Queue` to `HistoryBackend::Histogram` is possible at any
+    /// time: the stored blocks are sorted into the histogram bins and all following measurements
+    /// are the same as if the histogram had been used from the start, except that only the
+    /// blocks retained within the maximum history are converted, see
+    /// [`EbuR128::set_max_history`](struct.EbuR128.html#method.set_max_history). The integrated
+    /// loudness and loudness range change by up to the quantization of the histogram bins.
+    ///
+    /// The individual blocks can't be recovered from a histogram, so switching to
+    /// `HistoryBackend::Queue` has to be done before any frames are added, like
+    /// [`EbuR128::set_weighting`](struct.EbuR128.html#method.set_weighting), and fails with
+    /// `Error::InvalidMode` otherwise.
+    pub fn set_history_backend(&mut self, backend: HistoryBackend) -> Result<(), Error> {
+        if backend == self.history_backend() {
+            return Ok(());
+        }
+
+        match backend {
+            HistoryBackend::Histogram => {
+                let resolution = self.histogram_resolution;
+                self.block_energy_history.convert_to_histogram(resolution);
+                if let Some(ref mut channel_energy_history) = self.channel_energy_history {
+                    channel_energy_history.convert_to_histogram(resolution);
+                }
+                self.short_term_block_energy_history
+                    .convert_to_histogram(resolution);
+                if let Some(ref mut dialogue_gate) = self.dialogue_gate {
+                    dialogue_gate
+                        .block_energy_history
+                        .convert_to_histogram(resolution);
+                }
+                if let Some(ref mut segment) = self.segment_measurement {
+                    segment
+                        .block_energy_history
+                        .convert_to_histogram(resolution);
+                    segment
+                        .short_term_block_energy_history
+                        .convert_to_histogram(resolution);
+                }
+                self.mode.insert(Mode::HISTOGRAM);
+            }
+            HistoryBackend::Queue => {
+                if self.frames_processed != 0 {
+                    return Err(Error::InvalidMode);
+                }
+
+                self.mode.remove(Mode::HISTOGRAM);
+                self.recreate_histories();
+            }
+        }
+        self.resize_window_energies();
+
+        Ok(())
+    }
+
+    /// Get the number of histogram bins per LU.
+    pub fn histogram_resolution(&self) -> u32 {
+        self.histogram_resolution
+    }
+
+    /// Set the number of histogram bins per LU, from 1 to 100. The default is 10, i.e. bins of
+    /// 0.1 LU.
+    ///
+    /// With `HistoryBackend::Histogram` the integrated loudness and loudness range are quantized to
+    /// the bins, which can add up to a few tenths of an LU to the loudness range compared to
+    /// `HistoryBackend::Queue`. Finer bins reduce this error, while the memory of each histogram
+    /// grows linearly with the resolution, e.g. to about 240kB per history with 100 bins per LU,
+    /// see [`EbuR128::memory_usage`](struct.EbuR128.html#method.memory_usage). Queues are sorted
+    /// into bins of this resolution for
+    /// [`EbuR128::block_energy_histogram`](struct.EbuR128.html#method.block_energy_histogram) and
+    /// when switching to `HistoryBackend::Histogram`.
+    ///
+    /// This has to be done before any frames are added, like
+    /// [`EbuR128::set_weighting`](struct.EbuR128.html#method.set_weighting). Fails with
+    /// `Error::OutOfRange` for an unsupported resolution and with `Error::InvalidMode` if frames
+    /// were already processed.
+    pub fn set_histogram_resolution(&mut self, bins_per_lu: u32) -> Result<(), Error> {
+        if !(crate::history::HISTOGRAM_RESOLUTION_RANGE.0
+            ..=crate::history::HISTOGRAM_RESOLUTION_RANGE.1)
+            .contains(&bins_per_lu)
+        {
+            return Err(Error::OutOfRange);
+        }
+
+        if bins_per_lu == self.histogram_resolution {
+            return Ok(());
+        }
+
+        if self.frames_processed != 0 {
+            return Err(Error::InvalidMode);
+        }
+
+        self.histogram_resolution = bins_per_lu;
+        self.recreate_histories();
+
+        Ok(())
+    }
+
+    /// Replace all gating block histories with empty ones for the current mode and histogram
+    /// resolution.
+    fn recreate_histories(&mut self) {
+        use crate::history::{ChannelHistory, History};
+
+        let use_histogram = self.mode.contains(Mode::HISTOGRAM);
+        let resolution = self.histogram_resolution;
+
+        self.block_energy_history = History::new(use_histogram, resolution, self.history / 100);
+        if self.channel_energy_history.is_some() {
+            self.channel_energy_history = Some(ChannelHistory::new(
+                use_histogram,
+                resolution,
+                self.channels as usize,
+                self.history / 100,
+            ));
+        }
+        self.short_term_block_energy_history =
+            History::new(use_histogram, resolution, self.history / 3000);
+        if let Some(ref mut dialogue_gate) = self.dialogue_gate {
+            dialogue_gate.block_energy_history =
+                History::new(use_histogram, resolution, self.history / 100);
+        }
+        if let Some(ref mut segment) = self.segment_measurement {
+            segment.block_energy_history =
+                History::new(use_histogram, resolution, self.history / 100);
+            segment.short_term_block_energy_history =
+                History::new(use_histogram, resolution, self.history / 3000);
+        }
+    }
+
+    /// Get the memory used by this instance in bytes.
+    ///
+    /// This includes all allocated but unused capacity. With `Mode::HISTOGRAM` and without
+    /// true peak events the memory usage doesn't change while processing frames, except for the
+    /// retained 100ms segment energies if a maximum history is set. Without `Mode::HISTOGRAM`
+    /// the block histories grow until the maximum history is reached, see
+    /// [`EbuR128::set_max_history`](struct.EbuR128.html#method.set_max_history).
+    pub fn memory_usage(&self) -> MemoryUsage {
+        use core::mem::size_of;
+
+        let f64_size = size_of::<f64>();
+
+        let block_history = self.block_energy_history.heap_size()
+            + self
+                .channel_energy_history
+                .as_ref()
+                .map_or(0, |h| h.heap_size())
+            + self.short_term_block_energy_history.heap_size()
+            + self
+                .dialogue_gate
+                .as_ref()
+                .map_or(0, |g| g.block_energy_history.heap_size())
+            + self.segment_measurement.as_ref().map_or(0, |segment| {
+                size_of::<SegmentMeasurement>()
+                    + segment.block_energy_history.heap_size()
+                    + segment.short_term_block_energy_history.heap_size()
+                    + (segment.sample_peak.len() + segment.true_peak.len()) * f64_size
+            })
+            + (self.segment_energies.capacity() + self.window_energies.capacity()) * f64_size;
+
+        let true_peak = self
+            .filter
+            .true_peak_analyzer()
+            .map_or(0, crate::TruePeak::memory_usage);
+        // The analyzer itself is part of the instance
+        let true_peak_inline = self
+            .filter
+            .true_peak_analyzer()
+            .map_or(0, |_| size_of::<crate::TruePeak>());
+
+        let other = size_of::<Self>() - true_peak_inline
+            + self.filter.heap_size()
+            + self.channel_map.len() * size_of::<Channel>()
+            + self.channel_weights.len() * size_of::<Option<f64>>()
+            + (self.sample_peak.len() + self.true_peak.len()) * f64_size
+            + self.pending_samples.capacity() * f64_size;
+
+        MemoryUsage {
+            audio_buffer: self.audio_data.len() * f64_size,
+            block_history,
+            true_peak,
+            other,
+        }
+    }
+
     /// Set channel type.
     ///
     /// The default is:
@@ -393,19 +1369,46 @@ impl EbuR128 {
     /// * 4 \-> `LeftSurround`
     /// * 5 \-> `RightSurround`
     /// * _ \-> `Unused`
+    ///
+    /// The channel type can be changed at any time, e.g. to reuse an instance for a different
+    /// channel layout, and its weight applies to all gating blocks completed afterwards. If the
+    /// type changes, the sample and true peak of the channel are reset, as they belong to the
+    /// previous signal of the channel. The loudness history is kept, use
+    /// [`EbuR128::reset`](struct.EbuR128.html#method.reset) to start over.
+    ///
+    /// Fails with `Error::InvalidChannelIndex` if `channel_number` is not a valid channel, if
+    /// `Channel::DualMono` is set for anything but the only channel of a mono instance or if the
+    /// channel would become an unpaired or duplicated member of a dual-mono pair. Both channels of
+    /// a pair have to be set at once with
+    /// [`EbuR128::set_channel_map`](struct.EbuR128.html#method.set_channel_map).
     pub fn set_channel(&mut self, channel_number: u32, value: Channel) -> Result<(), Error> {
         if channel_number >= self.channels {
             return Err(Error::InvalidChannelIndex);
         }
 
-        if value == Channel::DualMono && (self.channels != 1 || channel_number != 0) {
-            return Err(Error::InvalidChannelIndex);
-        }
+        validate_channel_map(self.channel_map.iter().enumerate().map(|(c, channel)| {
+            if c == channel_number as usize {
+                value
+            } else {
+                *channel
+            }
+        }))?;
+
+        self.change_channel(channel_number as usize, value);
 
-        self.channel_map[channel_number as usize] = value;
         Ok(())
     }
 
+    /// Changes the type of channel `c` and resets its peaks if it differs.
+    fn change_channel(&mut self, c: usize, value: Channel) {
+        if self.channel_map[c] != value {
+            self.channel_map[c] = value;
+            self.sample_peak[c] = 0.0;
+            self.true_peak[c] = 0.0;
+            self.filter.reset_channel_peaks(c);
+        }
+    }
+
     /// Set channel types.
     ///
     /// The default is:
@@ -417,25 +1420,52 @@ impl EbuR128 {
     /// * 4 \-> `LeftSurround`
     /// * 5 \-> `RightSurround`
     /// * _ \-> `Unused`
+    ///
+    /// Maps for standard channel orders are available via
+    /// [`Channel::wave_order`](enum.Channel.html#method.wave_order),
+    /// [`Channel::vorbis_order`](enum.Channel.html#method.vorbis_order) and
+    /// [`Channel::smpte_order`](enum.Channel.html#method.smpte_order). Like with
+    /// [`EbuR128::set_channel`](struct.EbuR128.html#method.set_channel), the map can be changed at
+    /// any time and applies to all gating blocks completed afterwards, and the peaks of the
+    /// channels whose type changes are reset.
+    ///
+    /// Fails with `Error::InvalidChannelIndex` if the map doesn't have one element per channel,
+    /// if it contains `Channel::DualMono` for anything but the only channel of a mono instance or
+    /// if it doesn't contain either none or exactly one of both `Channel::DualMonoLeft` and
+    /// `Channel::DualMonoRight`.
     pub fn set_channel_map(&mut self, channel_map: &[Channel]) -> Result<(), Error> {
         if channel_map.len() != self.channels as usize {
             return Err(Error::InvalidChannelIndex);
         }
 
-        for (channel_number, value) in channel_map.iter().enumerate() {
-            if *value == Channel::DualMono && (self.channels != 1 || channel_number != 0) {
-                return Err(Error::InvalidChannelIndex);
-            }
+        validate_channel_map(channel_map.iter().copied())?;
+
+        for (c, value) in channel_map.iter().enumerate() {
+            self.change_channel(c, *value);
         }
 
-        self.channel_map.copy_from_slice(channel_map);
         Ok(())
     }
 
     /// Change library parameters.
     ///
-    /// Note that the channel map will be reset when setting a different number of channels. The
-    /// current unfinished block will be lost.
+    /// Nothing happens if both are unchanged. Otherwise the measurement continues with the new
+    /// parameters, so the integrated loudness and the loudness range cover the audio before and
+    /// after the change exactly like
+    /// [`EbuR128::loudness_global_multiple`](struct.EbuR128.html#method.loudness_global_multiple)
+    /// of two separate instances would. The completed gating blocks are already summed over the
+    /// channels and are kept, together with the dialogue gate history, the maximum momentary and
+    /// short-term loudness and the number of processed frames.
+    ///
+    /// The audio state doesn't fit the new parameters and is reset: the filter and true peak
+    /// interpolator states, the true peak events, the current unfinished block, the buffered audio
+    /// and the 100ms segment energies for loudness windows and the momentary and short-term
+    /// loudness, the samples of an incomplete frame passed via `Extend` and a segment begun with
+    /// [`EbuR128::begin_segment`](struct.EbuR128.html#method.begin_segment). The true peak
+    /// oversampling and event threshold are kept.
+    ///
+    /// When setting a different number of channels, the channel map, the channel weights, the
+    /// sample and true peak maxima and the per-channel loudness history are reset as well.
     pub fn change_parameters(&mut self, channels: u32, rate: u32) -> Result<(), Error> {
         if channels == 0 || channels > MAX_CHANNELS {
             return Err(Error::NoMem);
@@ -454,8 +1484,17 @@ impl EbuR128 {
         if self.channels != channels {
             self.channels = channels;
             self.channel_map = default_channel_map(channels).into_boxed_slice();
+            self.channel_weights = vec![None; channels as usize].into_boxed_slice();
             self.sample_peak = vec![0.0; channels as usize].into_boxed_slice();
             self.true_peak = vec![0.0; channels as usize].into_boxed_slice();
+            if self.channel_energy_history.is_some() {
+                self.channel_energy_history = Some(crate::history::ChannelHistory::new(
+                    self.mode.contains(Mode::HISTOGRAM),
+                    self.histogram_resolution,
+                    channels as usize,
+                    self.history / 100,
+                ));
+            }
         }
 
         if self.rate != rate {
@@ -463,12 +1502,20 @@ impl EbuR128 {
             self.samples_in_100ms = (rate as usize + 5) / 10;
         }
 
-        self.filter = crate::filter::Filter::new(
+        let filter = crate::filter::Filter::new(
             rate,
             channels,
+            self.filter.weighting(),
             self.mode.contains(Mode::SAMPLE_PEAK),
             self.mode.contains(Mode::TRUE_PEAK),
         );
+        let old_filter = core::mem::replace(&mut self.filter, filter);
+        if let (Some(tp), Some(old_tp)) = (
+            self.filter.true_peak_analyzer_mut(),
+            old_filter.true_peak_analyzer(),
+        ) {
+            tp.copy_settings(old_tp);
+        }
 
         // the first block needs 400ms of audio data
         self.needed_frames = self.samples_in_100ms * 4;
@@ -476,42 +1523,177 @@ impl EbuR128 {
         self.audio_data_index = 0;
         // reset short term frame counter
         self.short_term_frame_counter = 0;
+        // the previous segments are not in the audio buffer anymore
+        self.segment_energies.clear();
+        // and don't belong to the new configuration
+        self.window_energies.clear();
+        self.window_energies_dropped = 0;
+        self.pending_samples.clear();
+        self.segment_measurement = None;
 
         Ok(())
     }
 
-    /// Set the maximum window duration.
-    ///
-    /// Set the maximum duration in ms that will be used for
-    /// [`EbuR128::loudness_window`](struct.EbuR128.html#method.loudness_window). Note that this
-    /// destroys the current content of the audio buffer.
-    pub fn set_max_window(&mut self, window: u32) -> Result<(), Error> {
-        let window = if self.mode.contains(Mode::S) {
-            std::cmp::max(window, 3000)
-        } else if self.mode.contains(Mode::M) {
-            std::cmp::max(window, 400)
-        } else {
-            window
-        };
+    /// Get the selected frequency weighting.
+    pub fn weighting(&self) -> Weighting {
+        self.filter.weighting()
+    }
 
-        if window as usize == self.window {
+    /// Select the frequency weighting. The default is `Weighting::K`.
+    ///
+    /// This has to be done before any frames are added or seeded, i.e. directly after creation or
+    /// after [`EbuR128::reset`](struct.EbuR128.html#method.reset). Fails with
+    /// `Error::InvalidMode` if frames were already processed.
+    pub fn set_weighting(&mut self, weighting: Weighting) -> Result<(), Error> {
+        if weighting == self.filter.weighting() {
             return Ok(());
         }
 
-        self.audio_data = Self::allocate_audio_data(self.channels, self.rate, window as usize)?;
-        self.window = window as usize;
+        if self.frames_processed != 0 {
+            return Err(Error::InvalidMode);
+        }
 
-        // the first block needs 400ms of audio data
-        self.needed_frames = self.samples_in_100ms * 4;
-        // start at the beginning of the buffer
-        self.audio_data_index = 0;
-        // reset short term frame counter
-        self.short_term_frame_counter = 0;
+        self.filter.set_weighting(self.rate, weighting);
 
         Ok(())
     }
 
-    /// Set the maximum history.
+    /// Get the handling of NaN and infinite input samples.
+    pub fn invalid_sample_policy(&self) -> InvalidSamplePolicy {
+        self.invalid_sample_policy
+    }
+
+    /// Select the handling of NaN and infinite input samples. The default is
+    /// `InvalidSamplePolicy::Ignore`, which processes them like all other samples and makes all
+    /// following loudness values NaN.
+    ///
+    /// The policy applies to all functions adding or seeding frames and can be changed at any
+    /// time. Checking the samples costs an additional pass over floating point input, and clamping
+    /// copies the input if it contains invalid samples.
+    pub fn set_invalid_sample_policy(&mut self, policy: InvalidSamplePolicy) {
+        self.invalid_sample_policy = policy;
+    }
+
+    /// Get the number of NaN and infinite samples that were replaced with silence since
+    /// construction or the last reset with `InvalidSamplePolicy::ClampToZero`.
+    pub fn sanitized_samples(&self) -> u64 {
+        self.sanitized_samples
+    }
+
+    /// Get the selected true peak oversampling factor.
+    pub fn true_peak_oversampling(&self) -> crate::TruePeakOversampling {
+        self.filter
+            .true_peak_analyzer()
+            .map(crate::TruePeak::oversampling)
+            .unwrap_or_default()
+    }
+
+    /// Select the oversampling factor for the true peak measurement.
+    ///
+    /// ITU BS.1770-4 requires at least 4x oversampling at 48kHz, which is what
+    /// `TruePeakOversampling::Auto` selects, but higher factors reduce the under-reading for
+    /// heavily clipped signals. Changing the factor only resets the true peak interpolator. The
+    /// loudness measurement and the peaks measured so far are kept.
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::TRUE_PEAK` is not set.
+    pub fn set_true_peak_oversampling(
+        &mut self,
+        oversampling: crate::TruePeakOversampling,
+    ) -> Result<(), Error> {
+        if !self.mode.contains(Mode::TRUE_PEAK) {
+            return Err(Error::InvalidMode);
+        }
+
+        if let Some(tp) = self.filter.true_peak_analyzer_mut() {
+            tp.set_oversampling(oversampling);
+        }
+
+        Ok(())
+    }
+
+    /// Set the threshold in dBTP above which true peak events are recorded.
+    ///
+    /// This allows finding the positions of clipping, e.g. for quality control reports. See
+    /// [`TruePeak::set_event_threshold`](struct.TruePeak.html#method.set_event_threshold) for
+    /// details. The frame indices of the events are counted from the first frame that was
+    /// processed after creation, [`EbuR128::reset`](struct.EbuR128.html#method.reset) or
+    /// [`EbuR128::change_parameters`](struct.EbuR128.html#method.change_parameters).
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::TRUE_PEAK` is not set.
+    pub fn set_true_peak_event_threshold(&mut self, dbtp: f64) -> Result<(), Error> {
+        if !self.mode.contains(Mode::TRUE_PEAK) {
+            return Err(Error::InvalidMode);
+        }
+
+        if let Some(tp) = self.filter.true_peak_analyzer_mut() {
+            tp.set_event_threshold(dbtp);
+        }
+
+        Ok(())
+    }
+
+    /// Get all finished true peak events.
+    ///
+    /// Events that are still ongoing at the end of the processed frames are only returned after
+    /// [`EbuR128::flush`](struct.EbuR128.html#method.flush).
+    pub fn true_peak_events(&self) -> Result<&[crate::TruePeakEvent], Error> {
+        if !self.mode.contains(Mode::TRUE_PEAK) {
+            return Err(Error::InvalidMode);
+        }
+
+        Ok(self
+            .filter
+            .true_peak_analyzer()
+            .map_or(&[][..], crate::TruePeak::events))
+    }
+
+    /// Whether true peak events were dropped because too many were detected.
+    pub fn true_peak_events_overflowed(&self) -> Result<bool, Error> {
+        if !self.mode.contains(Mode::TRUE_PEAK) {
+            return Err(Error::InvalidMode);
+        }
+
+        Ok(self
+            .filter
+            .true_peak_analyzer()
+            .is_some_and(crate::TruePeak::events_overflowed))
+    }
+
+    /// Set the maximum window duration.
+    ///
+    /// Set the maximum duration in ms that will be used for
+    /// [`EbuR128::loudness_window`](struct.EbuR128.html#method.loudness_window). Note that this
+    /// destroys the current content of the audio buffer.
+    pub fn set_max_window(&mut self, window: u32) -> Result<(), Error> {
+        let window = if self.mode.contains(Mode::S) {
+            core::cmp::max(window, 3000)
+        } else if self.mode.contains(Mode::M) {
+            core::cmp::max(window, 400)
+        } else {
+            window
+        };
+
+        if window as usize == self.window {
+            return Ok(());
+        }
+
+        self.audio_data = Self::allocate_audio_data(self.channels, self.rate, window as usize)?;
+        self.window = window as usize;
+        self.resize_window_energies();
+
+        // the first block needs 400ms of audio data
+        self.needed_frames = self.samples_in_100ms * 4;
+        // start at the beginning of the buffer
+        self.audio_data_index = 0;
+        // reset short term frame counter
+        self.short_term_frame_counter = 0;
+        // the previous segments are not in the audio buffer anymore
+        self.segment_energies.clear();
+
+        Ok(())
+    }
+
+    /// Set the maximum history.
     ///
     /// Set the maximum history in ms that will be stored for loudness integration. More history
     /// provides more accurate results, but requires more resources.
@@ -520,13 +1702,23 @@ impl EbuR128 {
     /// [`EbuR128::loudness_global`](struct.EbuR128.html#method.loudness_global) when
     /// `Mode::HISTOGRAM` is not set.
     ///
+    /// Without `Mode::HISTOGRAM` one 8 byte energy and its 4 byte position in the histogram bins
+    /// used for fast gating are stored per 100ms gating block, so memory grows without bound for
+    /// long programs with the default. With a maximum history the blocks are kept in a ring buffer
+    /// of `history / 100` entries (`history / 3000` for the loudness range) and the oldest block
+    /// is dropped when it is full. The results are then
+    /// calculated over the last `history` ms only and are not conformant to EBU R128 for longer
+    /// programs. `Mode::HISTOGRAM` uses constant memory and always considers all blocks, it can
+    /// also be selected with
+    /// [`EbuR128::set_history_backend`](struct.EbuR128.html#method.set_history_backend).
+    ///
     /// Default is `ULONG_MAX` (at least ~50 days). Minimum is 3000ms for `Mode::LRA` and 400ms
     /// for `Mode::M`.
     pub fn set_max_history(&mut self, history: u32) -> Result<(), Error> {
         let history = if self.mode.contains(Mode::S) {
-            std::cmp::max(history, 3000)
+            core::cmp::max(history, 3000)
         } else if self.mode.contains(Mode::M) {
-            std::cmp::max(history, 400)
+            core::cmp::max(history, 400)
         } else {
             history
         };
@@ -538,13 +1730,64 @@ impl EbuR128 {
         self.history = history as usize;
 
         self.block_energy_history.set_max_size(self.history / 100);
+        if let Some(ref mut channel_energy_history) = self.channel_energy_history {
+            channel_energy_history.set_max_size(self.history / 100);
+        }
         self.short_term_block_energy_history
             .set_max_size(self.history / 3000);
+        if let Some(ref mut dialogue_gate) = self.dialogue_gate {
+            dialogue_gate
+                .block_energy_history
+                .set_max_size(self.history / 100);
+        }
+        if let Some(ref mut segment) = self.segment_measurement {
+            segment
+                .block_energy_history
+                .set_max_size(self.history / 100);
+            segment
+                .short_term_block_energy_history
+                .set_max_size(self.history / 3000);
+        }
+        self.resize_window_energies();
 
         Ok(())
     }
 
+    /// Maximum number of retained 100ms segment energies.
+    fn max_window_energies(&self) -> usize {
+        if self.history != usize::MAX {
+            // Windows longer than the maximum window are calculated from the segments
+            core::cmp::max(self.history, self.window) / 100
+        } else if self.mode.contains(Mode::M) {
+            // Otherwise they're only needed for the loudness histories, the maximum window itself
+            // is calculated from the audio buffer
+            self.window / 100
+        } else {
+            0
+        }
+    }
+
+    /// Drop the oldest segment energies that are not retained anymore. With `Mode::HISTOGRAM`
+    /// memory for all retained ones is reserved so that processing does not allocate.
+    fn resize_window_energies(&mut self) {
+        let max = self.max_window_energies();
+        if self.window_energies.len() > max {
+            let dropped = self.window_energies.len() - max;
+            self.window_energies.drain(..dropped);
+            self.window_energies_dropped += dropped as u64;
+        }
+
+        if self.mode.contains(Mode::HISTOGRAM) {
+            self.window_energies
+                .reserve_exact(max - self.window_energies.len());
+        }
+    }
+
     /// Resets the current state.
+    ///
+    /// Clears all accumulated loudness history, the sample and true peak maxima and the filter
+    /// and interpolator states. The configuration (mode, channel map, sample rate, maximum window
+    /// and maximum history) is kept and no buffers are reallocated.
     pub fn reset(&mut self) {
         self.audio_data.fill(0.0);
 
@@ -557,17 +1800,263 @@ impl EbuR128 {
 
         self.true_peak.fill(0.0);
         self.sample_peak.fill(0.0);
+        self.frames_processed = 0;
+        self.gating_blocks = 0;
+        self.gating_blocks_above_absolute_threshold = 0;
+
+        self.segment_energies.clear();
+        self.window_energies.clear();
+        self.window_energies_dropped = 0;
+        self.momentary_max = None;
+        self.shortterm_max = None;
+        self.flushed = false;
+        self.pending_samples.clear();
+        self.last_error = None;
+        self.sanitized_samples = 0;
 
         self.filter.reset();
         self.block_energy_history.reset();
+        if let Some(ref mut channel_energy_history) = self.channel_energy_history {
+            channel_energy_history.reset();
+        }
         self.short_term_block_energy_history.reset();
+        if let Some(ref mut dialogue_gate) = self.dialogue_gate {
+            dialogue_gate.block_energy_history.reset();
+        }
+        self.segment_measurement = None;
+    }
+
+    /// Resets the sample and true peak maxima.
+    ///
+    /// This is useful for resetting the peak hold of a meter. Unlike
+    /// [`EbuR128::reset`](struct.EbuR128.html#method.reset) the loudness measurement, the filter and
+    /// interpolator states and the true peak events are kept.
+    ///
+    /// Fails with `Error::InvalidMode` if neither `Mode::SAMPLE_PEAK` nor `Mode::TRUE_PEAK` is
+    /// set.
+    pub fn reset_peaks(&mut self) -> Result<(), Error> {
+        if !self.mode.contains(Mode::SAMPLE_PEAK) && !self.mode.contains(Mode::TRUE_PEAK) {
+            return Err(Error::InvalidMode);
+        }
+
+        self.sample_peak.fill(0.0);
+        self.true_peak.fill(0.0);
+        self.filter.reset_peaks();
+        if let Some(tp) = self.filter.true_peak_analyzer_mut() {
+            tp.reset_peaks();
+        }
+
+        Ok(())
+    }
+
+    /// Create an instance for measuring the track following gaplessly after the frames added so
+    /// far, e.g. of an album.
+    ///
+    /// The new instance has the same configuration and continues with the filter and true peak
+    /// interpolator states of this one, so there is no filter warm-up at the track boundary. All
+    /// measurements start over like after [`EbuR128::reset`](struct.EbuR128.html#method.reset).
+    /// The album loudness can be calculated from all tracks with
+    /// [`EbuR128::loudness_global_multiple`](struct.EbuR128.html#method.loudness_global_multiple).
+    /// Like with separate instances, the gating blocks overlapping the boundary are not measured.
+    ///
+    /// The true peaks of the last few frames of this instance are only interpolated once the next
+    /// frames are known, so they are measured by the new instance. Don't flush this instance, as
+    /// the true peaks at the end would then be interpolated with silence instead of the next
+    /// track.
+    ///
+    /// Fails with `Error::InvalidMode` if this instance was already flushed.
+    pub fn fork_for_next_track(&self) -> Result<EbuR128, Error> {
+        if self.flushed {
+            return Err(Error::InvalidMode);
+        }
+
+        let mut filter = self.filter.clone();
+        filter.reset_peaks();
+        if let Some(tp) = filter.true_peak_analyzer_mut() {
+            tp.reset_measurement();
+        }
+
+        // Only the configuration and the filter are taken over, the histories start empty instead
+        // of being copied first
+        Ok(EbuR128 {
+            mode: self.mode,
+            rate: self.rate,
+            channels: self.channels,
+            audio_data: vec![0.0; self.audio_data.len()].into_boxed_slice(),
+            audio_data_index: 0,
+            needed_frames: self.samples_in_100ms * 4,
+            channel_map: self.channel_map.clone(),
+            channel_weights: self.channel_weights.clone(),
+            samples_in_100ms: self.samples_in_100ms,
+            filter,
+            block_energy_history: self.block_energy_history.empty_like(),
+            channel_energy_history: self
+                .channel_energy_history
+                .as_ref()
+                .map(crate::history::ChannelHistory::empty_like),
+            short_term_block_energy_history: self.short_term_block_energy_history.empty_like(),
+            short_term_frame_counter: 0,
+            sample_peak: vec![0.0; self.sample_peak.len()].into_boxed_slice(),
+            true_peak: vec![0.0; self.true_peak.len()].into_boxed_slice(),
+            window: self.window,
+            history: self.history,
+            frames_processed: 0,
+            gating_blocks: 0,
+            gating_blocks_above_absolute_threshold: 0,
+            segment_energies: VecDeque::with_capacity(30),
+            window_energies: if self.mode.contains(Mode::HISTOGRAM | Mode::M) {
+                VecDeque::with_capacity(self.window / 100)
+            } else {
+                VecDeque::new()
+            },
+            window_energies_dropped: 0,
+            momentary_max: None,
+            shortterm_max: None,
+            flushed: false,
+            dialogue_gate: self
+                .dialogue_gate
+                .as_ref()
+                .map(|dialogue_gate| DialogueGate {
+                    callback: dialogue_gate.callback.clone(),
+                    block_energy_history: dialogue_gate.block_energy_history.empty_like(),
+                }),
+            segment_measurement: None,
+            progress_callback: self.progress_callback.clone(),
+            progress_interval: self.progress_interval,
+            total_frames_hint: self.total_frames_hint,
+            pending_samples: Vec::new(),
+            last_error: None,
+            invalid_sample_policy: self.invalid_sample_policy,
+            sanitized_samples: 0,
+            histogram_resolution: self.histogram_resolution,
+        })
+    }
+
+    /// Merge the measurements of another instance into this one.
+    ///
+    /// This can be used to analyze segments of a long signal in parallel with one instance per
+    /// segment and to combine the results afterwards. The gating block histories are combined,
+    /// and the sample and true peaks are the maximum of both instances. The filter state and any
+    /// incomplete block of this instance are kept, the ones of `other` are discarded.
+    ///
+    /// Gating blocks overlapping the boundary between the two segments are not measured by either
+    /// instance, so the results can differ slightly from a single instance processing the whole
+    /// signal: up to three 400ms blocks for the integrated loudness and up to two 3s blocks for
+    /// the loudness range are missing per boundary. To avoid this, let the segments overlap as
+    /// described in
+    /// [`EbuR128::loudness_global_multiple`](struct.EbuR128.html#method.loudness_global_multiple).
+    ///
+    /// The audio buffer and the 100ms segment energies of `other` are dropped, so
+    /// [`EbuR128::loudness_window`](struct.EbuR128.html#method.loudness_window) and the momentary
+    /// and short-term loudness only reflect the frames added to this instance after a merge.
+    ///
+    /// Fails with `Error::IncompatibleState` if the two instances differ in mode, sample rate,
+    /// number of channels, channel map, weighting, maximum window or maximum history.
+    pub fn merge(&mut self, other: &EbuR128) -> Result<(), Error> {
+        if self.mode.bits() != other.mode.bits()
+            || self.rate != other.rate
+            || self.channels != other.channels
+            || self.window != other.window
+            || self.history != other.history
+            || self.channel_map != other.channel_map
+            || self.channel_weights != other.channel_weights
+            || self.filter.weighting() != other.filter.weighting()
+            || self.histogram_resolution != other.histogram_resolution
+            || self.dialogue_gate.is_some() != other.dialogue_gate.is_some()
+        {
+            return Err(Error::IncompatibleState);
+        }
+
+        self.block_energy_history
+            .merge(&other.block_energy_history)?;
+        if let (Some(channel_energy_history), Some(other_channel_energy_history)) = (
+            self.channel_energy_history.as_mut(),
+            other.channel_energy_history.as_ref(),
+        ) {
+            channel_energy_history.merge(other_channel_energy_history)?;
+        }
+        self.short_term_block_energy_history
+            .merge(&other.short_term_block_energy_history)?;
+        if let (Some(dialogue_gate), Some(other_dialogue_gate)) =
+            (self.dialogue_gate.as_mut(), other.dialogue_gate.as_ref())
+        {
+            dialogue_gate
+                .block_energy_history
+                .merge(&other_dialogue_gate.block_energy_history)?;
+        }
+
+        for (sample_peak, other_sample_peak) in
+            Iterator::zip(self.sample_peak.iter_mut(), other.sample_peak.iter())
+        {
+            if *other_sample_peak > *sample_peak {
+                *sample_peak = *other_sample_peak;
+            }
+        }
+
+        for (true_peak, other_true_peak) in
+            Iterator::zip(self.true_peak.iter_mut(), other.true_peak.iter())
+        {
+            if *other_true_peak > *true_peak {
+                *true_peak = *other_true_peak;
+            }
+        }
+
+        self.frames_processed += other.frames_processed;
+        self.gating_blocks = self.gating_blocks.saturating_add(other.gating_blocks);
+        self.gating_blocks_above_absolute_threshold = self
+            .gating_blocks_above_absolute_threshold
+            .saturating_add(other.gating_blocks_above_absolute_threshold);
+
+        if let Some(other_momentary_max) = other.momentary_max {
+            if self
+                .momentary_max
+                .is_none_or(|max| other_momentary_max > max)
+            {
+                self.momentary_max = Some(other_momentary_max);
+            }
+        }
+        if let Some(other_shortterm_max) = other.shortterm_max {
+            if self
+                .shortterm_max
+                .is_none_or(|max| other_shortterm_max > max)
+            {
+                self.shortterm_max = Some(other_shortterm_max);
+            }
+        }
+
+        Ok(())
     }
 
     /// Process frames. This is the generic variant of the different public add_frames() functions
     /// that are defined below.
-    fn add_frames<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
+    fn add_gating_block(&mut self, energy: f64) {
+        let above_absolute_threshold = self.block_energy_history.add(energy);
+
+        // The block consists of the last four completed 100ms segments
+        let completed_segments = self.completed_segments();
+        if let Some(ref mut segment) = self.segment_measurement {
+            if completed_segments >= segment.first_segment + 4 {
+                segment.block_energy_history.add(energy);
+            }
+        }
+
+        if let Some(ref mut dialogue_gate) = self.dialogue_gate {
+            if (dialogue_gate.callback)(self.gating_blocks) {
+                dialogue_gate.block_energy_history.add(energy);
+            }
+        }
+
+        self.gating_blocks = self.gating_blocks.saturating_add(1);
+        if above_absolute_threshold {
+            self.gating_blocks_above_absolute_threshold = self
+                .gating_blocks_above_absolute_threshold
+                .saturating_add(1);
+        }
+    }
+
+    fn add_samples<'a, T: crate::utils::Sample + 'a, S: crate::Samples<'a, T>>(
         &mut self,
-        mut src: S,
+        src: S,
     ) -> Result<(), Error> {
         if src.frames() == 0 {
             return Ok(());
@@ -577,10 +2066,85 @@ impl EbuR128 {
             return Err(Error::NoMem);
         }
 
+        if src.channels() != self.channels as usize {
+            return Err(Error::InvalidChannelCount);
+        }
+
+        if self.flushed {
+            return Err(Error::InvalidMode);
+        }
+
+        if let Some(sanitized) = self.check_samples(&src)? {
+            return self.process_samples(crate::Interleaved::new(&sanitized, src.channels())?);
+        }
+
+        self.process_samples(src)
+    }
+
+    /// Check the samples according to the invalid sample policy.
+    ///
+    /// Returns the interleaved samples with all invalid samples replaced with silence if there
+    /// are any and they should be clamped.
+    fn check_samples<'a, T: crate::utils::Sample + 'a, S: crate::Samples<'a, T>>(
+        &mut self,
+        src: &S,
+    ) -> Result<Option<Vec<T>>, Error> {
+        if self.invalid_sample_policy == InvalidSamplePolicy::Ignore {
+            return Ok(None);
+        }
+
+        let Some((frame_index, channel)) = crate::utils::find_invalid_sample(src) else {
+            return Ok(None);
+        };
+
+        if self.invalid_sample_policy == InvalidSamplePolicy::Error {
+            return Err(Error::InvalidSample {
+                frame_index: frame_index as u64,
+                channel: channel as u32,
+            });
+        }
+
+        let channels = src.channels();
+        let mut sanitized = vec![T::EQUILIBRIUM; src.frames() * channels];
+        for c in 0..channels {
+            let mut index = c;
+            src.foreach_sample(c, |sample| {
+                if sample.is_finite() {
+                    sanitized[index] = sample;
+                } else {
+                    self.sanitized_samples += 1;
+                }
+                index += channels;
+            });
+        }
+
+        Ok(Some(sanitized))
+    }
+
+    fn process_samples<'a, T: crate::utils::Sample + 'a, S: crate::Samples<'a, T>>(
+        &mut self,
+        mut src: S,
+    ) -> Result<(), Error> {
+        // Process the frames within the interpolator delay separately so that the true peaks of
+        // the audio before the segment are not considered for it
+        if let Some(delay) = self
+            .segment_measurement
+            .as_ref()
+            .map(|segment| segment.true_peak_delay_frames)
+        {
+            if delay > 0 && src.frames() > delay {
+                let (current, next) = src.split_at(delay);
+                self.process_samples(current)?;
+                return self.process_samples(next);
+            }
+        }
+
+        let frames = src.frames();
         self.filter.reset_peaks();
 
         while src.frames() > 0 {
             let num_frames = src.frames();
+            let prev_frames_processed = self.frames_processed;
 
             if num_frames >= self.needed_frames {
                 let (current, next) = src.split_at(self.needed_frames);
@@ -595,14 +2159,36 @@ impl EbuR128 {
                 src = next;
                 self.audio_data_index += self.needed_frames;
 
-                if self.mode.contains(Mode::I) {
+                // The first block completes four segments at once, all others one
+                let segments = if self.segment_energies.is_empty() {
+                    4
+                } else {
+                    1
+                };
+                self.update_loudness_max(segments);
+
+                if let Some(ref mut channel_energy_history) = self.channel_energy_history {
+                    let mut channel_energies: SmallVec<[f64; 16]> =
+                        smallvec![0.0; self.channels as usize];
+                    let energy = crate::filter::Filter::calc_gating_block_with_channels(
+                        self.samples_in_100ms * 4,
+                        &self.audio_data,
+                        self.audio_data_index,
+                        &self.channel_map,
+                        &self.channel_weights,
+                        |c, energy| channel_energies[c] = energy,
+                    );
+                    channel_energy_history.add(energy, &channel_energies);
+                    self.add_gating_block(energy);
+                } else if self.mode.contains(Mode::I) {
                     let energy = crate::filter::Filter::calc_gating_block(
                         self.samples_in_100ms * 4,
                         &self.audio_data,
                         self.audio_data_index,
                         &self.channel_map,
+                        &self.channel_weights,
                     );
-                    self.block_energy_history.add(energy);
+                    self.add_gating_block(energy);
                 }
 
                 if self.mode.contains(Mode::LRA) {
@@ -610,6 +2196,13 @@ impl EbuR128 {
                     if self.short_term_frame_counter == self.samples_in_100ms * 30 {
                         let energy = self.energy_shortterm()?;
                         self.short_term_block_energy_history.add(energy);
+
+                        let completed_segments = self.completed_segments();
+                        if let Some(ref mut segment) = self.segment_measurement {
+                            if completed_segments >= segment.first_segment + 30 {
+                                segment.short_term_block_energy_history.add(energy);
+                            }
+                        }
                         self.short_term_frame_counter = self.samples_in_100ms * 20;
                     }
                 }
@@ -619,6 +2212,7 @@ impl EbuR128 {
                 }
 
                 // 100ms are needed for all blocks besides the first one
+                self.frames_processed += self.needed_frames as u64;
                 self.needed_frames = self.samples_in_100ms;
             } else {
                 let (current, next) = src.split_at(num_frames);
@@ -636,10 +2230,26 @@ impl EbuR128 {
                 }
 
                 src = next;
+                self.frames_processed += num_frames as u64;
                 self.needed_frames -= num_frames;
             }
+
+            if let Some(ref progress_callback) = self.progress_callback {
+                if self.frames_processed / self.progress_interval
+                    > prev_frames_processed / self.progress_interval
+                {
+                    progress_callback(self.frames_processed, self.total_frames_hint);
+                }
+            }
         }
 
+        self.update_peaks(frames);
+
+        Ok(())
+    }
+
+    /// Update the maximum peaks with the ones of the last `frames` processed frames.
+    fn update_peaks(&mut self, frames: usize) {
         let prev_sample_peak = self.filter.sample_peak();
         for (sample_peak, prev_sample_peak) in
             Iterator::zip(self.sample_peak.iter_mut(), prev_sample_peak.iter())
@@ -658,110 +2268,642 @@ impl EbuR128 {
             }
         }
 
-        Ok(())
+        if let Some(ref mut segment) = self.segment_measurement {
+            for (sample_peak, prev_sample_peak) in
+                Iterator::zip(segment.sample_peak.iter_mut(), prev_sample_peak.iter())
+            {
+                if *prev_sample_peak > *sample_peak {
+                    *sample_peak = *prev_sample_peak;
+                }
+            }
+            if segment.true_peak_delay_frames > 0 {
+                segment.true_peak_delay_frames -= frames.min(segment.true_peak_delay_frames);
+            } else {
+                for (true_peak, prev_true_peak) in
+                    Iterator::zip(segment.true_peak.iter_mut(), prev_true_peak.iter())
+                {
+                    if *prev_true_peak > *true_peak {
+                        *true_peak = *prev_true_peak;
+                    }
+                }
+            }
+        }
     }
 
-    fn seed_frames<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(&mut self, src: S) {
-        self.filter.seed(src, &self.channel_map);
-    }
+    /// Finish the measurement at the end of the stream.
+    ///
+    /// Completes the current unfinished block by padding it with silence, so that it is
+    /// considered for all loudness measurements. This also allows measuring the momentary
+    /// loudness of streams shorter than 400ms. Afterwards the true peak interpolator is drained
+    /// so that inter-sample peaks right at the end of the stream are considered, too.
+    ///
+    /// After flushing, adding more frames fails with `Error::InvalidMode` until
+    /// [`EbuR128::reset`](struct.EbuR128.html#method.reset) is called. Flushing multiple times has
+    /// no further effect.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        if self.flushed {
+            return Ok(());
+        }
 
-    /// Add interleaved frames to be processed.
-    pub fn add_frames_i16(&mut self, frames: &[i16]) -> Result<(), Error> {
-        self.add_frames(crate::Interleaved::new(frames, self.channels as usize)?)
-    }
+        // Before the first block was completed there are no completed segments
+        let block_frames = if self.segment_energies.is_empty() {
+            self.samples_in_100ms * 4
+        } else {
+            self.samples_in_100ms
+        };
 
-    /// Add interleaved frames to be processed.
-    pub fn add_frames_i32(&mut self, frames: &[i32]) -> Result<(), Error> {
-        self.add_frames(crate::Interleaved::new(frames, self.channels as usize)?)
-    }
+        if self.needed_frames < block_frames {
+            let zeros = vec![0.0f32; self.needed_frames * self.channels as usize];
+            // The padding is not counted and not reported as progress
+            let frames_processed = self.frames_processed;
+            let progress_callback = self.progress_callback.take();
+            let res = self.add_samples(crate::Interleaved::new(&zeros, self.channels as usize)?);
+            self.frames_processed = frames_processed;
+            self.progress_callback = progress_callback;
+            res?;
+        }
 
-    /// Add interleaved frames to be processed.
-    pub fn add_frames_f32(&mut self, frames: &[f32]) -> Result<(), Error> {
-        self.add_frames(crate::Interleaved::new(frames, self.channels as usize)?)
-    }
+        self.filter.reset_peaks();
+        self.filter.flush_true_peak();
+        self.update_peaks(0);
 
-    /// Add interleaved frames to be processed.
-    pub fn add_frames_f64(&mut self, frames: &[f64]) -> Result<(), Error> {
-        self.add_frames(crate::Interleaved::new(frames, self.channels as usize)?)
-    }
+        self.flushed = true;
 
-    /// Add planar frames to be processed.
-    pub fn add_frames_planar_i16(&mut self, frames: &[&[i16]]) -> Result<(), Error> {
-        self.add_frames(crate::Planar::new(frames)?)
+        Ok(())
     }
 
-    /// Add planar frames to be processed.
-    pub fn add_frames_planar_i32(&mut self, frames: &[&[i32]]) -> Result<(), Error> {
-        self.add_frames(crate::Planar::new(frames)?)
-    }
+    /// Update the maximum momentary and short-term energy after `segments` 100ms segments were
+    /// completed at the current position.
+    fn update_loudness_max(&mut self, segments: usize) {
+        for k in (0..segments).rev() {
+            let energy = crate::filter::Filter::calc_gating_block(
+                self.samples_in_100ms,
+                &self.audio_data,
+                self.audio_data_index - k * self.samples_in_100ms,
+                &self.channel_map,
+                &self.channel_weights,
+            );
 
-    /// Add planar frames to be processed.
-    pub fn add_frames_planar_f32(&mut self, frames: &[&[f32]]) -> Result<(), Error> {
-        self.add_frames(crate::Planar::new(frames)?)
-    }
+            if self.segment_energies.len() == 30 {
+                self.segment_energies.pop_front();
+            }
+            self.segment_energies.push_back(energy);
 
-    /// Add planar frames to be processed.
-    pub fn add_frames_planar_f64(&mut self, frames: &[&[f64]]) -> Result<(), Error> {
-        self.add_frames(crate::Planar::new(frames)?)
-    }
+            if self.window_energies.len() == self.max_window_energies() {
+                self.window_energies_dropped += 1;
+                if self.window_energies.pop_front().is_some() {
+                    self.window_energies.push_back(energy);
+                }
+            } else {
+                self.window_energies.push_back(energy);
+            }
 
-    /// Add interleaved frames to warmup filters, but not be considered for measurements.
-    /// See [`EbuR128::loudness_global_multiple`] for example usage.
-    pub fn seed_frames_i16(&mut self, frames: &[i16]) -> Result<(), Error> {
-        self.seed_frames(crate::Interleaved::new(frames, self.channels as usize)?);
-        Ok(())
-    }
+            let completed_segments = self.completed_segments();
+            let len = self.segment_energies.len();
+            if len >= 4 {
+                let momentary = self.segment_energies.range(len - 4..).sum::<f64>() / 4.0;
+                if self.momentary_max.is_none_or(|max| momentary > max) {
+                    self.momentary_max = Some(momentary);
+                }
+
+                if let Some(ref mut segment) = self.segment_measurement {
+                    if completed_segments >= segment.first_segment + 4
+                        && segment.momentary_max.is_none_or(|max| momentary > max)
+                    {
+                        segment.momentary_max = Some(momentary);
+                    }
+                }
+            }
+
+            if len == 30 {
+                let shortterm = self.segment_energies.iter().sum::<f64>() / 30.0;
+                if self.shortterm_max.is_none_or(|max| shortterm > max) {
+                    self.shortterm_max = Some(shortterm);
+                }
+
+                if let Some(ref mut segment) = self.segment_measurement {
+                    if completed_segments >= segment.first_segment + 30
+                        && segment.shortterm_max.is_none_or(|max| shortterm > max)
+                    {
+                        segment.shortterm_max = Some(shortterm);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Number of 100ms segments completed since construction, the last reset or the last change
+    /// of the parameters.
+    fn completed_segments(&self) -> u64 {
+        self.window_energies_dropped + self.window_energies.len() as u64
+    }
+
+    fn seed_frames<'a, T: crate::utils::Sample + 'a, S: crate::Samples<'a, T>>(
+        &mut self,
+        src: S,
+    ) -> Result<(), Error> {
+        if src.channels() != self.channels as usize {
+            return Err(Error::InvalidChannelCount);
+        }
+
+        if src.frames() == 0 {
+            return Ok(());
+        }
+
+        match self.check_samples(&src)? {
+            Some(sanitized) => self.filter.seed(
+                crate::Interleaved::new(&sanitized, src.channels())?,
+                &self.channel_map,
+            ),
+            None => self.filter.seed(src, &self.channel_map),
+        }
+
+        Ok(())
+    }
+
+    /// Add interleaved frames of any supported [`Sample`](trait.Sample.html) format to be
+    /// processed.
+    ///
+    /// This is the same as calling the function for the specific format, e.g.
+    /// [`EbuR128::add_frames_f32`](struct.EbuR128.html#method.add_frames_f32).
+    pub fn add_frames<S: Sample>(&mut self, frames: &[S]) -> Result<(), Error> {
+        self.add_samples(crate::Interleaved::new(frames, self.channels as usize)?)
+    }
+
+    /// Add interleaved frames to be processed.
+    pub fn add_frames_i16(&mut self, frames: &[i16]) -> Result<(), Error> {
+        self.add_frames(frames)
+    }
+
+    /// Add interleaved, packed 24 bit little-endian frames to be processed.
+    ///
+    /// Each sample takes 3 bytes and the full 24 bit range is scaled to ±1.0.
+    pub fn add_frames_i24(&mut self, frames: &[u8]) -> Result<(), Error> {
+        self.add_samples(crate::InterleavedI24::new(frames, self.channels as usize)?)
+    }
+
+    /// Add interleaved 24 bit frames, sign-extended to 32 bit, to be processed.
+    ///
+    /// The full 24 bit range is scaled to ±1.0.
+    pub fn add_frames_i24_unpacked(&mut self, frames: &[i32]) -> Result<(), Error> {
+        self.add_samples(crate::InterleavedI24::new(frames, self.channels as usize)?)
+    }
+
+    /// Add interleaved frames to be processed.
+    pub fn add_frames_i32(&mut self, frames: &[i32]) -> Result<(), Error> {
+        self.add_frames(frames)
+    }
+
+    /// Add interleaved frames to be processed.
+    pub fn add_frames_f32(&mut self, frames: &[f32]) -> Result<(), Error> {
+        self.add_frames(frames)
+    }
+
+    /// Add interleaved frames from a raw pointer to be processed.
+    ///
+    /// This is the same as [`EbuR128::add_frames_f32`](struct.EbuR128.html#method.add_frames_f32)
+    /// with the `num_frames * channels` samples starting at `ptr`, for C hosts that only have a
+    /// pointer and a number of frames. Fails with `Error::NoMem` if the number of samples
+    /// overflows.
+    ///
+    /// # Safety
+    ///
+    /// Unless `num_frames` is 0, `ptr` must be non-null, aligned for `f32` and valid for reads of
+    /// `num_frames * channels` samples. The samples must not be modified while this is running and
+    /// must not overlap with memory owned by the `EbuR128`. `ptr` may be null if `num_frames` is 0.
+    pub unsafe fn add_frames_f32_raw(
+        &mut self,
+        ptr: *const f32,
+        num_frames: usize,
+    ) -> Result<(), Error> {
+        if num_frames == 0 {
+            return self.add_frames_f32(&[]);
+        }
+
+        let samples = num_frames
+            .checked_mul(self.channels as usize)
+            .ok_or(Error::NoMem)?;
+
+        self.add_frames_f32(core::slice::from_raw_parts(ptr, samples))
+    }
+
+    /// Add a single frame with one sample per channel to be processed.
+    ///
+    /// This is meant for real-time audio callbacks that receive one frame at a time and gives the
+    /// same results as passing all frames at once to
+    /// [`EbuR128::add_frames_f32`](struct.EbuR128.html#method.add_frames_f32). Filtering happens
+    /// per frame, all other work only whenever a 100ms segment is completed.
+    ///
+    /// Neither this nor the other `add_frames` functions lock. They don't allocate either as long
+    /// as `Mode::HISTOGRAM` is used, there are at most 16 channels and no dialogue gate callback
+    /// allocates. Without `Mode::HISTOGRAM` the gating block history grows over time.
+    ///
+    /// Fails with `Error::InvalidChannelCount` if `frame` doesn't have one sample per channel.
+    pub fn push_frame_f32(&mut self, frame: &[f32]) -> Result<(), Error> {
+        if frame.len() != self.channels as usize {
+            return Err(Error::InvalidChannelCount);
+        }
+
+        self.add_samples(crate::Interleaved::new(frame, self.channels as usize)?)
+    }
+
+    /// Add interleaved frames to be processed with a different channel map for this call only.
+    ///
+    /// This is useful if the channel order of the input differs from the configured
+    /// [`EbuR128::channel_map`](struct.EbuR128.html#method.channel_map), e.g. when a demuxer
+    /// delivers the LFE channel at a different position. The configured channel map is not
+    /// changed. Gating blocks completed during this call are weighted according to `order`, even
+    /// though they overlap with up to 300ms of previously added audio.
+    ///
+    /// Fails with `Error::InvalidChannelCount` if `order` doesn't have one element per channel
+    /// and with `Error::InvalidChannelIndex` if it is not a valid channel map.
+    pub fn add_frames_f32_with_channel_order(
+        &mut self,
+        frames: &[f32],
+        order: &[Channel],
+    ) -> Result<(), Error> {
+        if order.len() != self.channels as usize {
+            return Err(Error::InvalidChannelCount);
+        }
+
+        let mut channel_map = [Channel::Unused; MAX_CHANNELS as usize];
+        let channel_map = &mut channel_map[..self.channels as usize];
+        channel_map.copy_from_slice(&self.channel_map);
+
+        self.set_channel_map(order)?;
+        let res = self.add_frames_f32(frames);
+        self.channel_map.copy_from_slice(channel_map);
+
+        res
+    }
+
+    /// Add interleaved frames to be processed.
+    pub fn add_frames_f64(&mut self, frames: &[f64]) -> Result<(), Error> {
+        self.add_frames(frames)
+    }
+
+    /// Add [`dasp_frame::Frame`](https://docs.rs/dasp_frame)s to be processed.
+    ///
+    /// The samples can be of any `dasp_sample` type and are converted to `f64`, e.g. `[f32; 2]`
+    /// frames give the same results as
+    /// [`EbuR128::add_frames_f32`](struct.EbuR128.html#method.add_frames_f32). Fails with
+    /// `Error::InvalidChannelCount` if the number of channels of the frames doesn't match.
+    #[cfg(feature = "dasp")]
+    pub fn add_dasp_frames<F>(&mut self, frames: &[F]) -> Result<(), Error>
+    where
+        F: dasp_frame::Frame + Sync,
+        F::Sample: dasp_sample::ToSample<f64>,
+    {
+        self.add_samples(crate::utils::DaspFrames::new(frames))
+    }
+
+    /// Add a decoded [`symphonia`](https://docs.rs/symphonia) `AudioBufferRef` to be processed.
+    ///
+    /// All sample formats are supported. Signed 16/32 bit integer and floating point samples give
+    /// the same results as [`EbuR128::add_frames_planar`](struct.EbuR128.html#method.add_frames_planar),
+    /// 24 bit samples the same results as
+    /// [`EbuR128::add_frames_planar_i24_unpacked`](struct.EbuR128.html#method.add_frames_planar_i24_unpacked),
+    /// and all other formats are converted to `f64` first.
+    ///
+    /// Fails with `Error::InvalidChannelCount` if the number of channels of the buffer doesn't
+    /// match and with `Error::IncompatibleState` if its sample rate doesn't match.
+    #[cfg(feature = "symphonia")]
+    pub fn add_audio_buffer(
+        &mut self,
+        buf: &symphonia_core::audio::AudioBufferRef<'_>,
+    ) -> Result<(), Error> {
+        use crate::utils::PlanarConverted;
+        use symphonia_core::audio::AudioBufferRef;
+        use symphonia_core::conv::IntoSample;
+        use symphonia_core::sample::Sample as _;
+
+        let spec = buf.spec();
+        if spec.channels.count() != self.channels as usize {
+            return Err(Error::InvalidChannelCount);
+        }
+        if spec.rate != self.rate {
+            return Err(Error::IncompatibleState);
+        }
+        if buf.frames() == 0 {
+            return Ok(());
+        }
+
+        match buf {
+            AudioBufferRef::U8(buf) => self.add_samples(PlanarConverted::new(
+                buf.planes().planes(),
+                IntoSample::<f64>::into_sample,
+            )?),
+            AudioBufferRef::U16(buf) => self.add_samples(PlanarConverted::new(
+                buf.planes().planes(),
+                IntoSample::<f64>::into_sample,
+            )?),
+            AudioBufferRef::U24(buf) => self.add_samples(PlanarConverted::new(
+                buf.planes().planes(),
+                IntoSample::<f64>::into_sample,
+            )?),
+            AudioBufferRef::U32(buf) => self.add_samples(PlanarConverted::new(
+                buf.planes().planes(),
+                IntoSample::<f64>::into_sample,
+            )?),
+            AudioBufferRef::S8(buf) => self.add_samples(PlanarConverted::new(
+                buf.planes().planes(),
+                IntoSample::<f64>::into_sample,
+            )?),
+            AudioBufferRef::S16(buf) => self.add_frames_planar(buf.planes().planes()),
+            AudioBufferRef::S24(buf) => self
+                .add_samples(PlanarConverted::new(buf.planes().planes(), |s| {
+                    crate::utils::I24::new_unchecked(s.clamped().inner())
+                })?),
+            AudioBufferRef::S32(buf) => self.add_frames_planar(buf.planes().planes()),
+            AudioBufferRef::F32(buf) => self.add_frames_planar(buf.planes().planes()),
+            AudioBufferRef::F64(buf) => self.add_frames_planar(buf.planes().planes()),
+        }
+    }
+
+    /// Add interleaved frames stored as raw bytes in the given format to be processed.
+    ///
+    /// The bytes don't have to be aligned for the sample type, e.g. if they come directly from an
+    /// audio capture API, and give the same results as the typed functions like
+    /// [`EbuR128::add_frames_f32`](struct.EbuR128.html#method.add_frames_f32). Fails with
+    /// `Error::NoMem` if the length of `data` is not a multiple of
+    /// [`SampleFormat::bytes_per_sample`](enum.SampleFormat.html#method.bytes_per_sample) times
+    /// the number of channels.
+    pub fn add_frames_bytes(&mut self, data: &[u8], format: SampleFormat) -> Result<(), Error> {
+        use crate::utils::{self, InterleavedBytes};
+
+        let channels = self.channels as usize;
+        match format {
+            SampleFormat::S16Le => {
+                self.add_samples(InterleavedBytes::<utils::S16Le>::new(data, channels)?)
+            }
+            SampleFormat::S16Be => {
+                self.add_samples(InterleavedBytes::<utils::S16Be>::new(data, channels)?)
+            }
+            SampleFormat::S24Le => {
+                self.add_samples(InterleavedBytes::<utils::S24Le>::new(data, channels)?)
+            }
+            SampleFormat::S24Be => {
+                self.add_samples(InterleavedBytes::<utils::S24Be>::new(data, channels)?)
+            }
+            SampleFormat::S32Le => {
+                self.add_samples(InterleavedBytes::<utils::S32Le>::new(data, channels)?)
+            }
+            SampleFormat::S32Be => {
+                self.add_samples(InterleavedBytes::<utils::S32Be>::new(data, channels)?)
+            }
+            SampleFormat::F32Le => {
+                self.add_samples(InterleavedBytes::<utils::F32Le>::new(data, channels)?)
+            }
+            SampleFormat::F32Be => {
+                self.add_samples(InterleavedBytes::<utils::F32Be>::new(data, channels)?)
+            }
+            SampleFormat::F64Le => {
+                self.add_samples(InterleavedBytes::<utils::F64Le>::new(data, channels)?)
+            }
+            SampleFormat::F64Be => {
+                self.add_samples(InterleavedBytes::<utils::F64Be>::new(data, channels)?)
+            }
+        }
+    }
+
+    /// Take the first error that happened while processing samples passed via `Extend`, if any.
+    ///
+    /// `Extend` can't report errors, so they are stored until retrieved with this function.
+    /// Samples passed after an error are still processed if possible.
+    pub fn take_error(&mut self) -> Option<Error> {
+        self.last_error.take()
+    }
+
+    /// Add samples passed via `Extend` to the pending samples and process all complete frames,
+    /// in batches of up to 100ms.
+    fn extend_samples(&mut self, samples: impl Iterator<Item = f64>) {
+        let batch_size = self.samples_in_100ms * self.channels as usize;
+
+        for sample in samples {
+            self.pending_samples.push(sample);
+            if self.pending_samples.len() >= batch_size {
+                self.process_pending_samples();
+            }
+        }
+
+        self.process_pending_samples();
+    }
+
+    fn process_pending_samples(&mut self) {
+        let channels = self.channels as usize;
+        let complete = self.pending_samples.len() / channels * channels;
+        if complete == 0 {
+            return;
+        }
+
+        let mut samples = core::mem::take(&mut self.pending_samples);
+        if let Err(err) = self.add_frames_f64(&samples[..complete]) {
+            self.last_error.get_or_insert(err);
+        }
+        samples.drain(..complete);
+        self.pending_samples = samples;
+    }
+
+    /// Add frames from an iterator with one item per frame to be processed.
+    ///
+    /// This is useful for sources that can't provide the frames as a contiguous slice, e.g. ring
+    /// buffers. The frames are collected in batches into a buffer on the stack and then processed
+    /// like with [`EbuR128::add_frames_f32`](struct.EbuR128.html#method.add_frames_f32), which
+    /// gives the same results.
+    ///
+    /// If a frame does not have one sample per channel, all frames before it are added and
+    /// `Error::InvalidFrameLength` is returned with their number.
+    pub fn add_frames_iter<I, F>(&mut self, iter: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = F>,
+        F: AsRef<[f32]>,
+    {
+        let channels = self.channels as usize;
+        let mut buffer = [0.0f32; ITER_BUFFER_SAMPLES];
+        let buffer_frames = ITER_BUFFER_SAMPLES / channels;
+
+        let mut frames = 0;
+        let mut frames_added = 0;
+        for frame in iter {
+            let frame = frame.as_ref();
+            if frame.len() != channels {
+                self.add_frames_f32(&buffer[..frames * channels])
+                    .map_err(|err| err.after_frames(frames_added))?;
+                return Err(Error::InvalidFrameLength {
+                    frames_added: frames_added + frames as u64,
+                });
+            }
+
+            buffer[frames * channels..][..channels].copy_from_slice(frame);
+            frames += 1;
+            if frames == buffer_frames {
+                self.add_frames_f32(&buffer[..frames * channels])
+                    .map_err(|err| err.after_frames(frames_added))?;
+                frames_added += frames as u64;
+                frames = 0;
+            }
+        }
+
+        self.add_frames_f32(&buffer[..frames * channels])
+            .map_err(|err| err.after_frames(frames_added))
+    }
+
+    /// Add interleaved samples from an iterator to be processed.
+    ///
+    /// Like [`EbuR128::add_frames_iter`](struct.EbuR128.html#method.add_frames_iter) but with one
+    /// item per sample. If the number of samples is not a multiple of the number of channels,
+    /// all complete frames are added and `Error::InvalidFrameLength` is returned with their
+    /// number. Unlike the `Extend` implementation the samples of an incomplete frame are not
+    /// kept for the next call.
+    pub fn add_samples_iter<I: IntoIterator<Item = f32>>(&mut self, iter: I) -> Result<(), Error> {
+        let channels = self.channels as usize;
+        let mut buffer = [0.0f32; ITER_BUFFER_SAMPLES];
+        let buffer_len = ITER_BUFFER_SAMPLES / channels * channels;
+
+        let mut len = 0;
+        let mut frames_added = 0;
+        for sample in iter {
+            buffer[len] = sample;
+            len += 1;
+            if len == buffer_len {
+                self.add_frames_f32(&buffer[..len])
+                    .map_err(|err| err.after_frames(frames_added))?;
+                frames_added += (len / channels) as u64;
+                len = 0;
+            }
+        }
+
+        let complete = len / channels * channels;
+        self.add_frames_f32(&buffer[..complete])
+            .map_err(|err| err.after_frames(frames_added))?;
+        if complete != len {
+            return Err(Error::InvalidFrameLength {
+                frames_added: frames_added + (complete / channels) as u64,
+            });
+        }
 
-    /// Add interleaved frames to warmup filters, but not be considered for measurements.
-    /// See [`EbuR128::loudness_global_multiple`] for example usage.
-    pub fn seed_frames_i32(&mut self, frames: &[i32]) -> Result<(), Error> {
-        self.seed_frames(crate::Interleaved::new(frames, self.channels as usize)?);
         Ok(())
     }
 
+    /// Add planar frames of any supported [`Sample`](trait.Sample.html) format to be processed.
+    ///
+    /// This is the same as calling the function for the specific format, e.g.
+    /// [`EbuR128::add_frames_planar_f32`](struct.EbuR128.html#method.add_frames_planar_f32).
+    pub fn add_frames_planar<S: Sample>(&mut self, frames: &[&[S]]) -> Result<(), Error> {
+        self.add_samples(crate::Planar::new(frames)?)
+    }
+
+    /// Add planar frames to be processed.
+    ///
+    /// Each channel is filtered directly from its own slice without interleaving the frames first.
+    /// Fails with `Error::InvalidChannelCount` if the number of slices does not match the number
+    /// of channels and with `Error::InvalidFrameCount` if the slices differ in length.
+    pub fn add_frames_planar_i16(&mut self, frames: &[&[i16]]) -> Result<(), Error> {
+        self.add_frames_planar(frames)
+    }
+
+    /// Add planar, packed 24 bit little-endian frames to be processed.
+    ///
+    /// Each sample takes 3 bytes and the full 24 bit range is scaled to ±1.0.
+    pub fn add_frames_planar_i24(&mut self, frames: &[&[u8]]) -> Result<(), Error> {
+        self.add_samples(crate::PlanarI24::new(frames)?)
+    }
+
+    /// Add planar 24 bit frames, sign-extended to 32 bit, to be processed.
+    ///
+    /// The full 24 bit range is scaled to ±1.0.
+    pub fn add_frames_planar_i24_unpacked(&mut self, frames: &[&[i32]]) -> Result<(), Error> {
+        self.add_samples(crate::PlanarI24::new(frames)?)
+    }
+
+    /// Add planar frames to be processed.
+    ///
+    /// Each channel is filtered directly from its own slice without interleaving the frames first.
+    /// Fails with `Error::InvalidChannelCount` if the number of slices does not match the number
+    /// of channels and with `Error::InvalidFrameCount` if the slices differ in length.
+    pub fn add_frames_planar_i32(&mut self, frames: &[&[i32]]) -> Result<(), Error> {
+        self.add_frames_planar(frames)
+    }
+
+    /// Add planar frames to be processed.
+    ///
+    /// Each channel is filtered directly from its own slice without interleaving the frames first.
+    /// Fails with `Error::InvalidChannelCount` if the number of slices does not match the number
+    /// of channels and with `Error::InvalidFrameCount` if the slices differ in length.
+    pub fn add_frames_planar_f32(&mut self, frames: &[&[f32]]) -> Result<(), Error> {
+        self.add_frames_planar(frames)
+    }
+
+    /// Add planar frames to be processed.
+    ///
+    /// Each channel is filtered directly from its own slice without interleaving the frames first.
+    /// Fails with `Error::InvalidChannelCount` if the number of slices does not match the number
+    /// of channels and with `Error::InvalidFrameCount` if the slices differ in length.
+    pub fn add_frames_planar_f64(&mut self, frames: &[&[f64]]) -> Result<(), Error> {
+        self.add_frames_planar(frames)
+    }
+
+    /// Add interleaved frames to warmup filters, but not be considered for measurements.
+    /// See [`EbuR128::loudness_global_multiple`] for example usage.
+    pub fn seed_frames_i16(&mut self, frames: &[i16]) -> Result<(), Error> {
+        self.seed_frames(crate::Interleaved::new(frames, self.channels as usize)?)
+    }
+
+    /// Add interleaved frames to warmup filters, but not be considered for measurements.
+    /// See [`EbuR128::loudness_global_multiple`] for example usage.
+    pub fn seed_frames_i32(&mut self, frames: &[i32]) -> Result<(), Error> {
+        self.seed_frames(crate::Interleaved::new(frames, self.channels as usize)?)
+    }
+
     /// Add interleaved frames to warmup filters, but not be considered for measurements.
     /// See [`EbuR128::loudness_global_multiple`] for example usage.
     pub fn seed_frames_f32(&mut self, frames: &[f32]) -> Result<(), Error> {
-        self.seed_frames(crate::Interleaved::new(frames, self.channels as usize)?);
-        Ok(())
+        self.seed_frames(crate::Interleaved::new(frames, self.channels as usize)?)
     }
 
     /// Add interleaved frames to warmup filters, but not be considered for measurements.
     /// See [`EbuR128::loudness_global_multiple`] for example usage.
     pub fn seed_frames_f64(&mut self, frames: &[f64]) -> Result<(), Error> {
-        self.seed_frames(crate::Interleaved::new(frames, self.channels as usize)?);
-        Ok(())
+        self.seed_frames(crate::Interleaved::new(frames, self.channels as usize)?)
     }
 
     /// Add planar frames to warmup filters, but not be considered for measurements.
     /// See [`EbuR128::loudness_global_multiple`] for example usage.
     pub fn seed_frames_planar_i16(&mut self, frames: &[&[i16]]) -> Result<(), Error> {
-        self.seed_frames(crate::Planar::new(frames)?);
-        Ok(())
+        self.seed_frames(crate::Planar::new(frames)?)
     }
 
     /// Add planar frames to warmup filters, but not be considered for measurements.
     /// See [`EbuR128::loudness_global_multiple`] for example usage.
     pub fn seed_frames_planar_i32(&mut self, frames: &[&[i32]]) -> Result<(), Error> {
-        self.seed_frames(crate::Planar::new(frames)?);
-        Ok(())
+        self.seed_frames(crate::Planar::new(frames)?)
     }
 
     /// Add planar frames to warmup filters, but not be considered for measurements.
     /// See [`EbuR128::loudness_global_multiple`] for example usage.
     pub fn seed_frames_planar_f32(&mut self, frames: &[&[f32]]) -> Result<(), Error> {
-        self.seed_frames(crate::Planar::new(frames)?);
-        Ok(())
+        self.seed_frames(crate::Planar::new(frames)?)
     }
 
     /// Add planar frames to warmup filters, but not be considered for measurements.
     /// See [`EbuR128::loudness_global_multiple`] for example usage.
     pub fn seed_frames_planar_f64(&mut self, frames: &[&[f64]]) -> Result<(), Error> {
-        self.seed_frames(crate::Planar::new(frames)?);
-        Ok(())
+        self.seed_frames(crate::Planar::new(frames)?)
     }
 
     /// Get global integrated loudness in LUFS.
+    ///
+    /// This is the gated loudness of ITU BS.1770-4, which is identical to BS.1770-3 for up to
+    /// 5.1 channels and only adds the weights of the additional loudspeaker positions. Neither
+    /// revision defines a speech gate, for dialogue gating see
+    /// [`EbuR128::loudness_dialogue_gated`](struct.EbuR128.html#method.loudness_dialogue_gated).
+    ///
+    /// The cost of calling this does not grow with the length of the measurement, also without
+    /// `Mode::HISTOGRAM`: only the blocks within 0.1 LU of the relative threshold are gated
+    /// individually, all others are summed up while they are added.
+    ///
+    /// With a [`Weighting`](enum.Weighting.html) other than `Weighting::K` this is the gated
+    /// level with the selected weighting instead.
     pub fn loudness_global(&self) -> Result<f64, Error> {
         if !self.mode.contains(Mode::I) {
             return Err(Error::InvalidMode);
@@ -770,6 +2912,112 @@ impl EbuR128 {
         Ok(self.block_energy_history.gated_loudness())
     }
 
+    /// Get global integrated loudness, see
+    /// [`EbuR128::loudness_global`](struct.EbuR128.html#method.loudness_global).
+    pub fn loudness_global_typed(&self) -> Result<LoudnessValue, Error> {
+        self.loudness_global().map(LoudnessValue)
+    }
+
+    /// Get global integrated loudness in LUFS with a non-standard relative gate.
+    ///
+    /// BS.1770 specifies a relative gate of -10 LU below the ungated loudness, which is used by
+    /// [`EbuR128::loudness_global`](struct.EbuR128.html#method.loudness_global). Other gates,
+    /// e.g. -8 LU, are calculated from the same gating blocks. The absolute gate of -70 LUFS is
+    /// always applied. With `Mode::HISTOGRAM` whole histogram bins are included or excluded,
+    /// depending on whether their center is above the relative threshold, exactly like for the
+    /// standard gate.
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::I` is not set and with `Error::OutOfRange` if
+    /// the gate is NaN.
+    pub fn loudness_global_with_gate(&self, relative_gate_lu: f64) -> Result<f64, Error> {
+        if !self.mode.contains(Mode::I) {
+            return Err(Error::InvalidMode);
+        }
+
+        if relative_gate_lu.is_nan() {
+            return Err(Error::OutOfRange);
+        }
+
+        Ok(self
+            .block_energy_history
+            .gated_loudness_with_gate(relative_gate_lu))
+    }
+
+    /// Get global loudness in LUFS without the relative gate.
+    ///
+    /// Only the absolute gate of -70 LUFS is applied.
+    pub fn loudness_global_ungated(&self) -> Result<f64, Error> {
+        self.loudness_global_with_gate(-f64::INFINITY)
+    }
+
+    /// Get the gain in dB that has to be applied to reach the given integrated loudness in LUFS,
+    /// e.g. -23 LUFS for EBU R128.
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::I` is not set and with `Error::NoChange` if the
+    /// integrated loudness is undefined, i.e. no gating block passed the absolute gate.
+    pub fn gain_to_target(&self, target_lufs: f64) -> Result<f64, Error> {
+        let loudness = self.loudness_global()?;
+        if !loudness.is_finite() {
+            return Err(Error::NoChange);
+        }
+
+        Ok(target_lufs - loudness)
+    }
+
+    /// Get the ReplayGain 2.0 track gain in dB, i.e. the gain to reach the reference level of
+    /// -18 LUFS.
+    ///
+    /// Fails like [`EbuR128::gain_to_target`](struct.EbuR128.html#method.gain_to_target), i.e.
+    /// with `Error::NoChange` for silent input.
+    pub fn replaygain_track_gain(&self) -> Result<f64, Error> {
+        self.gain_to_target(REPLAYGAIN_REFERENCE_LUFS)
+    }
+
+    /// Get the ReplayGain 2.0 track peak, i.e. the maximum true peak of all channels as a linear
+    /// value where 1.0 is full scale, not in dBTP.
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::TRUE_PEAK` is not set and with `Error::NoChange`
+    /// if no frames were processed yet.
+    pub fn replaygain_track_peak(&self) -> Result<f64, Error> {
+        Ok(self
+            .true_peak_per_channel()?
+            .into_iter()
+            .fold(0.0, f64::max))
+    }
+
+    /// Get the ReplayGain 2.0 track gain and peak together.
+    ///
+    /// The gain is `-18.0 - loudness_global` in dB, where -18 LUFS corresponds to the 89 dB SPL
+    /// reference level of ReplayGain 2.0. The peak is the linear maximum true peak of all
+    /// channels.
+    ///
+    /// Fails with `Error::InvalidMode` unless both `Mode::I` and `Mode::TRUE_PEAK` are set, and
+    /// otherwise like [`EbuR128::replaygain_track_gain`](struct.EbuR128.html#method.replaygain_track_gain)
+    /// and [`EbuR128::replaygain_track_peak`](struct.EbuR128.html#method.replaygain_track_peak).
+    pub fn replaygain_track_tags(&self) -> Result<ReplayGainTags, Error> {
+        if !self.mode.contains(Mode::I | Mode::TRUE_PEAK) {
+            return Err(Error::InvalidMode);
+        }
+
+        Ok(ReplayGainTags {
+            gain: self.replaygain_track_gain()?,
+            peak: self.replaygain_track_peak()?,
+        })
+    }
+
+    /// Get the gain to reach -23 LUFS in the Q7.8 fixed-point format of the Opus
+    /// `R128_TRACK_GAIN` tag, i.e. in units of 1/256 dB. The value is clamped to the range of
+    /// `i16`.
+    ///
+    /// Fails like [`EbuR128::gain_to_target`](struct.EbuR128.html#method.gain_to_target), i.e.
+    /// with `Error::NoChange` for silent input.
+    pub fn opus_r128_gain(&self) -> Result<i16, Error> {
+        let gain = self.gain_to_target(OPUS_R128_REFERENCE_LUFS)?;
+        let q78 = math::round(gain * 256.0);
+
+        Ok(q78.clamp(i16::MIN as f64, i16::MAX as f64) as i16)
+    }
+
     /// Get global integrated loudness in LUFS across multiple instances.
     ///
     /// This can be used to allow parallel iteration of long signals, assuming some care is taken:
@@ -778,12 +3026,14 @@ impl EbuR128 {
     ///     1. Feed the first 100ms of the chunk (these are samples overlapping with last chunk) through `seed_frames_*` function. This is sufficient to make filter-states in each instance what they would have been if a single analyzer would have reached this point.
     ///     2. Process the remaining samples of each chunk through the analyzer
     ///  3. Call [`EbuR128::loudness_global_multiple`] over all the chunks to get the global loudness
+    ///
+    /// Instances with and without `Mode::HISTOGRAM` can be mixed and only require `Mode::I`. The
+    /// free function [`loudness_global_multiple`](fn.loudness_global_multiple.html) takes a slice
+    /// instead and additionally requires `Mode::HISTOGRAM` for all instances.
     // FIXME: Should maybe be IntoIterator? Maybe AsRef<Self>?
     pub fn loudness_global_multiple<'a>(
         iter: impl Iterator<Item = &'a Self>,
     ) -> Result<f64, Error> {
-        use smallvec::SmallVec;
-
         let h = iter
             .map(|e| {
                 if !e.mode.contains(Mode::I) {
@@ -807,6 +3057,7 @@ impl EbuR128 {
             &self.audio_data,
             self.audio_data_index,
             &self.channel_map,
+            &self.channel_weights,
         ))
     }
 
@@ -821,6 +3072,28 @@ impl EbuR128 {
         Ok(energy_to_loudness(energy))
     }
 
+    /// Get momentary loudness (last 400ms), see
+    /// [`EbuR128::loudness_momentary`](struct.EbuR128.html#method.loudness_momentary).
+    pub fn loudness_momentary_typed(&self) -> Result<LoudnessValue, Error> {
+        self.loudness_momentary().map(LoudnessValue)
+    }
+
+    /// Get the maximum momentary loudness (400ms) in LUFS of all frames that have been processed.
+    ///
+    /// This is updated every 100ms of processed audio and fails with `Error::NoChange` until the
+    /// first 400ms were processed.
+    pub fn loudness_momentary_max(&self) -> Result<f64, Error> {
+        if !self.mode.contains(Mode::M) {
+            return Err(Error::InvalidMode);
+        }
+
+        match self.momentary_max {
+            None => Err(Error::NoChange),
+            Some(max) if max <= 0.0 => Ok(-f64::INFINITY),
+            Some(max) => Ok(energy_to_loudness(max)),
+        }
+    }
+
     fn energy_shortterm(&self) -> Result<f64, Error> {
         self.energy_in_interval(self.samples_in_100ms * 30)
     }
@@ -836,33 +3109,267 @@ impl EbuR128 {
         Ok(energy_to_loudness(energy))
     }
 
-    /// Get loudness of the specified window in LUFS.
-    ///
-    /// window must not be larger than the current window. The current window can be changed by
-    /// calling [`EbuR128::set_max_window`](struct.EbuR128.html#method.set_max_window).
-    pub fn loudness_window(&self, window: u32) -> Result<f64, Error> {
-        let interval_frames = (self.rate as usize)
-            .checked_mul(window as usize)
-            .ok_or(Error::InvalidMode)?
-            / 1000;
-        let energy = self.energy_in_interval(interval_frames)?;
-
-        if energy <= 0.0 {
-            return Ok(-f64::INFINITY);
-        }
-
-        Ok(energy_to_loudness(energy))
+    /// Get short-term loudness (last 3s), see
+    /// [`EbuR128::loudness_shortterm`](struct.EbuR128.html#method.loudness_shortterm).
+    pub fn loudness_shortterm_typed(&self) -> Result<LoudnessValue, Error> {
+        self.loudness_shortterm().map(LoudnessValue)
     }
 
-    /// Get loudness range (LRA) of programme in LU.
+    /// Get the maximum short-term loudness (3s) in LUFS of all frames that have been processed.
     ///
-    /// Calculates loudness range according to EBU 3342.
-    pub fn loudness_range(&self) -> Result<f64, Error> {
-        if !self.mode.contains(Mode::LRA) {
+    /// This is updated every 100ms of processed audio and fails with `Error::NoChange` until the
+    /// first 3s were processed.
+    pub fn loudness_shortterm_max(&self) -> Result<f64, Error> {
+        if !self.mode.contains(Mode::S) {
             return Err(Error::InvalidMode);
         }
 
-        Ok(self.short_term_block_energy_history.loudness_range())
+        match self.shortterm_max {
+            None => Err(Error::NoChange),
+            Some(max) if max <= 0.0 => Ok(-f64::INFINITY),
+            Some(max) => Ok(energy_to_loudness(max)),
+        }
+    }
+
+    /// Get the momentary loudness (400ms) in LUFS over time.
+    ///
+    /// Returns one value per 100ms of processed audio, i.e. every value that was considered for
+    /// [`EbuR128::loudness_momentary_max`](struct.EbuR128.html#method.loudness_momentary_max),
+    /// together with the time of the end of its block relative to the start of the measurement.
+    ///
+    /// The values are calculated from the retained energies of the 100ms segments, see
+    /// [`EbuR128::loudness_window`](struct.EbuR128.html#method.loudness_window), so only the
+    /// values within the maximum window are returned unless a maximum history was set explicitly.
+    /// With a maximum history the values within it are returned.
+    pub fn loudness_momentary_history(&self) -> Result<Vec<(Duration, f64)>, Error> {
+        if !self.mode.contains(Mode::M) {
+            return Err(Error::InvalidMode);
+        }
+
+        Ok(self.loudness_history(4))
+    }
+
+    /// Get the short-term loudness (3s) in LUFS over time.
+    ///
+    /// Returns one value per 100ms of processed audio after the first 3s, i.e. every value that
+    /// was considered for
+    /// [`EbuR128::loudness_shortterm_max`](struct.EbuR128.html#method.loudness_shortterm_max),
+    /// together with the time of the end of its block relative to the start of the measurement.
+    /// This is the loudness curve shown by loudness meters.
+    ///
+    /// The same retention as for
+    /// [`EbuR128::loudness_momentary_history`](struct.EbuR128.html#method.loudness_momentary_history)
+    /// applies.
+    pub fn loudness_shortterm_history(&self) -> Result<Vec<(Duration, f64)>, Error> {
+        if !self.mode.contains(Mode::S) {
+            return Err(Error::InvalidMode);
+        }
+
+        Ok(self.loudness_history(30))
+    }
+
+    /// Calculate the loudness of all blocks of `segments` retained 100ms segments.
+    fn loudness_history(&self, segments: usize) -> Vec<(Duration, f64)> {
+        let rate = self.rate;
+
+        (segments..=self.window_energies.len())
+            .map(|end| {
+                // Summed in the same order as for the maximum loudness
+                let energy =
+                    self.window_energies.range(end - segments..end).sum::<f64>() / segments as f64;
+                let loudness = if energy <= 0.0 {
+                    -f64::INFINITY
+                } else {
+                    energy_to_loudness(energy)
+                };
+
+                let frames =
+                    (self.window_energies_dropped + end as u64) * self.samples_in_100ms as u64;
+
+                (frames_to_duration(frames, rate), loudness)
+            })
+            .collect()
+    }
+
+    /// Get loudness of the specified window in LUFS.
+    ///
+    /// This allows measuring with integration windows other than the momentary (400ms) and
+    /// short-term (3s) ones, e.g. as used by some non-EBU broadcast recommendations. The loudness
+    /// is calculated exactly over the last `window` ms of audio, independent of the 100ms block
+    /// grid.
+    ///
+    /// Windows up to the maximum window are calculated exactly from the buffered audio. The
+    /// maximum window can be changed by calling
+    /// [`EbuR128::set_max_window`](struct.EbuR128.html#method.set_max_window) or
+    /// [`EbuR128Builder::max_window`](struct.EbuR128Builder.html#method.max_window). Longer
+    /// windows are calculated from the energies of the completed 100ms segments instead, rounded
+    /// to a multiple of 100ms and without the currently incomplete segment. If a maximum history
+    /// was set explicitly with
+    /// [`EbuR128::set_max_history`](struct.EbuR128.html#method.set_max_history) or
+    /// [`EbuR128Builder::max_history`](struct.EbuR128Builder.html#method.max_history), these are
+    /// retained for the maximum history or the maximum window, whichever is longer, i.e. one
+    /// value per 100ms of audio. Otherwise only the ones within the maximum window are retained,
+    /// so that the memory usage stays constant, and longer windows are not available. Unlike
+    /// changing the maximum window, changing the maximum history does not require resetting any
+    /// state.
+    ///
+    /// Fails with `Error::OutOfRange` if `window` is shorter than 100ms, the duration of one
+    /// segment, and with `Error::RequestedWindowTooLarge` if not enough audio was retained yet.
+    pub fn loudness_window(&self, window: u32) -> Result<f64, Error> {
+        if window < 100 {
+            return Err(Error::OutOfRange);
+        }
+
+        let interval_frames = (self.rate as usize)
+            .checked_mul(window as usize)
+            .ok_or(Error::InvalidMode)?
+            / 1000;
+
+        if interval_frames <= self.audio_data.len() / self.channels as usize {
+            return self.loudness_window_buffered(window);
+        }
+
+        let energy = {
+            let segments = (window as usize + 50) / 100;
+            if segments > self.window_energies.len() {
+                return Err(Error::RequestedWindowTooLarge {
+                    available_ms: self.window_energies.len() as u64 * 100,
+                    requested_ms: window as u64,
+                });
+            }
+
+            self.window_energies
+                .range(self.window_energies.len() - segments..)
+                .sum::<crate::history::CompensatedSum>()
+                .value()
+                / segments as f64
+        };
+
+        if energy <= 0.0 {
+            return Ok(-f64::INFINITY);
+        }
+
+        Ok(energy_to_loudness(energy))
+    }
+
+    /// Get loudness of the specified window in LUFS, only from the buffered audio.
+    ///
+    /// Fails with `Error::InvalidMode` if the window is larger than the maximum window.
+    pub(crate) fn loudness_window_buffered(&self, window: u32) -> Result<f64, Error> {
+        let interval_frames = (self.rate as usize)
+            .checked_mul(window as usize)
+            .ok_or(Error::InvalidMode)?
+            / 1000;
+        let energy = self.energy_in_interval(interval_frames)?;
+
+        if energy <= 0.0 {
+            return Ok(-f64::INFINITY);
+        }
+
+        Ok(energy_to_loudness(energy))
+    }
+
+    /// Get loudness range (LRA) of programme in LU.
+    ///
+    /// Calculates loudness range according to EBU 3342.
+    pub fn loudness_range(&self) -> Result<f64, Error> {
+        self.loudness_range_with_percentiles(10.0, 95.0)
+    }
+
+    /// Get loudness range of programme in LU with custom percentiles.
+    ///
+    /// Like [`EbuR128::loudness_range`](struct.EbuR128.html#method.loudness_range) but with the
+    /// given low and high percentiles, in percent, instead of 10 and 95. All percentiles are
+    /// selected with the same nearest-rank method as the loudness range of EBU 3342, so
+    /// `loudness_range` is exactly `loudness_range_with_percentiles(10.0, 95.0)`. The result only
+    /// changes when a percentile moves to the next block, or the next histogram bin with
+    /// `Mode::HISTOGRAM`. For interpolated percentiles see
+    /// [`LoudnessStats::percentile`](struct.LoudnessStats.html#method.percentile).
+    ///
+    /// Fails with `Error::OutOfRange` unless `0.0 <= low < high <= 100.0`.
+    pub fn loudness_range_with_percentiles(&self, low: f64, high: f64) -> Result<f64, Error> {
+        if !self.mode.contains(Mode::LRA) {
+            return Err(Error::InvalidMode);
+        }
+
+        if !(0.0 <= low && low < high && high <= 100.0) {
+            return Err(Error::OutOfRange);
+        }
+
+        Ok(
+            match self
+                .short_term_block_energy_history
+                .loudness_range_energies(low / 100.0, high / 100.0)
+            {
+                Some((l_en, h_en)) => energy_to_loudness(h_en) - energy_to_loudness(l_en),
+                None => 0.0,
+            },
+        )
+    }
+
+    /// Get the low and high boundaries of the loudness range (LRA) of programme in LUFS.
+    ///
+    /// These are the loudness values at the 10th and 95th percentile of the short-term loudness
+    /// distribution according to EBU 3342, the loudness range is their difference. Returns
+    /// `-f64::INFINITY` for both if there are no short-term blocks above the gates.
+    ///
+    /// Both are calculated exactly like for
+    /// [`EbuR128::loudness_range`](struct.EbuR128.html#method.loudness_range), with and without
+    /// `Mode::HISTOGRAM`, so `high - low` is always exactly the loudness range if there are blocks.
+    /// A low upper boundary and a small range mean quiet and dense content, a high upper boundary
+    /// and a large range loud content with quiet passages.
+    pub fn loudness_range_boundaries(&self) -> Result<(f64, f64), Error> {
+        if !self.mode.contains(Mode::LRA) {
+            return Err(Error::InvalidMode);
+        }
+
+        let (low, high) = crate::history::LRA_PERCENTILES;
+        Ok(
+            match self
+                .short_term_block_energy_history
+                .loudness_range_energies(low, high)
+            {
+                Some((l_en, h_en)) => (energy_to_loudness(l_en), energy_to_loudness(h_en)),
+                None => (-f64::INFINITY, -f64::INFINITY),
+            },
+        )
+    }
+
+    /// Get statistics of the short-term loudness distribution, e.g. the median, the 99th
+    /// percentile or the standard deviation of the short-term loudness.
+    ///
+    /// These are calculated from the same 3s blocks as
+    /// [`EbuR128::loudness_range`](struct.EbuR128.html#method.loudness_range), i.e. the blocks
+    /// above the absolute gate of -70 LUFS and the relative gate of -20 LU below their mean
+    /// energy. See [`LoudnessStats`](struct.LoudnessStats.html) for how they are calculated with
+    /// and without `Mode::HISTOGRAM`.
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::LRA` is not set and with `Error::NoChange` if
+    /// there are no short-term blocks above the gates.
+    pub fn shortterm_statistics(&self) -> Result<LoudnessStats, Error> {
+        self.shortterm_statistics_with_gate(Some(crate::history::LRA_RELATIVE_GATE))
+    }
+
+    /// Get statistics of the short-term loudness distribution without the relative gate.
+    ///
+    /// Like [`EbuR128::shortterm_statistics`](struct.EbuR128.html#method.shortterm_statistics)
+    /// but including all 3s blocks above the absolute gate of -70 LUFS, e.g. to also describe the
+    /// quiet passages of the content.
+    pub fn shortterm_statistics_ungated(&self) -> Result<LoudnessStats, Error> {
+        self.shortterm_statistics_with_gate(None)
+    }
+
+    fn shortterm_statistics_with_gate(
+        &self,
+        relative_gate: Option<f64>,
+    ) -> Result<LoudnessStats, Error> {
+        if !self.mode.contains(Mode::LRA) {
+            return Err(Error::InvalidMode);
+        }
+
+        self.short_term_block_energy_history
+            .loudness_stats(relative_gate)
+            .ok_or(Error::NoChange)
     }
 
     /// Get loudness range (LRA) of programme in LU across multiple instances.
@@ -872,8 +3379,6 @@ impl EbuR128 {
     pub fn loudness_range_multiple<'a>(
         iter: impl IntoIterator<Item = &'a Self>,
     ) -> Result<f64, Error> {
-        use smallvec::SmallVec;
-
         let h = iter
             .into_iter()
             .map(|e| {
@@ -903,6 +3408,22 @@ impl EbuR128 {
         Ok(self.sample_peak[channel_number as usize])
     }
 
+    /// Get maximum sample peak of all channels from all frames that have been processed.
+    ///
+    /// The returned values are indexed by channel number. Returns `Error::NoChange` if no frames
+    /// were processed yet.
+    pub fn sample_peak_per_channel(&self) -> Result<Vec<f64>, Error> {
+        if !self.mode.contains(Mode::SAMPLE_PEAK) {
+            return Err(Error::InvalidMode);
+        }
+
+        if self.frames_processed == 0 {
+            return Err(Error::NoChange);
+        }
+
+        Ok(self.sample_peak.to_vec())
+    }
+
     /// Get maximum sample peak from the last call to
     /// [`EbuR128::add_frames`](struct.EbuR128.html#method.add_frames_i16).
     ///
@@ -945,6 +3466,221 @@ impl EbuR128 {
         }
     }
 
+    /// Get maximum true peak of all channels from all frames that have been processed.
+    ///
+    /// The returned values are indexed by channel number. Returns `Error::NoChange` if no frames
+    /// were processed yet. See [`EbuR128::true_peak`](struct.EbuR128.html#method.true_peak) for
+    /// details about the algorithm.
+    pub fn true_peak_per_channel(&self) -> Result<Vec<f64>, Error> {
+        if !self.mode.contains(Mode::TRUE_PEAK) {
+            return Err(Error::InvalidMode);
+        }
+
+        if self.frames_processed == 0 {
+            return Err(Error::NoChange);
+        }
+
+        Ok(
+            Iterator::zip(self.sample_peak.iter(), self.true_peak.iter())
+                .map(|(sample_peak, true_peak)| {
+                    if *sample_peak > *true_peak {
+                        *sample_peak
+                    } else {
+                        *true_peak
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Get the headroom in dB between the maximum true peak of all channels and 0 dBTP.
+    ///
+    /// This is the maximum gain that can be applied without clipping, and is negative if the
+    /// true peak is above 0 dBTP.
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::TRUE_PEAK` is not set and with `Error::NoChange`
+    /// if no frames were processed yet or all frames were silent.
+    pub fn true_peak_headroom(&self) -> Result<f64, Error> {
+        let true_peak = self.true_peak_max_all_channels()?;
+        if !true_peak.is_finite() {
+            return Err(Error::NoChange);
+        }
+
+        Ok(-true_peak)
+    }
+
+    /// Get all final values of the measurement at once.
+    ///
+    /// Values that are not measured with the configured mode are `None`. See
+    /// [`EbuR128Summary`](struct.EbuR128Summary.html) for the units.
+    ///
+    /// Fails with `Error::NoChange` if no frames were processed yet.
+    pub fn summary(&self) -> Result<EbuR128Summary, Error> {
+        if self.frames_processed == 0 {
+            return Err(Error::NoChange);
+        }
+
+        let sample_peak_max = if self.mode.contains(Mode::SAMPLE_PEAK) {
+            let sample_peak = self.sample_peak.iter().copied().fold(0.0, f64::max);
+            Some(20.0 * math::log10(sample_peak))
+        } else {
+            None
+        };
+
+        Ok(EbuR128Summary {
+            loudness_global: self.loudness_global().ok(),
+            loudness_range: self.loudness_range().ok(),
+            loudness_momentary_max: self.loudness_momentary_max().ok(),
+            loudness_shortterm_max: self.loudness_shortterm_max().ok(),
+            sample_peak_max,
+            true_peak_max: self.true_peak_max_all_channels().ok(),
+        })
+    }
+
+    /// Collect the values compared by the `PartialEq` implementation.
+    fn measurement_snapshot(&self) -> MeasurementSnapshot {
+        MeasurementSnapshot {
+            loudness_global: self.loudness_global(),
+            loudness_range: self.loudness_range(),
+            true_peak: (0..self.channels).map(|c| self.true_peak(c)).collect(),
+            sample_peak: (0..self.channels).map(|c| self.sample_peak(c)).collect(),
+            frames_processed: self.frames_processed,
+        }
+    }
+
+    /// Begin measuring a segment of the programme, e.g. a chapter or a scene.
+    ///
+    /// The segment is measured in addition to the whole programme until
+    /// [`EbuR128::end_segment`](struct.EbuR128.html#method.end_segment) is called. Gating blocks
+    /// and loudness windows are only considered for the segment if they start at or after the
+    /// current position. As they are aligned to the start of the measurement, the segment is
+    /// measured exactly like by a separate instance if it starts at a multiple of 100ms, or of 1s
+    /// for the loudness range. The peaks consider all frames added from now on, but the filters and
+    /// the true peak interpolator continue with the audio before the segment.
+    ///
+    /// Frames of other instances added with [`EbuR128::merge`](struct.EbuR128.html#method.merge)
+    /// are not considered, and [`EbuR128::reset`](struct.EbuR128.html#method.reset) and
+    /// [`EbuR128::change_parameters`](struct.EbuR128.html#method.change_parameters) discard the
+    /// segment.
+    ///
+    /// Fails with `Error::IncompatibleState` if a segment is already measured, segments can't be
+    /// nested.
+    pub fn begin_segment(&mut self) -> Result<(), Error> {
+        if self.segment_measurement.is_some() {
+            return Err(Error::IncompatibleState);
+        }
+
+        // Frames of the current, incomplete block that are not part of a completed 100ms segment
+        let block_frames = if self.segment_energies.is_empty() {
+            self.samples_in_100ms * 4
+        } else {
+            self.samples_in_100ms
+        };
+        let pending_frames = block_frames - self.needed_frames;
+        let first_segment =
+            self.completed_segments() + pending_frames.div_ceil(self.samples_in_100ms) as u64;
+
+        let use_histogram = self.mode.contains(Mode::HISTOGRAM);
+        self.segment_measurement = Some(Box::new(SegmentMeasurement {
+            first_segment,
+            start_frame: self.frames_processed,
+            block_energy_history: crate::history::History::new(
+                use_histogram,
+                self.histogram_resolution,
+                self.history / 100,
+            ),
+            short_term_block_energy_history: crate::history::History::new(
+                use_histogram,
+                self.histogram_resolution,
+                self.history / 3000,
+            ),
+            sample_peak: vec![0.0; self.channels as usize].into_boxed_slice(),
+            true_peak: vec![0.0; self.channels as usize].into_boxed_slice(),
+            true_peak_delay_frames: self
+                .filter
+                .true_peak_analyzer()
+                .map_or(0, crate::true_peak::TruePeak::delay),
+            momentary_max: None,
+            shortterm_max: None,
+        }));
+
+        Ok(())
+    }
+
+    /// End the segment started with
+    /// [`EbuR128::begin_segment`](struct.EbuR128.html#method.begin_segment) and get all its final
+    /// values at once, like [`EbuR128::summary`](struct.EbuR128.html#method.summary) does for the
+    /// whole programme.
+    ///
+    /// Fails with `Error::NoChange` if no segment is measured or if no frames were added since it
+    /// began. The segment is ended in any case.
+    pub fn end_segment(&mut self) -> Result<EbuR128Summary, Error> {
+        let segment = self.segment_measurement.take().ok_or(Error::NoChange)?;
+        if self.frames_processed == segment.start_frame {
+            return Err(Error::NoChange);
+        }
+
+        let max_loudness = |energy: f64| {
+            if energy <= 0.0 {
+                -f64::INFINITY
+            } else {
+                energy_to_loudness(energy)
+            }
+        };
+        let sample_peak = segment.sample_peak.iter().copied().fold(0.0, f64::max);
+        let true_peak = segment
+            .true_peak
+            .iter()
+            .copied()
+            .fold(sample_peak, f64::max);
+
+        Ok(EbuR128Summary {
+            loudness_global: if self.mode.contains(Mode::I) {
+                Some(segment.block_energy_history.gated_loudness())
+            } else {
+                None
+            },
+            loudness_range: if self.mode.contains(Mode::LRA) {
+                Some(segment.short_term_block_energy_history.loudness_range())
+            } else {
+                None
+            },
+            loudness_momentary_max: segment.momentary_max.map(max_loudness),
+            loudness_shortterm_max: if self.mode.contains(Mode::S) {
+                segment.shortterm_max.map(max_loudness)
+            } else {
+                None
+            },
+            sample_peak_max: if self.mode.contains(Mode::SAMPLE_PEAK) {
+                Some(20.0 * math::log10(sample_peak))
+            } else {
+                None
+            },
+            true_peak_max: if self.mode.contains(Mode::TRUE_PEAK) {
+                Some(20.0 * math::log10(true_peak))
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Get the maximum true peak of all channels in dBTP from all frames that have been
+    /// processed.
+    ///
+    /// Unlike [`EbuR128::true_peak`](struct.EbuR128.html#method.true_peak) this is already
+    /// converted to dBTP, and is `-f64::INFINITY` if all frames were silent.
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::TRUE_PEAK` is not set and with `Error::NoChange`
+    /// if no frames were processed yet.
+    pub fn true_peak_max_all_channels(&self) -> Result<f64, Error> {
+        let true_peak = self
+            .true_peak_per_channel()?
+            .into_iter()
+            .fold(0.0, f64::max);
+
+        Ok(20.0 * math::log10(true_peak))
+    }
+
     /// Get maximum true peak from the last call to
     /// [`EbuR128::add_frames`](struct.EbuR128.html#method.add_frames_i16).
     ///
@@ -975,6 +3711,66 @@ impl EbuR128 {
         }
     }
 
+    /// Get the gated loudness contribution of a channel in LUFS.
+    ///
+    /// This is the K-weighted energy of the channel, including its channel weight, over the same
+    /// gating blocks as used by [`EbuR128::loudness_global`](struct.EbuR128.html#method.loudness_global).
+    /// The energies of all channels add up to the energy of the integrated loudness, exactly
+    /// without `Mode::HISTOGRAM` and approximately with it. Unused channels and measurements
+    /// without any blocks above the gates result in `-f64::INFINITY`.
+    ///
+    /// Requires `Mode::CHANNEL_I`.
+    pub fn channel_loudness(&self, channel_number: u32) -> Result<f64, Error> {
+        if channel_number >= self.channels {
+            return Err(Error::InvalidChannelIndex);
+        }
+
+        Ok(self.channel_loudness_all()?[channel_number as usize])
+    }
+
+    /// Get the gated loudness contribution of all channels in LUFS.
+    ///
+    /// The returned values are indexed by channel number. See
+    /// [`EbuR128::channel_loudness`](struct.EbuR128.html#method.channel_loudness) for details.
+    pub fn channel_loudness_all(&self) -> Result<Vec<f64>, Error> {
+        let channel_energy_history = match self.channel_energy_history {
+            Some(ref channel_energy_history) if self.mode.contains(Mode::CHANNEL_I) => {
+                channel_energy_history
+            }
+            _ => return Err(Error::InvalidMode),
+        };
+
+        let relative_threshold = match crate::history::History::relative_gate(
+            &[&self.block_energy_history],
+            crate::history::RELATIVE_GATE,
+        ) {
+            Ok(gate) => gate,
+            Err(crate::history::HistoryError::NoBlocksAboveThreshold) => {
+                return Ok(vec![-f64::INFINITY; self.channels as usize]);
+            }
+            Err(crate::history::HistoryError::RelativeThresholdIsNan) => {
+                return Ok(vec![f64::NAN; self.channels as usize]);
+            }
+        };
+
+        let (count, energies) = channel_energy_history.gated_energies(relative_threshold);
+        if count == 0 {
+            return Ok(vec![-f64::INFINITY; self.channels as usize]);
+        }
+
+        Ok(energies
+            .into_iter()
+            .map(|energy| {
+                let energy = energy / count as f64;
+                if energy <= 0.0 {
+                    -f64::INFINITY
+                } else {
+                    energy_to_loudness(energy)
+                }
+            })
+            .collect())
+    }
+
     /// Get relative threshold in LUFS.
     pub fn relative_threshold(&self) -> Result<f64, Error> {
         if !self.mode.contains(Mode::I) {
@@ -990,4 +3786,723 @@ impl EbuR128 {
             .gating_block_count_and_energy()
             .ok()
     }
-}
\ No newline at end of file
+
+    /// Enable dialogue gating with a callback that decides which gating blocks contain dialogue,
+    /// e.g. based on an external voice activity detector.
+    ///
+    /// The callback is called with the index of every completed 400ms gating block, counted since
+    /// construction or the last reset. Block `n` covers the audio from `n * 100` ms to
+    /// `n * 100 + 400` ms. Blocks for which it returns `true` are used for
+    /// [`EbuR128::loudness_dialogue_gated`](struct.EbuR128.html#method.loudness_dialogue_gated).
+    /// Setting a new callback discards the previously tagged blocks. The callback is shared between
+    /// clones of this instance and is not part of the serialized state.
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::I` is not set.
+    pub fn set_block_gate_callback(
+        &mut self,
+        callback: impl Fn(u64) -> bool + Send + Sync + 'static,
+    ) -> Result<(), Error> {
+        if !self.mode.contains(Mode::I) {
+            return Err(Error::InvalidMode);
+        }
+
+        self.dialogue_gate = Some(DialogueGate {
+            callback: Arc::new(callback),
+            block_energy_history: crate::history::History::new(
+                self.mode.contains(Mode::HISTOGRAM),
+                self.histogram_resolution,
+                self.history / 100,
+            ),
+        });
+
+        Ok(())
+    }
+
+    /// Disable dialogue gating and discard the tagged blocks.
+    pub fn clear_block_gate_callback(&mut self) {
+        self.dialogue_gate = None;
+    }
+
+    /// Set a callback for reporting the progress of long-running analyses.
+    ///
+    /// The callback is called with the number of frames processed since construction or the last
+    /// reset and the hint set with
+    /// [`EbuR128::set_total_frames_hint`](struct.EbuR128.html#method.set_total_frames_hint) every
+    /// time the number of frames passes a multiple of the
+    /// [progress interval](struct.EbuR128.html#method.set_progress_interval), also in the middle
+    /// of a single call to add frames. It is called from the thread adding the frames and should
+    /// return quickly. The callback is shared between clones of this instance and is not part of
+    /// the serialized state.
+    pub fn set_progress_callback(&mut self, callback: impl Fn(u64, u64) + Send + Sync + 'static) {
+        self.progress_callback = Some(Arc::new(callback));
+    }
+
+    /// Remove the progress callback.
+    pub fn clear_progress_callback(&mut self) {
+        self.progress_callback = None;
+    }
+
+    /// Set the number of frames between calls of the progress callback. The default is 48000.
+    ///
+    /// The callback is called at most once per completed 100ms segment and per call to add
+    /// frames, so very short intervals are not followed exactly. Fails with `Error::OutOfRange`
+    /// if `frames` is 0.
+    pub fn set_progress_interval(&mut self, frames: u64) -> Result<(), Error> {
+        if frames == 0 {
+            return Err(Error::OutOfRange);
+        }
+
+        self.progress_interval = frames;
+
+        Ok(())
+    }
+
+    /// Set the expected total number of frames that is passed to the progress callback, or 0 if
+    /// it is unknown, which is the default.
+    pub fn set_total_frames_hint(&mut self, frames: u64) {
+        self.total_frames_hint = frames;
+    }
+
+    /// Get the dialogue-gated loudness in LUFS.
+    ///
+    /// This is the loudness of all gating blocks tagged as dialogue by the callback set with
+    /// [`EbuR128::set_block_gate_callback`](struct.EbuR128.html#method.set_block_gate_callback)
+    /// that pass the absolute gate of -70 LUFS. The relative gate is not applied.
+    ///
+    /// No voice activity detection is included as there is no standardized one. The result
+    /// depends on the detector used for tagging the blocks, e.g. the same detector should be
+    /// used when comparing with a proprietary dialogue-gated measurement.
+    ///
+    /// Fails with `Error::InvalidMode` if no callback is set and with `Error::NoDialogueBlocks` if
+    /// no block above the absolute gate was tagged.
+    pub fn loudness_dialogue_gated(&self) -> Result<f64, Error> {
+        let dialogue_gate = self.dialogue_gate.as_ref().ok_or(Error::InvalidMode)?;
+
+        match crate::history::History::gating_block_count_and_energy_multiple(
+            &[&dialogue_gate.block_energy_history],
+            -f64::INFINITY,
+        ) {
+            Ok((count, energy)) => Ok(energy_to_loudness(energy / count as f64)),
+            Err(crate::history::HistoryError::NoBlocksAboveThreshold) => {
+                Err(Error::NoDialogueBlocks)
+            }
+            Err(crate::history::HistoryError::RelativeThresholdIsNan) => Ok(f64::NAN),
+        }
+    }
+
+    /// Get statistics about the gating of the integrated loudness.
+    ///
+    /// This shows how many gating blocks were discarded by the absolute and relative gates, e.g.
+    /// if a measurement is surprisingly quiet. See [`GatingStats`](struct.GatingStats.html).
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::I` is not set.
+    pub fn gating_block_stats(&self) -> Result<GatingStats, Error> {
+        if !self.mode.contains(Mode::I) {
+            return Err(Error::InvalidMode);
+        }
+
+        let blocks_above_relative_threshold = self
+            .block_energy_history
+            .gating_block_count_and_energy()
+            .map_or(0, |(count, _)| count);
+
+        Ok(GatingStats {
+            blocks: self.gating_blocks,
+            blocks_above_absolute_threshold: self.gating_blocks_above_absolute_threshold,
+            blocks_above_relative_threshold,
+            relative_threshold: self.block_energy_history.relative_threshold(),
+        })
+    }
+
+    /// Get an iterator over the energies of all stored gating blocks in chronological order.
+    ///
+    /// A gating block is 400ms long and a new block starts every 100ms. The energies are the mean
+    /// square power of the K-weighted signal, summed over the channels with their weights, before
+    /// the relative gate is applied. Use [`energy_to_loudness`](fn.energy_to_loudness.html) to
+    /// convert them to LUFS. Blocks below the absolute threshold of -70 LUFS are never stored and
+    /// not returned, and only the blocks of the last
+    /// [`EbuR128::max_history`](struct.EbuR128.html#method.max_history) ms are kept.
+    ///
+    /// This is only available if `Mode::I` is set and `Mode::HISTOGRAM` is not set. Histograms only
+    /// keep the number of blocks per loudness range, which makes memory usage constant but loses
+    /// the order of the blocks. Without histograms 12 bytes are stored per 100ms of audio, so the
+    /// maximum history should be limited for long-running measurements. Fails with
+    /// `Error::InvalidMode` otherwise.
+    pub fn gating_blocks_iter(&self) -> Result<impl Iterator<Item = f64> + '_, Error> {
+        if !self.mode.contains(Mode::I) {
+            return Err(Error::InvalidMode);
+        }
+
+        self.block_energy_history
+            .energies()
+            .ok_or(Error::InvalidMode)
+    }
+
+    /// Get the loudness histogram of the gating blocks used for the integrated loudness.
+    ///
+    /// Returns all bins from -70 LUFS to +30 LUFS in ascending order, i.e. 1000 bins of 0.1 LU
+    /// with the default
+    /// [`EbuR128::histogram_resolution`](struct.EbuR128.html#method.histogram_resolution). With
+    /// `Mode::HISTOGRAM` these are exactly the counts the gating is calculated from, using the
+    /// energy at the center of each bin. Otherwise the stored blocks of the last
+    /// [`EbuR128::max_history`](struct.EbuR128.html#method.max_history) ms are sorted into the
+    /// same bins.
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::I` is not set.
+    pub fn block_energy_histogram(&self) -> Result<Vec<HistogramBin>, Error> {
+        if !self.mode.contains(Mode::I) {
+            return Err(Error::InvalidMode);
+        }
+
+        Ok(self
+            .block_energy_history
+            .histogram_bins(self.histogram_resolution))
+    }
+
+    /// Get the loudness histogram of the 3s blocks used for the loudness range.
+    ///
+    /// The bins are the same as for
+    /// [`EbuR128::block_energy_histogram`](struct.EbuR128.html#method.block_energy_histogram).
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::LRA` is not set.
+    pub fn loudness_range_histogram(&self) -> Result<Vec<HistogramBin>, Error> {
+        if !self.mode.contains(Mode::LRA) {
+            return Err(Error::InvalidMode);
+        }
+
+        Ok(self
+            .short_term_block_energy_history
+            .histogram_bins(self.histogram_resolution))
+    }
+
+    /// Get the number of gating blocks in the histogram bin containing `lufs`.
+    ///
+    /// Bins are 0.1 LU wide with the default resolution, see
+    /// [`EbuR128::block_energy_histogram`](struct.EbuR128.html#method.block_energy_histogram).
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::I` or `Mode::HISTOGRAM` is not set and with
+    /// `Error::OutOfRange` if `lufs` is outside of
+    /// [`EbuR128::histogram_lufs_range`](struct.EbuR128.html#method.histogram_lufs_range).
+    pub fn histogram_blocks_at_lufs(&self, lufs: f64) -> Result<u64, Error> {
+        if !self.mode.contains(Mode::I | Mode::HISTOGRAM) {
+            return Err(Error::InvalidMode);
+        }
+
+        self.block_energy_history
+            .histogram_count_at(lufs)
+            .ok_or(Error::OutOfRange)
+    }
+
+    /// Get the loudness in LUFS at `percentile` (0 to 100) of the gating blocks above the absolute
+    /// threshold, e.g. 50 for the median loudness.
+    ///
+    /// The percentile is determined like the ones of the loudness range calculation, i.e. the
+    /// loudness is the center of the histogram bin containing the block at the percentile.
+    ///
+    /// Fails with `Error::InvalidMode` if `Mode::I` or `Mode::HISTOGRAM` is not set, with
+    /// `Error::OutOfRange` if `percentile` is outside of 0 to 100 and with `Error::NoChange` if
+    /// there are no gating blocks above the absolute threshold.
+    pub fn loudness_percentile(&self, percentile: f64) -> Result<f64, Error> {
+        if !self.mode.contains(Mode::I | Mode::HISTOGRAM) {
+            return Err(Error::InvalidMode);
+        }
+
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(Error::OutOfRange);
+        }
+
+        self.block_energy_history
+            .percentile_energy(percentile / 100.0)
+            .map(energy_to_loudness)
+            .ok_or(Error::NoChange)
+    }
+
+    /// Get the range of the loudness histogram in LUFS.
+    ///
+    /// Returns the lower boundary of the first bin (inclusive) and the upper boundary of the last
+    /// bin (exclusive).
+    pub fn histogram_lufs_range() -> (f64, f64) {
+        crate::history::HISTOGRAM_LUFS_RANGE
+    }
+}
+
+/// Compare the measurements: the integrated loudness, the loudness range, the true and sample
+/// peaks of all channels and the number of processed frames. Values that are not measured with
+/// the configured mode compare equal if both fail the same way.
+///
+/// The filter and interpolator states and the incomplete block are not compared, so equal
+/// instances can give different results once more frames are added, e.g. if one of them was
+/// seeded. As the measured values are floating point numbers, instances that measured NaN are
+/// never equal.
+impl PartialEq for EbuR128 {
+    fn eq(&self, other: &Self) -> bool {
+        self.measurement_snapshot() == other.measurement_snapshot()
+    }
+}
+
+/// Add interleaved samples to be processed, e.g. as the sink of an iterator pipeline.
+///
+/// All complete frames are processed before `extend` returns, samples of an incomplete frame are
+/// kept until the remaining samples of the frame are passed. Errors are stored and can be
+/// retrieved with [`EbuR128::take_error`](struct.EbuR128.html#method.take_error).
+impl Extend<f32> for EbuR128 {
+    fn extend<I: IntoIterator<Item = f32>>(&mut self, iter: I) {
+        self.extend_samples(iter.into_iter().map(f64::from));
+    }
+}
+
+/// Add interleaved samples to be processed, see the `Extend<f32>` implementation.
+impl Extend<f64> for EbuR128 {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, iter: I) {
+        self.extend_samples(iter.into_iter());
+    }
+}
+
+#[cfg(feature = "serde")]
+mod state {
+    use super::{
+        validate_channel_map, Channel, EbuR128, Error, InvalidSamplePolicy, Mode,
+        SegmentMeasurement, DEFAULT_PROGRESS_INTERVAL, MAX_CHANNELS, MAX_RATE,
+    };
+    use crate::filter::Filter;
+    use crate::history::{ChannelHistory, History};
+
+    use alloc::collections::VecDeque;
+    use alloc::{boxed::Box, vec::Vec};
+    use core::convert::TryFrom;
+
+    /// Version of the serialized state. Must be increased on every incompatible change.
+    const STATE_VERSION: u32 = 6;
+
+    #[derive(serde::Serialize)]
+    struct StateRef<'a> {
+        version: u32,
+        mode: u8,
+        rate: u32,
+        channels: u32,
+        audio_data: &'a [f64],
+        audio_data_index: usize,
+        needed_frames: usize,
+        channel_map: &'a [Channel],
+        channel_weights: &'a [Option<f64>],
+        samples_in_100ms: usize,
+        filter: &'a Filter,
+        block_energy_history: &'a History,
+        channel_energy_history: Option<&'a ChannelHistory>,
+        short_term_block_energy_history: &'a History,
+        short_term_frame_counter: usize,
+        sample_peak: &'a [f64],
+        true_peak: &'a [f64],
+        window: usize,
+        history: usize,
+        frames_processed: u64,
+        gating_blocks: u64,
+        gating_blocks_above_absolute_threshold: u64,
+        segment_energies: &'a VecDeque<f64>,
+        window_energies: &'a VecDeque<f64>,
+        window_energies_dropped: u64,
+        momentary_max: Option<f64>,
+        shortterm_max: Option<f64>,
+        flushed: bool,
+        pending_samples: &'a [f64],
+        segment_measurement: Option<&'a SegmentMeasurement>,
+        invalid_sample_policy: InvalidSamplePolicy,
+        sanitized_samples: u64,
+        histogram_resolution: u32,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct State {
+        version: u32,
+        mode: u8,
+        rate: u32,
+        channels: u32,
+        audio_data: Box<[f64]>,
+        audio_data_index: usize,
+        needed_frames: usize,
+        channel_map: Box<[Channel]>,
+        channel_weights: Box<[Option<f64>]>,
+        samples_in_100ms: usize,
+        filter: Filter,
+        block_energy_history: History,
+        channel_energy_history: Option<ChannelHistory>,
+        short_term_block_energy_history: History,
+        short_term_frame_counter: usize,
+        sample_peak: Box<[f64]>,
+        true_peak: Box<[f64]>,
+        window: usize,
+        history: usize,
+        frames_processed: u64,
+        gating_blocks: u64,
+        gating_blocks_above_absolute_threshold: u64,
+        segment_energies: VecDeque<f64>,
+        window_energies: VecDeque<f64>,
+        window_energies_dropped: u64,
+        momentary_max: Option<f64>,
+        shortterm_max: Option<f64>,
+        flushed: bool,
+        pending_samples: Vec<f64>,
+        #[serde(default)]
+        segment_measurement: Option<Box<SegmentMeasurement>>,
+        #[serde(default)]
+        invalid_sample_policy: InvalidSamplePolicy,
+        #[serde(default)]
+        sanitized_samples: u64,
+        histogram_resolution: u32,
+    }
+
+    impl TryFrom<State> for EbuR128 {
+        type Error = Error;
+
+        fn try_from(s: State) -> Result<Self, Error> {
+            if s.version != STATE_VERSION {
+                return Err(Error::IncompatibleState);
+            }
+
+            let mode = Mode::from_bits(s.mode).ok_or(Error::IncompatibleState)?;
+            if !mode.contains(Mode::M)
+                || s.channels == 0
+                || s.channels > MAX_CHANNELS
+                || !(16..=MAX_RATE).contains(&s.rate)
+                || s.samples_in_100ms != (s.rate as usize + 5) / 10
+            {
+                return Err(Error::IncompatibleState);
+            }
+
+            let audio_data_frames = EbuR128::allocate_audio_data(s.channels, s.rate, s.window)
+                .map_err(|_| Error::IncompatibleState)?
+                .len()
+                / s.channels as usize;
+
+            let use_histogram = mode.contains(Mode::HISTOGRAM);
+            if s.audio_data.len() != audio_data_frames * s.channels as usize
+                || s.audio_data_index >= audio_data_frames
+                || s.needed_frames == 0
+                || s.needed_frames > s.samples_in_100ms * 4
+                || s.short_term_frame_counter > s.samples_in_100ms * 30
+                || s.segment_energies.len() > 30
+                || s.window_energies.len() > core::cmp::max(s.history, s.window) / 100
+                || s.gating_blocks_above_absolute_threshold > s.gating_blocks
+                || s.pending_samples.len() >= s.channels as usize
+                || s.channel_map.len() != s.channels as usize
+                || validate_channel_map(s.channel_map.iter().copied()).is_err()
+                || s.channel_weights.len() != s.channels as usize
+                || s.channel_weights
+                    .iter()
+                    .flatten()
+                    .any(|weight| !(*weight >= 0.0 && weight.is_finite()))
+                || s.sample_peak.len() != s.channels as usize
+                || s.true_peak.len() != s.channels as usize
+                || !s.filter.is_compatible(
+                    s.rate,
+                    s.channels,
+                    s.filter.weighting(),
+                    mode.contains(Mode::SAMPLE_PEAK),
+                    mode.contains(Mode::TRUE_PEAK),
+                )
+                || !(crate::history::HISTOGRAM_RESOLUTION_RANGE.0
+                    ..=crate::history::HISTOGRAM_RESOLUTION_RANGE.1)
+                    .contains(&s.histogram_resolution)
+                || !s
+                    .block_energy_history
+                    .is_compatible(use_histogram, s.histogram_resolution)
+                || !s
+                    .short_term_block_energy_history
+                    .is_compatible(use_histogram, s.histogram_resolution)
+                || mode.contains(Mode::CHANNEL_I) != s.channel_energy_history.is_some()
+                || s.channel_energy_history.as_ref().is_some_and(|h| {
+                    !h.is_compatible(use_histogram, s.histogram_resolution, s.channels as usize)
+                })
+                || s.segment_measurement.as_ref().is_some_and(|m| {
+                    m.start_frame > s.frames_processed
+                        || m.sample_peak.len() != s.channels as usize
+                        || m.true_peak.len() != s.channels as usize
+                        || !m
+                            .block_energy_history
+                            .is_compatible(use_histogram, s.histogram_resolution)
+                        || !m
+                            .short_term_block_energy_history
+                            .is_compatible(use_histogram, s.histogram_resolution)
+                })
+            {
+                return Err(Error::IncompatibleState);
+            }
+
+            let mut ebur128 = EbuR128 {
+                mode,
+                rate: s.rate,
+                channels: s.channels,
+                audio_data: s.audio_data,
+                audio_data_index: s.audio_data_index,
+                needed_frames: s.needed_frames,
+                channel_map: s.channel_map,
+                channel_weights: s.channel_weights,
+                samples_in_100ms: s.samples_in_100ms,
+                filter: s.filter,
+                block_energy_history: s.block_energy_history,
+                channel_energy_history: s.channel_energy_history,
+                short_term_block_energy_history: s.short_term_block_energy_history,
+                short_term_frame_counter: s.short_term_frame_counter,
+                sample_peak: s.sample_peak,
+                true_peak: s.true_peak,
+                window: s.window,
+                history: s.history,
+                frames_processed: s.frames_processed,
+                gating_blocks: s.gating_blocks,
+                gating_blocks_above_absolute_threshold: s.gating_blocks_above_absolute_threshold,
+                segment_energies: s.segment_energies,
+                window_energies: s.window_energies,
+                window_energies_dropped: s.window_energies_dropped,
+                momentary_max: s.momentary_max,
+                shortterm_max: s.shortterm_max,
+                flushed: s.flushed,
+                dialogue_gate: None,
+                segment_measurement: s.segment_measurement,
+                progress_callback: None,
+                progress_interval: DEFAULT_PROGRESS_INTERVAL,
+                total_frames_hint: 0,
+                pending_samples: s.pending_samples,
+                last_error: None,
+                invalid_sample_policy: s.invalid_sample_policy,
+                sanitized_samples: s.sanitized_samples,
+                histogram_resolution: s.histogram_resolution,
+            };
+            ebur128.resize_window_energies();
+
+            Ok(ebur128)
+        }
+    }
+
+    impl serde::Serialize for EbuR128 {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            StateRef {
+                version: STATE_VERSION,
+                mode: self.mode.bits(),
+                rate: self.rate,
+                channels: self.channels,
+                audio_data: &self.audio_data,
+                audio_data_index: self.audio_data_index,
+                needed_frames: self.needed_frames,
+                channel_map: &self.channel_map,
+                channel_weights: &self.channel_weights,
+                samples_in_100ms: self.samples_in_100ms,
+                filter: &self.filter,
+                block_energy_history: &self.block_energy_history,
+                channel_energy_history: self.channel_energy_history.as_ref(),
+                short_term_block_energy_history: &self.short_term_block_energy_history,
+                short_term_frame_counter: self.short_term_frame_counter,
+                sample_peak: &self.sample_peak,
+                true_peak: &self.true_peak,
+                window: self.window,
+                history: self.history,
+                frames_processed: self.frames_processed,
+                gating_blocks: self.gating_blocks,
+                gating_blocks_above_absolute_threshold: self.gating_blocks_above_absolute_threshold,
+                segment_energies: &self.segment_energies,
+                window_energies: &self.window_energies,
+                window_energies_dropped: self.window_energies_dropped,
+                momentary_max: self.momentary_max,
+                shortterm_max: self.shortterm_max,
+                flushed: self.flushed,
+                pending_samples: &self.pending_samples,
+                segment_measurement: self.segment_measurement.as_deref(),
+                invalid_sample_policy: self.invalid_sample_policy,
+                sanitized_samples: self.sanitized_samples,
+                histogram_resolution: self.histogram_resolution,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> serde::Deserialize<'de> for EbuR128 {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let state = State::deserialize(deserializer)?;
+            EbuR128::try_from(state).map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+/// Get global integrated loudness in LUFS across multiple instances in histogram mode.
+///
+/// This is a thin wrapper around
+/// [`EbuR128::loudness_global_multiple`](struct.EbuR128.html#method.loudness_global_multiple)
+/// that takes a slice and only accepts instances created with `Mode::I | Mode::HISTOGRAM`, and
+/// fails with `Error::InvalidMode` otherwise. The associated function only requires `Mode::I` and
+/// also accepts instances that keep the block energies in a queue.
+///
+/// The histograms of all instances are combined before the absolute and relative gates are
+/// applied, so the result is the integrated loudness of all measured programmes together.
+pub fn loudness_global_multiple(states: &[&EbuR128]) -> Result<f64, Error> {
+    if states
+        .iter()
+        .any(|e| !e.mode.contains(Mode::I | Mode::HISTOGRAM))
+    {
+        return Err(Error::InvalidMode);
+    }
+
+    EbuR128::loudness_global_multiple(states.iter().copied())
+}
+
+/// Builder for [`EbuR128`](struct.EbuR128.html).
+///
+/// Validates the whole configuration in [`EbuR128Builder::build`](#method.build) so that either
+/// a fully configured instance or an error is returned.
+#[derive(Debug, Clone)]
+pub struct EbuR128Builder {
+    channels: u32,
+    rate: u32,
+    mode: Mode,
+    channel_map: Option<Vec<Channel>>,
+    max_window: Option<u32>,
+    max_history: Option<u32>,
+    weighting: Weighting,
+}
+
+impl Default for EbuR128Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EbuR128Builder {
+    /// Create a new builder. At least the number of channels, the sample rate and the mode have
+    /// to be set before calling [`EbuR128Builder::build`](#method.build).
+    pub fn new() -> Self {
+        EbuR128Builder {
+            channels: 0,
+            rate: 0,
+            mode: Mode::empty(),
+            channel_map: None,
+            max_window: None,
+            max_history: None,
+            weighting: Weighting::K,
+        }
+    }
+
+    /// Set the number of channels.
+    pub fn channels(mut self, channels: u32) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    /// Set the number of channels, same as [`EbuR128Builder::channels`](#method.channels).
+    pub fn with_channels(self, channels: u32) -> Self {
+        self.channels(channels)
+    }
+
+    /// Set the sample rate.
+    pub fn rate(mut self, rate: u32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Set the sample rate, same as [`EbuR128Builder::rate`](#method.rate).
+    pub fn with_rate(self, rate: u32) -> Self {
+        self.rate(rate)
+    }
+
+    /// Set the processing mode.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set the processing mode, same as [`EbuR128Builder::mode`](#method.mode).
+    pub fn with_mode(self, mode: Mode) -> Self {
+        self.mode(mode)
+    }
+
+    /// Set the channel map. Must have as many elements as there are channels.
+    ///
+    /// See [`EbuR128::set_channel_map`](struct.EbuR128.html#method.set_channel_map).
+    pub fn channel_map(mut self, channel_map: &[Channel]) -> Self {
+        self.channel_map = Some(channel_map.to_vec());
+        self
+    }
+
+    /// Set the maximum window duration in ms.
+    ///
+    /// See [`EbuR128::set_max_window`](struct.EbuR128.html#method.set_max_window).
+    pub fn max_window(mut self, window: u32) -> Self {
+        self.max_window = Some(window);
+        self
+    }
+
+    /// Set the maximum history in ms, e.g. to bound memory usage for long programs without
+    /// `Mode::HISTOGRAM`.
+    ///
+    /// See [`EbuR128::set_max_history`](struct.EbuR128.html#method.set_max_history).
+    pub fn max_history(mut self, history: u32) -> Self {
+        self.max_history = Some(history);
+        self
+    }
+
+    /// Set the maximum history in ms, like
+    /// [`EbuR128Builder::max_history`](#method.max_history). Values above `u32::MAX` are clamped.
+    pub fn with_history_ms(self, history: u64) -> Self {
+        self.max_history(core::cmp::min(history, u64::from(u32::MAX)) as u32)
+    }
+
+    /// Set the frequency weighting.
+    ///
+    /// See [`EbuR128::set_weighting`](struct.EbuR128.html#method.set_weighting).
+    pub fn weighting(mut self, weighting: Weighting) -> Self {
+        self.weighting = weighting;
+        self
+    }
+
+    /// Validate the configuration and create the [`EbuR128`](struct.EbuR128.html) instance.
+    ///
+    /// In addition to the checks done by [`EbuR128::new`](struct.EbuR128.html#method.new) this
+    /// fails with `Error::InvalidChannelIndex` if the channel map is invalid and with
+    /// `Error::InvalidMode` if the maximum window or history is shorter than required by the mode
+    /// (3000ms for `Mode::S`, 400ms for `Mode::M`) or if the maximum window exceeds the maximum
+    /// history.
+    pub fn build(self) -> Result<EbuR128, Error> {
+        let min_duration = if self.mode.contains(Mode::S) {
+            3000
+        } else {
+            400
+        };
+
+        if let Some(ref channel_map) = self.channel_map {
+            if channel_map.len() != self.channels as usize {
+                return Err(Error::InvalidChannelIndex);
+            }
+        }
+
+        if let Some(window) = self.max_window {
+            if window < min_duration {
+                return Err(Error::InvalidMode);
+            }
+        }
+
+        if let Some(history) = self.max_history {
+            if history < min_duration {
+                return Err(Error::InvalidMode);
+            }
+
+            if self.max_window.is_some_and(|window| window > history) {
+                return Err(Error::InvalidMode);
+            }
+        }
+
+        let mut ebur128 = EbuR128::with_defaults(self.channels, self.rate, self.mode)?;
+
+        if let Some(ref channel_map) = self.channel_map {
+            ebur128.set_channel_map(channel_map)?;
+        }
+
+        if let Some(window) = self.max_window {
+            ebur128.set_max_window(window)?;
+        }
+
+        if let Some(history) = self.max_history {
+            ebur128.set_max_history(history)?;
+        }
+
+        ebur128.set_weighting(self.weighting)?;
+
+        Ok(ebur128)
+    }
+}
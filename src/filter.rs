@@ -19,20 +19,29 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
-use std::fmt;
+use alloc::{boxed::Box, vec};
+use core::fmt;
 
-use crate::ebur128::Channel;
+use crate::ebur128::{Channel, Weighting, MAX_RATE};
+use crate::math;
 use crate::utils::Sample;
+use crate::Error;
 
 /// BS.1770 filter and optional sample/true peak measurement context.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Filter {
     channels: u32,
+    /// Selected frequency weighting.
+    weighting: Weighting,
     /// BS.1770 filter coefficients (numerator).
     b: [f64; 5],
     /// BS.1770 filter coefficients (denominator).
     a: [f64; 5],
     /// One filter state per channel.
     filter_state: Box<[[f64; 5]]>,
+    /// Second order section applied after the filter above for weightings of higher order.
+    post_filter: Option<PostFilter>,
 
     /// Whether to measure sample peak.
     calculate_sample_peak: bool,
@@ -49,9 +58,11 @@ impl fmt::Debug for Filter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Filter")
             .field("channels", &self.channels)
+            .field("weighting", &self.weighting)
             .field("b", &self.b)
             .field("a", &self.a)
             .field("filter_state", &self.filter_state)
+            .field("post_filter", &self.post_filter)
             .field("calculate_sample_peak", &self.calculate_sample_peak)
             .field("sample_peak", &self.sample_peak)
             .field("calculate_true_peak", &self.tp.is_some())
@@ -60,78 +71,321 @@ impl fmt::Debug for Filter {
     }
 }
 
+/// Energy weight of the channel type when summing up the channels.
+pub fn channel_weight(channel: Channel) -> f64 {
+    match channel {
+        Channel::Unused => 0.0,
+        Channel::LeftSurround
+        | Channel::RightSurround
+        | Channel::Mp060
+        | Channel::Mm060
+        | Channel::Mp090
+        | Channel::Mm090 => 1.41,
+        Channel::DualMono => 2.0,
+        Channel::DualMonoLeft | Channel::DualMonoRight => 0.5,
+        Channel::Left
+        | Channel::Right
+        | Channel::Center
+        | Channel::MpSC
+        | Channel::MmSC
+        | Channel::Mp135
+        | Channel::Mm135
+        | Channel::Mp180
+        | Channel::Up000
+        | Channel::Up030
+        | Channel::Um030
+        | Channel::Up045
+        | Channel::Um045
+        | Channel::Up090
+        | Channel::Um090
+        | Channel::Up110
+        | Channel::Um110
+        | Channel::Up135
+        | Channel::Um135
+        | Channel::Up180
+        | Channel::Tp000
+        | Channel::Bp000
+        | Channel::Bp045
+        | Channel::Bm045 => 1.0,
+    }
+}
+
+/// Minimum number of frames for filtering the channels in parallel.
+#[cfg(feature = "rayon")]
+pub(crate) const PARALLEL_MIN_FRAMES: usize = 4096;
+
+/// Coefficients of the two stages of the K-weighting filter of ITU BS.1770.
+///
+/// Both stages are second order sections with the coefficients normalized to `a[0] == 1`. The
+/// measurement runs both stages as one 4th order filter with the coefficients returned by
+/// [`KWeightingCoefficients::combined`](#method.combined).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KWeightingCoefficients {
+    /// High shelving filter coefficients (numerator).
+    pub high_shelf_b: [f64; 3],
+    /// High shelving filter coefficients (denominator).
+    pub high_shelf_a: [f64; 3],
+    /// RLB high-pass filter coefficients (numerator).
+    pub high_pass_b: [f64; 3],
+    /// RLB high-pass filter coefficients (denominator).
+    pub high_pass_a: [f64; 3],
+}
+
+impl KWeightingCoefficients {
+    /// Numerator and denominator of both stages combined into one 4th order filter, exactly as
+    /// used for the measurement.
+    pub fn combined(&self) -> ([f64; 5], [f64; 5]) {
+        let (pb, pa) = (&self.high_shelf_b, &self.high_shelf_a);
+        let (rb, ra) = (&self.high_pass_b, &self.high_pass_a);
+
+        (
+            // Numerator
+            [
+                pb[0] * rb[0],
+                pb[0] * rb[1] + pb[1] * rb[0],
+                pb[0] * rb[2] + pb[1] * rb[1] + pb[2] * rb[0],
+                pb[1] * rb[2] + pb[2] * rb[1],
+                pb[2] * rb[2],
+            ],
+            // Denominator
+            [
+                pa[0] * ra[0],
+                pa[0] * ra[1] + pa[1] * ra[0],
+                pa[0] * ra[2] + pa[1] * ra[1] + pa[2] * ra[0],
+                pa[1] * ra[2] + pa[2] * ra[1],
+                pa[2] * ra[2],
+            ],
+        )
+    }
+}
+
+/// Get the K-weighting filter coefficients for the sample rate.
+///
+/// These are the coefficients used by [`EbuR128`](struct.EbuR128.html) with `Weighting::K`.
+///
+/// Fails with `Error::NoMem` for sample rates that are not supported by
+/// [`EbuR128::new`](struct.EbuR128.html#method.new).
+pub fn filter_coefficients(rate: u32) -> Result<KWeightingCoefficients, Error> {
+    if !(16..=MAX_RATE).contains(&rate) {
+        return Err(Error::NoMem);
+    }
+
+    Ok(k_weighting_coefficients(rate as f64))
+}
+
+/// Get the response of the K-weighting filter in dB at the frequency for the sample rate.
+///
+/// This evaluates the transfer function of the combined filter, e.g. it gives about 0.7 dB at
+/// 1kHz and 4 dB at high frequencies.
+///
+/// Fails with `Error::NoMem` for sample rates that are not supported by
+/// [`EbuR128::new`](struct.EbuR128.html#method.new).
+pub fn response_db(rate: u32, freq_hz: f64) -> Result<f64, Error> {
+    let (b, a) = filter_coefficients(rate)?.combined();
+
+    let omega = 2.0 * core::f64::consts::PI * freq_hz / rate as f64;
+    let evaluate = |p: &[f64; 5]| {
+        let (mut re, mut im) = (0.0, 0.0);
+        for (k, p) in p.iter().enumerate() {
+            re += p * math::cos(k as f64 * omega);
+            im -= p * math::sin(k as f64 * omega);
+        }
+        re * re + im * im
+    };
+
+    Ok(10.0 * math::log10(evaluate(&b) / evaluate(&a)))
+}
+
 #[allow(non_snake_case)]
-fn filter_coefficients(rate: f64) -> ([f64; 5], [f64; 5]) {
+fn k_weighting_coefficients(rate: f64) -> KWeightingCoefficients {
     let f0 = 1681.974450955533;
     let G = 3.999843853973347;
     let Q = 0.7071752369554196;
 
-    let K = f64::tan(std::f64::consts::PI * f0 / rate);
-    let Vh = f64::powf(10.0, G / 20.0);
-    let Vb = f64::powf(Vh, 0.4996667741545416);
+    let K = math::tan(core::f64::consts::PI * f0 / rate);
+    let Vh = math::powf(10.0, G / 20.0);
+    let Vb = math::powf(Vh, 0.4996667741545416);
 
     let mut pb = [0.0, 0.0, 0.0];
     let mut pa = [1.0, 0.0, 0.0];
-    let rb = [1.0, -2.0, 1.0];
+    let mut rb = [1.0, -2.0, 1.0];
     let mut ra = [1.0, 0.0, 0.0];
 
-    let a0 = 1.0 + K / Q + K * K;
-    pb[0] = (Vh + Vb * K / Q + K * K) / a0;
-    pb[1] = 2.0 * (K * K - Vh) / a0;
-    pb[2] = (Vh - Vb * K / Q + K * K) / a0;
-    pa[1] = 2.0 * (K * K - 1.0) / a0;
-    pa[2] = (1.0 - K / Q + K * K) / a0;
+    // Below about 3.4kHz the shelf is above the Nyquist frequency and the filter would be
+    // unstable, so leave out this stage as there are no frequencies it could boost anyway
+    if f0 < rate / 2.0 {
+        let a0 = 1.0 + K / Q + K * K;
+        pb[0] = (Vh + Vb * K / Q + K * K) / a0;
+        pb[1] = 2.0 * (K * K - Vh) / a0;
+        pb[2] = (Vh - Vb * K / Q + K * K) / a0;
+        pa[1] = 2.0 * (K * K - 1.0) / a0;
+        pa[2] = (1.0 - K / Q + K * K) / a0;
+    } else {
+        pb[0] = 1.0;
+    }
 
     let f0 = 38.13547087602444;
     let Q = 0.5003270373238773;
-    let K = f64::tan(std::f64::consts::PI * f0 / rate);
-
-    ra[1] = 2.0 * (K * K - 1.0) / (1.0 + K / Q + K * K);
-    ra[2] = (1.0 - K / Q + K * K) / (1.0 + K / Q + K * K);
-
-    (
-        // Numerator
-        [
-            pb[0] * rb[0],
-            pb[0] * rb[1] + pb[1] * rb[0],
-            pb[0] * rb[2] + pb[1] * rb[1] + pb[2] * rb[0],
-            pb[1] * rb[2] + pb[2] * rb[1],
-            pb[2] * rb[2],
-        ],
-        // Denominator
-        [
-            pa[0] * ra[0],
-            pa[0] * ra[1] + pa[1] * ra[0],
-            pa[0] * ra[2] + pa[1] * ra[1] + pa[2] * ra[0],
-            pa[1] * ra[2] + pa[2] * ra[1],
-            pa[2] * ra[2],
-        ],
-    )
+    let K = math::tan(core::f64::consts::PI * f0 / rate);
+
+    // Same for the high-pass below about 76Hz
+    if f0 < rate / 2.0 {
+        ra[1] = 2.0 * (K * K - 1.0) / (1.0 + K / Q + K * K);
+        ra[2] = (1.0 - K / Q + K * K) / (1.0 + K / Q + K * K);
+    } else {
+        rb = [1.0, 0.0, 0.0];
+    }
+
+    KWeightingCoefficients {
+        high_shelf_b: pb,
+        high_shelf_a: pa,
+        high_pass_b: rb,
+        high_pass_a: ra,
+    }
+}
+
+/// Second order section with one filter state per channel.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct PostFilter {
+    /// Filter coefficients (numerator).
+    b: [f64; 3],
+    /// Filter coefficients (denominator).
+    a: [f64; 3],
+    /// One filter state per channel.
+    filter_state: Box<[[f64; 3]]>,
+}
+
+impl PostFilter {
+    #[inline]
+    fn process(b: &[f64; 3], a: &[f64; 3], filter_state: &mut [f64; 3], src: f64) -> f64 {
+        filter_state[0] = src - a[1] * filter_state[1] - a[2] * filter_state[2];
+        let dest = b[0] * filter_state[0] + b[1] * filter_state[1] + b[2] * filter_state[2];
+
+        filter_state[2] = filter_state[1];
+        filter_state[1] = filter_state[0];
+
+        dest
+    }
+}
+
+/// Multiplies out `(1 - r z^-1)` for all `roots` into `p`, which has one element more.
+fn polynomial_from_roots(roots: &[f64], p: &mut [f64]) {
+    assert!(p.len() == roots.len() + 1);
+
+    p.fill(0.0);
+    p[0] = 1.0;
+    for (i, root) in roots.iter().enumerate() {
+        for j in (1..=i + 1).rev() {
+            p[j] -= root * p[j - 1];
+        }
+    }
+}
+
+/// IEC 61672-1 A-weighting as 4th order filter followed by a second order section.
+///
+/// The analog filter is transformed with the bilinear transform and normalized to unity gain at
+/// 1kHz.
+#[allow(clippy::type_complexity)]
+fn a_weighting_coefficients(rate: f64) -> (([f64; 5], [f64; 5]), ([f64; 3], [f64; 3])) {
+    let f1 = 20.598997;
+    let f2 = 107.65265;
+    let f3 = 737.86223;
+    let f4 = 12194.217;
+
+    // Poles of the analog filter mapped to the z-plane. The four zeros at DC map to z = 1, the
+    // two zeros at infinity to z = -1.
+    let pole = |f: f64| {
+        let w = 2.0 * core::f64::consts::PI * f;
+        (2.0 * rate - w) / (2.0 * rate + w)
+    };
+    let (p1, p2, p3, p4) = (pole(f1), pole(f2), pole(f3), pole(f4));
+
+    let mut b = [0.0; 5];
+    let mut a = [0.0; 5];
+    polynomial_from_roots(&[1.0, 1.0, -1.0, -1.0], &mut b);
+    polynomial_from_roots(&[p1, p1, p4, p4], &mut a);
+
+    let mut post_b = [0.0; 3];
+    let mut post_a = [0.0; 3];
+    polynomial_from_roots(&[1.0, 1.0], &mut post_b);
+    polynomial_from_roots(&[p2, p3], &mut post_a);
+
+    // Magnitude of the response at 1kHz, if representable at this samplerate
+    if rate > 2000.0 {
+        let theta = 2.0 * core::f64::consts::PI * 1000.0 / rate;
+        let (sin, cos) = (math::sin(theta), math::cos(theta));
+        let magnitude = |roots: &[f64]| {
+            roots
+                .iter()
+                .map(|r| {
+                    let re = 1.0 - r * cos;
+                    let im = r * sin;
+                    re * re + im * im
+                })
+                .product::<f64>()
+        };
+
+        let gain = math::sqrt(
+            magnitude(&[p1, p1, p4, p4, p2, p3]) / magnitude(&[1.0, 1.0, -1.0, -1.0, 1.0, 1.0]),
+        );
+        b.iter_mut().for_each(|b| *b *= gain);
+    }
+
+    ((b, a), (post_b, post_a))
+}
+
+/// Set filter states that are too small to matter to zero, so that they don't decay into denormals
+/// and slow down filtering. Only needed if the CPU doesn't flush denormals to zero.
+fn flush_denormal_state(filter_state: &mut [f64; 5]) {
+    for v in filter_state {
+        if v.abs() < f64::EPSILON {
+            *v = 0.0;
+        }
+    }
+}
+
+/// Number of channels that the filter processes at once with the implementation selected at
+/// runtime: 4 with AVX2, 2 with SSE2/NEON and 1 if SIMD is disabled.
+///
+/// Only for benchmarks and tests that have to make sure that the expected implementation is used.
+#[doc(hidden)]
+pub fn simd_filter_channels() -> usize {
+    if !crate::utils::simd_enabled() {
+        1
+    } else if simd::is_available() {
+        4
+    } else {
+        2
+    }
 }
 
 impl Filter {
     pub fn new(
         rate: u32,
         channels: u32,
+        weighting: Weighting,
         calculate_sample_peak: bool,
         calculate_true_peak: bool,
     ) -> Self {
         assert!(rate > 0);
         assert!(channels > 0);
 
-        let (b, a) = filter_coefficients(rate as f64);
+        let ((b, a), post_filter) = Self::weighting_filters(rate, channels, weighting);
 
         let tp = if calculate_true_peak {
-            crate::true_peak::TruePeak::new(rate, channels)
+            crate::true_peak::TruePeak::new(rate, channels).ok()
         } else {
             None
         };
 
         Filter {
             channels,
+            weighting,
             b,
             a,
             filter_state: vec![[0.0; 5]; channels as usize].into_boxed_slice(),
+            post_filter,
             calculate_sample_peak,
             sample_peak: vec![0.0; channels as usize].into_boxed_slice(),
             tp,
@@ -139,21 +393,135 @@ impl Filter {
         }
     }
 
+    #[allow(clippy::type_complexity)]
+    fn weighting_filters(
+        rate: u32,
+        channels: u32,
+        weighting: Weighting,
+    ) -> (([f64; 5], [f64; 5]), Option<PostFilter>) {
+        match weighting {
+            Weighting::K => (k_weighting_coefficients(rate as f64).combined(), None),
+            Weighting::A => {
+                let (coefficients, (b, a)) = a_weighting_coefficients(rate as f64);
+                (
+                    coefficients,
+                    Some(PostFilter {
+                        b,
+                        a,
+                        filter_state: vec![[0.0; 3]; channels as usize].into_boxed_slice(),
+                    }),
+                )
+            }
+            Weighting::None => {
+                let identity = [1.0, 0.0, 0.0, 0.0, 0.0];
+                ((identity, identity), None)
+            }
+        }
+    }
+
+    pub fn weighting(&self) -> Weighting {
+        self.weighting
+    }
+
+    /// Select a different weighting. This resets the filter states.
+    pub fn set_weighting(&mut self, rate: u32, weighting: Weighting) {
+        let ((b, a), post_filter) = Self::weighting_filters(rate, self.channels, weighting);
+
+        self.weighting = weighting;
+        self.b = b;
+        self.a = a;
+        self.post_filter = post_filter;
+        self.filter_state.iter_mut().for_each(|f| f.fill(0.0));
+    }
+
     pub fn reset_peaks(&mut self) {
         self.sample_peak.fill(0.0);
         self.true_peak.fill(0.0);
     }
 
+    /// Reset the sample and true peak maxima of one channel, including the ones of the true peak
+    /// analyzer.
+    pub fn reset_channel_peaks(&mut self, channel: usize) {
+        self.sample_peak[channel] = 0.0;
+        self.true_peak[channel] = 0.0;
+        if let Some(ref mut tp) = self.tp {
+            tp.reset_channel_peak(channel);
+        }
+    }
+
     pub fn reset(&mut self) {
         self.reset_peaks();
 
         self.filter_state.iter_mut().for_each(|f| f.fill(0.0));
+        if let Some(ref mut post_filter) = self.post_filter {
+            post_filter
+                .filter_state
+                .iter_mut()
+                .for_each(|f| f.fill(0.0));
+        }
 
         if let Some(ref mut tp) = self.tp {
             tp.reset();
         }
     }
 
+    /// Check if the (deserialized) state matches the given configuration.
+    #[cfg(feature = "serde")]
+    pub fn is_compatible(
+        &self,
+        rate: u32,
+        channels: u32,
+        weighting: Weighting,
+        calculate_sample_peak: bool,
+        calculate_true_peak: bool,
+    ) -> bool {
+        let tp_compatible = match self.tp {
+            Some(ref tp) => calculate_true_peak && tp.is_compatible(rate, channels),
+            None => !calculate_true_peak,
+        };
+
+        let post_filter_compatible = match self.post_filter {
+            Some(ref post_filter) => {
+                weighting == Weighting::A && post_filter.filter_state.len() == channels as usize
+            }
+            None => weighting != Weighting::A,
+        };
+
+        self.channels == channels
+            && self.weighting == weighting
+            && self.filter_state.len() == channels as usize
+            && post_filter_compatible
+            && self.calculate_sample_peak == calculate_sample_peak
+            && self.sample_peak.len() == channels as usize
+            && self.true_peak.len() == channels as usize
+            && tp_compatible
+    }
+
+    /// The true peak analyzer, if true peaks are calculated.
+    /// Number of bytes allocated for the filter states and peaks, without the true peak analyzer.
+    pub fn heap_size(&self) -> usize {
+        self.filter_state.len() * core::mem::size_of::<[f64; 5]>()
+            + self.post_filter.as_ref().map_or(0, |post_filter| {
+                post_filter.filter_state.len() * core::mem::size_of::<[f64; 3]>()
+            })
+            + (self.sample_peak.len() + self.true_peak.len()) * core::mem::size_of::<f64>()
+    }
+
+    pub fn true_peak_analyzer(&self) -> Option<&crate::true_peak::TruePeak> {
+        self.tp.as_ref()
+    }
+
+    pub fn true_peak_analyzer_mut(&mut self) -> Option<&mut crate::true_peak::TruePeak> {
+        self.tp.as_mut()
+    }
+
+    /// Drain the true peak interpolator. The resulting peaks are available via `true_peak()`.
+    pub fn flush_true_peak(&mut self) {
+        if let Some(ref mut tp) = self.tp {
+            tp.flush_into(&mut self.true_peak);
+        }
+    }
+
     pub fn sample_peak(&self) -> &[f64] {
         &self.sample_peak
     }
@@ -169,7 +537,7 @@ impl Filter {
         dest_index: usize,
         channel_map: &[crate::ebur128::Channel],
     ) {
-        assert!(dest.len() % self.channels as usize == 0);
+        assert!(dest.len().is_multiple_of(self.channels as usize));
         assert!(channel_map.len() == self.channels as usize);
         assert!(src.channels() == self.channels as usize);
         assert!(self.filter_state.len() == self.channels as usize);
@@ -200,45 +568,117 @@ impl Filter {
             let dest_stride = dest.len() / self.channels as usize;
             assert!(dest_index + src.frames() <= dest_stride);
 
-            for (c, (channel_map, dest)) in
-                Iterator::zip(channel_map.iter(), dest.chunks_exact_mut(dest_stride)).enumerate()
-            {
-                if *channel_map == crate::ebur128::Channel::Unused {
-                    continue;
+            // Filter groups of channels at once if possible, the remaining ones below
+            let mut simd_channels = 0;
+            if crate::utils::simd_enabled() {
+                simd_channels = simd::process(
+                    &src,
+                    &self.a,
+                    &self.b,
+                    &mut self.filter_state,
+                    dest,
+                    dest_index,
+                    channel_map,
+                    ftz.is_none(),
+                );
+                simd_channels |= pairs::process(
+                    &src,
+                    &self.a,
+                    &self.b,
+                    &mut self.filter_state,
+                    dest,
+                    dest_index,
+                    channel_map,
+                    simd_channels,
+                    ftz.is_none(),
+                );
+            }
+
+            let Filter {
+                ref mut filter_state,
+                ref a,
+                ref b,
+                ..
+            } = *self;
+            let process_channel = |c: usize,
+                                   channel_map: &crate::ebur128::Channel,
+                                   dest: &mut [f64],
+                                   filter_state: &mut [f64; 5],
+                                   flush_denormals: bool| {
+                if *channel_map == crate::ebur128::Channel::Unused || simd_channels & (1 << c) != 0
+                {
+                    return;
                 }
 
-                assert!(c < src.channels());
+                Self::process_channel(
+                    &src,
+                    c,
+                    a,
+                    b,
+                    filter_state,
+                    &mut dest[dest_index..],
+                    flush_denormals,
+                );
+            };
 
-                let Filter {
+            #[cfg(feature = "rayon")]
+            let parallel = src.frames() >= PARALLEL_MIN_FRAMES;
+            #[cfg(not(feature = "rayon"))]
+            let parallel = false;
+
+            if parallel {
+                // Flushing denormals has to be enabled in each thread separately
+                #[cfg(feature = "rayon")]
+                {
+                    use rayon::prelude::*;
+
+                    channel_map
+                        .par_iter()
+                        .zip(dest.par_chunks_exact_mut(dest_stride))
+                        .zip(filter_state.par_iter_mut())
+                        .enumerate()
+                        .for_each(|(c, ((channel_map, dest), filter_state))| {
+                            ftz::with_ftz(|ftz| {
+                                process_channel(c, channel_map, dest, filter_state, ftz.is_none())
+                            })
+                        });
+                }
+            } else {
+                for (c, ((channel_map, dest), filter_state)) in channel_map
+                    .iter()
+                    .zip(dest.chunks_exact_mut(dest_stride))
+                    .zip(filter_state.iter_mut())
+                    .enumerate()
+                {
+                    process_channel(c, channel_map, dest, filter_state, ftz.is_none());
+                }
+            }
+
+            if let Some(ref mut post_filter) = self.post_filter {
+                let PostFilter {
                     ref mut filter_state,
                     ref a,
                     ref b,
-                    ..
-                } = *self;
-                let filter_state = &mut filter_state[c];
+                } = *post_filter;
 
-                src.foreach_sample_zipped(c, dest[dest_index..].iter_mut(), |src, dest| {
-                    filter_state[0] = (*src).to_sample::<f64>()
-                        - a[1] * filter_state[1]
-                        - a[2] * filter_state[2]
-                        - a[3] * filter_state[3]
-                        - a[4] * filter_state[4];
-                    *dest = b[0] * filter_state[0]
-                        + b[1] * filter_state[1]
-                        + b[2] * filter_state[2]
-                        + b[3] * filter_state[3]
-                        + b[4] * filter_state[4];
-
-                    filter_state[4] = filter_state[3];
-                    filter_state[3] = filter_state[2];
-                    filter_state[2] = filter_state[1];
-                    filter_state[1] = filter_state[0];
-                });
+                for ((channel_map, dest), filter_state) in channel_map
+                    .iter()
+                    .zip(dest.chunks_exact_mut(dest_stride))
+                    .zip(filter_state.iter_mut())
+                {
+                    if *channel_map == crate::ebur128::Channel::Unused {
+                        continue;
+                    }
 
-                if ftz.is_none() {
-                    for v in filter_state {
-                        if v.abs() < f64::EPSILON {
-                            *v = 0.0;
+                    for dest in &mut dest[dest_index..dest_index + src.frames()] {
+                        *dest = PostFilter::process(b, a, filter_state, *dest);
+                    }
+
+                    if ftz.is_none() {
+                        for v in filter_state {
+                            if v.abs() < f64::EPSILON {
+                                *v = 0.0;
+                            }
                         }
                     }
                 }
@@ -251,6 +691,41 @@ impl Filter {
         });
     }
 
+    /// Filter one channel with the scalar implementation.
+    fn process_channel<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
+        src: &S,
+        channel: usize,
+        a: &[f64; 5],
+        b: &[f64; 5],
+        filter_state: &mut [f64; 5],
+        dest: &mut [f64],
+        flush_denormals: bool,
+    ) {
+        assert!(channel < src.channels());
+
+        src.foreach_sample_zipped(channel, dest.iter_mut(), |src, dest| {
+            filter_state[0] = src.to_f64()
+                - a[1] * filter_state[1]
+                - a[2] * filter_state[2]
+                - a[3] * filter_state[3]
+                - a[4] * filter_state[4];
+            *dest = b[0] * filter_state[0]
+                + b[1] * filter_state[1]
+                + b[2] * filter_state[2]
+                + b[3] * filter_state[3]
+                + b[4] * filter_state[4];
+
+            filter_state[4] = filter_state[3];
+            filter_state[3] = filter_state[2];
+            filter_state[2] = filter_state[1];
+            filter_state[1] = filter_state[0];
+        });
+
+        if flush_denormals {
+            flush_denormal_state(filter_state);
+        }
+    }
+
     pub fn seed<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
         &mut self,
         src: S,
@@ -271,22 +746,64 @@ impl Filter {
                 let Filter {
                     ref mut filter_state,
                     ref a,
+                    ref b,
+                    ref mut post_filter,
                     ..
                 } = *self;
                 let filter_state = &mut filter_state[c];
 
-                src.foreach_sample(c, |src| {
-                    filter_state[0] = (*src).to_sample::<f64>()
-                        - a[1] * filter_state[1]
-                        - a[2] * filter_state[2]
-                        - a[3] * filter_state[3]
-                        - a[4] * filter_state[4];
-
-                    filter_state[4] = filter_state[3];
-                    filter_state[3] = filter_state[2];
-                    filter_state[2] = filter_state[1];
-                    filter_state[1] = filter_state[0];
-                });
+                match post_filter {
+                    // The second order section needs the output of the first filter
+                    Some(ref mut post_filter) => {
+                        let PostFilter {
+                            filter_state: ref mut post_filter_state,
+                            a: ref post_a,
+                            b: ref post_b,
+                        } = *post_filter;
+                        let post_filter_state = &mut post_filter_state[c];
+
+                        src.foreach_sample(c, |src| {
+                            filter_state[0] = src.to_f64()
+                                - a[1] * filter_state[1]
+                                - a[2] * filter_state[2]
+                                - a[3] * filter_state[3]
+                                - a[4] * filter_state[4];
+                            let dest = b[0] * filter_state[0]
+                                + b[1] * filter_state[1]
+                                + b[2] * filter_state[2]
+                                + b[3] * filter_state[3]
+                                + b[4] * filter_state[4];
+                            PostFilter::process(post_b, post_a, post_filter_state, dest);
+
+                            filter_state[4] = filter_state[3];
+                            filter_state[3] = filter_state[2];
+                            filter_state[2] = filter_state[1];
+                            filter_state[1] = filter_state[0];
+                        });
+
+                        if ftz.is_none() {
+                            for v in post_filter_state {
+                                if v.abs() < f64::EPSILON {
+                                    *v = 0.0;
+                                }
+                            }
+                        }
+                    }
+                    None => {
+                        src.foreach_sample(c, |src| {
+                            filter_state[0] = src.to_f64()
+                                - a[1] * filter_state[1]
+                                - a[2] * filter_state[2]
+                                - a[3] * filter_state[3]
+                                - a[4] * filter_state[4];
+
+                            filter_state[4] = filter_state[3];
+                            filter_state[3] = filter_state[2];
+                            filter_state[2] = filter_state[1];
+                            filter_state[1] = filter_state[0];
+                        });
+                    }
+                }
 
                 if ftz.is_none() {
                     for v in filter_state {
@@ -309,67 +826,563 @@ impl Filter {
         audio_data: &[f64],
         audio_data_index: usize,
         channel_map: &[Channel],
+        channel_weights: &[Option<f64>],
+    ) -> f64 {
+        Self::calc_gating_block_with_channels(
+            frames_per_block,
+            audio_data,
+            audio_data_index,
+            channel_map,
+            channel_weights,
+            |_, _| (),
+        )
+    }
+
+    /// Like `calc_gating_block` but additionally calls `channel_energy` with the weighted energy
+    /// of each used channel.
+    pub fn calc_gating_block_with_channels(
+        frames_per_block: usize,
+        audio_data: &[f64],
+        audio_data_index: usize,
+        channel_map: &[Channel],
+        channel_weights: &[Option<f64>],
+        mut channel_energy: impl FnMut(usize, f64),
     ) -> f64 {
         let mut sum = 0.0;
 
         let channels = channel_map.len();
-        assert!(audio_data.len() % channels == 0);
+        assert!(channel_weights.len() == channels);
+        assert!(audio_data.len().is_multiple_of(channels));
         let audio_data_stride = audio_data.len() / channels;
         assert!(audio_data_index <= audio_data_stride);
 
-        for (c, (channel, audio_data)) in Iterator::zip(
-            channel_map.iter(),
-            audio_data.chunks_exact(audio_data_stride),
-        )
-        .enumerate()
+        // The frames of the block, which might wrap around at the end of the buffer
+        let block = if audio_data_index < frames_per_block {
+            [
+                0..audio_data_index,
+                (audio_data_stride - frames_per_block + audio_data_index)..audio_data_stride,
+            ]
+        } else {
+            [audio_data_index - frames_per_block..audio_data_index, 0..0]
+        };
+
+        // Energy of the next channel if it was calculated together with the current one
+        let mut next_channel_sum = None;
+        let simd = crate::utils::simd_enabled();
+
+        for (c, (channel, channel_weight)) in
+            Iterator::zip(channel_map.iter(), channel_weights.iter()).enumerate()
         {
             if *channel == Channel::Unused {
                 continue;
             }
 
             assert!(c < channels);
-            assert!(audio_data_index <= audio_data.len());
-
-            let mut channel_sum = 0.0;
+            let channel_data = &audio_data[c * audio_data_stride..(c + 1) * audio_data_stride];
 
             // XXX: Don't use channel_sum += sum() here because that gives slightly different
             // results than the C version because of rounding errors
-            if audio_data_index < frames_per_block {
-                for frame in &audio_data[..audio_data_index] {
-                    channel_sum += *frame * *frame;
+            let mut channel_sum = match next_channel_sum.take() {
+                Some(channel_sum) => channel_sum,
+                // Sum up pairs of used channels at once if possible
+                None if simd && c + 1 < channels && channel_map[c + 1] != Channel::Unused => {
+                    let next_channel_data =
+                        &audio_data[(c + 1) * audio_data_stride..(c + 2) * audio_data_stride];
+                    let [channel_sum, next_sum] =
+                        pairs::sum_squares([channel_data, next_channel_data], &block);
+                    next_channel_sum = Some(next_sum);
+                    channel_sum
                 }
+                None => {
+                    let mut channel_sum = 0.0;
+                    for range in &block {
+                        for frame in &channel_data[range.clone()] {
+                            channel_sum += *frame * *frame;
+                        }
+                    }
+                    channel_sum
+                }
+            };
 
-                for frame in &audio_data[(audio_data.len() - frames_per_block + audio_data_index)..]
-                {
-                    channel_sum += *frame * *frame;
+            channel_sum *= channel_weight.unwrap_or_else(|| self::channel_weight(*channel));
+
+            channel_energy(c, channel_sum / frames_per_block as f64);
+            sum += channel_sum;
+        }
+
+        sum /= frames_per_block as f64;
+
+        sum
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod simd {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    use crate::ebur128::Channel;
+    use crate::utils::Sample;
+
+    /// Whether AVX2 is enabled at compile-time or, with the `std` feature, supported by the CPU.
+    #[inline]
+    pub fn is_available() -> bool {
+        #[cfg(target_feature = "avx2")]
+        {
+            true
+        }
+        #[cfg(all(not(target_feature = "avx2"), feature = "std"))]
+        {
+            std::is_x86_feature_detected!("avx2")
+        }
+        #[cfg(all(not(target_feature = "avx2"), not(feature = "std")))]
+        {
+            false
+        }
+    }
+
+    /// Filter groups of four used channels with AVX2 if available and return a bitmask of the
+    /// filtered channels.
+    ///
+    /// Each lane does exactly the same operations in the same order as the scalar implementation,
+    /// so the results are identical.
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    pub fn process<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
+        src: &S,
+        a: &[f64; 5],
+        b: &[f64; 5],
+        filter_state: &mut [[f64; 5]],
+        dest: &mut [f64],
+        dest_index: usize,
+        channel_map: &[Channel],
+        flush_denormals: bool,
+    ) -> u64 {
+        if !is_available() {
+            return 0;
+        }
+
+        // Safety: AVX2 is available
+        unsafe {
+            process_avx2(
+                src,
+                a,
+                b,
+                filter_state,
+                dest,
+                dest_index,
+                channel_map,
+                flush_denormals,
+            )
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn process_avx2<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
+        src: &S,
+        a: &[f64; 5],
+        b: &[f64; 5],
+        filter_state: &mut [[f64; 5]],
+        dest: &mut [f64],
+        dest_index: usize,
+        channel_map: &[Channel],
+        flush_denormals: bool,
+    ) -> u64 {
+        let channels = channel_map.len();
+        let dest_stride = dest.len() / channels;
+        let mut filtered = 0;
+
+        let mut c = 0;
+        while c + 4 <= channels {
+            if channel_map[c..c + 4].contains(&Channel::Unused) {
+                c += 1;
+                continue;
+            }
+
+            let (d0, d) = dest[c * dest_stride..(c + 4) * dest_stride].split_at_mut(dest_stride);
+            let (d1, d) = d.split_at_mut(dest_stride);
+            let (d2, d3) = d.split_at_mut(dest_stride);
+            let dest = d0[dest_index..]
+                .iter_mut()
+                .zip(&mut d1[dest_index..])
+                .zip(&mut d2[dest_index..])
+                .zip(&mut d3[dest_index..]);
+
+            let filter_state = &mut filter_state[c..c + 4];
+
+            // Safety: AVX2 is enabled for this function
+            unsafe {
+                let a = a.map(|a| _mm256_set1_pd(a));
+                let b = b.map(|b| _mm256_set1_pd(b));
+                let mut state = [0, 1, 2, 3, 4].map(|i| {
+                    _mm256_setr_pd(
+                        filter_state[0][i],
+                        filter_state[1][i],
+                        filter_state[2][i],
+                        filter_state[3][i],
+                    )
+                });
+
+                src.foreach_sample4_zipped(c, dest, |src, (((d0, d1), d2), d3)| {
+                    let src = src.map(|s| s.to_f64());
+                    state[0] = _mm256_sub_pd(
+                        _mm256_sub_pd(
+                            _mm256_sub_pd(
+                                _mm256_sub_pd(
+                                    _mm256_loadu_pd(src.as_ptr()),
+                                    _mm256_mul_pd(a[1], state[1]),
+                                ),
+                                _mm256_mul_pd(a[2], state[2]),
+                            ),
+                            _mm256_mul_pd(a[3], state[3]),
+                        ),
+                        _mm256_mul_pd(a[4], state[4]),
+                    );
+                    let out = _mm256_add_pd(
+                        _mm256_add_pd(
+                            _mm256_add_pd(
+                                _mm256_add_pd(
+                                    _mm256_mul_pd(b[0], state[0]),
+                                    _mm256_mul_pd(b[1], state[1]),
+                                ),
+                                _mm256_mul_pd(b[2], state[2]),
+                            ),
+                            _mm256_mul_pd(b[3], state[3]),
+                        ),
+                        _mm256_mul_pd(b[4], state[4]),
+                    );
+
+                    let mut tmp = [0.0; 4];
+                    _mm256_storeu_pd(tmp.as_mut_ptr(), out);
+                    *d0 = tmp[0];
+                    *d1 = tmp[1];
+                    *d2 = tmp[2];
+                    *d3 = tmp[3];
+
+                    state[4] = state[3];
+                    state[3] = state[2];
+                    state[2] = state[1];
+                    state[1] = state[0];
+                });
+
+                for (i, state) in state.iter().enumerate() {
+                    let mut tmp = [0.0; 4];
+                    _mm256_storeu_pd(tmp.as_mut_ptr(), *state);
+                    for (filter_state, v) in Iterator::zip(filter_state.iter_mut(), tmp) {
+                        filter_state[i] = v;
+                    }
                 }
-            } else {
-                for frame in &audio_data[(audio_data_index - frames_per_block)..audio_data_index] {
-                    channel_sum += *frame * *frame;
+            }
+
+            if flush_denormals {
+                filter_state
+                    .iter_mut()
+                    .for_each(super::flush_denormal_state);
+            }
+
+            filtered |= 0b1111 << c;
+            c += 4;
+        }
+
+        filtered
+    }
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+mod simd {
+    use crate::ebur128::Channel;
+    use crate::utils::Sample;
+
+    /// AVX2 is never available on other architectures.
+    #[inline(always)]
+    pub fn is_available() -> bool {
+        false
+    }
+
+    /// No SIMD implementation available, all channels are filtered by the scalar implementation.
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn process<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
+        _src: &S,
+        _a: &[f64; 5],
+        _b: &[f64; 5],
+        _filter_state: &mut [[f64; 5]],
+        _dest: &mut [f64],
+        _dest_index: usize,
+        _channel_map: &[Channel],
+        _flush_denormals: bool,
+    ) -> u64 {
+        0
+    }
+}
+
+/// Filtering and energy calculation of pairs of channels with two `f64` lanes.
+///
+/// Each lane does exactly the same operations in the same order as the scalar implementation, so
+/// the results are identical.
+mod pairs {
+    use core::ops::Range;
+
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    use core::arch::aarch64::float64x2_t;
+    #[cfg(all(target_arch = "x86", target_feature = "sse2"))]
+    use core::arch::x86::__m128d;
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    use core::arch::x86_64::__m128d;
+
+    use crate::ebur128::Channel;
+    use crate::utils::Sample;
+
+    /// Two `f64` lanes, using SSE2 on x86, NEON on aarch64 and scalar operations otherwise.
+    #[derive(Clone, Copy)]
+    struct F64x2(
+        #[cfg(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            target_feature = "sse2"
+        ))]
+        __m128d,
+        #[cfg(all(target_arch = "aarch64", target_feature = "neon"))] float64x2_t,
+        #[cfg(not(any(
+            all(
+                any(target_arch = "x86", target_arch = "x86_64"),
+                target_feature = "sse2"
+            ),
+            all(target_arch = "aarch64", target_feature = "neon")
+        )))]
+        [f64; 2],
+    );
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "sse2"
+    ))]
+    mod sse2 {
+        use super::F64x2;
+        use core::ops::{Add, Mul, Sub};
+
+        #[cfg(target_arch = "x86")]
+        use core::arch::x86::*;
+        #[cfg(target_arch = "x86_64")]
+        use core::arch::x86_64::*;
+
+        // Safety: SSE2 is enabled at compile-time
+        impl F64x2 {
+            #[inline(always)]
+            pub(super) fn new(v: [f64; 2]) -> Self {
+                unsafe { F64x2(_mm_loadu_pd(v.as_ptr())) }
+            }
+
+            #[inline(always)]
+            pub(super) fn splat(v: f64) -> Self {
+                unsafe { F64x2(_mm_set1_pd(v)) }
+            }
+
+            #[inline(always)]
+            pub(super) fn to_array(self) -> [f64; 2] {
+                let mut v = [0.0; 2];
+                unsafe { _mm_storeu_pd(v.as_mut_ptr(), self.0) };
+                v
+            }
+        }
+
+        macro_rules! f64x2_op {
+            ($trait:ident, $fn:ident, $intrinsic:ident) => {
+                impl $trait for F64x2 {
+                    type Output = F64x2;
+
+                    #[inline(always)]
+                    fn $fn(self, other: F64x2) -> F64x2 {
+                        unsafe { F64x2($intrinsic(self.0, other.0)) }
+                    }
                 }
+            };
+        }
+
+        f64x2_op!(Add, add, _mm_add_pd);
+        f64x2_op!(Sub, sub, _mm_sub_pd);
+        f64x2_op!(Mul, mul, _mm_mul_pd);
+    }
+
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    mod neon {
+        use super::F64x2;
+        use core::arch::aarch64::*;
+        use core::ops::{Add, Mul, Sub};
+
+        // Safety: NEON is enabled at compile-time
+        impl F64x2 {
+            #[inline(always)]
+            pub(super) fn new(v: [f64; 2]) -> Self {
+                unsafe { F64x2(vld1q_f64(v.as_ptr())) }
             }
 
-            match channel {
-                Channel::LeftSurround
-                | Channel::RightSurround
-                | Channel::Mp060
-                | Channel::Mm060
-                | Channel::Mp090
-                | Channel::Mm090 => {
-                    channel_sum *= 1.41;
+            #[inline(always)]
+            pub(super) fn splat(v: f64) -> Self {
+                unsafe { F64x2(vdupq_n_f64(v)) }
+            }
+
+            #[inline(always)]
+            pub(super) fn to_array(self) -> [f64; 2] {
+                let mut v = [0.0; 2];
+                unsafe { vst1q_f64(v.as_mut_ptr(), self.0) };
+                v
+            }
+        }
+
+        // Separate multiplications and additions, never fused, like in the scalar implementation
+        macro_rules! f64x2_op {
+            ($trait:ident, $fn:ident, $intrinsic:ident) => {
+                impl $trait for F64x2 {
+                    type Output = F64x2;
+
+                    #[inline(always)]
+                    fn $fn(self, other: F64x2) -> F64x2 {
+                        unsafe { F64x2($intrinsic(self.0, other.0)) }
+                    }
                 }
-                Channel::DualMono => {
-                    channel_sum *= 2.0;
+            };
+        }
+
+        f64x2_op!(Add, add, vaddq_f64);
+        f64x2_op!(Sub, sub, vsubq_f64);
+        f64x2_op!(Mul, mul, vmulq_f64);
+    }
+
+    #[cfg(not(any(
+        all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            target_feature = "sse2"
+        ),
+        all(target_arch = "aarch64", target_feature = "neon")
+    )))]
+    mod scalar {
+        use super::F64x2;
+        use core::ops::{Add, Mul, Sub};
+
+        impl F64x2 {
+            #[inline(always)]
+            pub(super) fn new(v: [f64; 2]) -> Self {
+                F64x2(v)
+            }
+
+            #[inline(always)]
+            pub(super) fn splat(v: f64) -> Self {
+                F64x2([v; 2])
+            }
+
+            #[inline(always)]
+            pub(super) fn to_array(self) -> [f64; 2] {
+                self.0
+            }
+        }
+
+        macro_rules! f64x2_op {
+            ($trait:ident, $fn:ident, $op:tt) => {
+                impl $trait for F64x2 {
+                    type Output = F64x2;
+
+                    #[inline(always)]
+                    fn $fn(self, other: F64x2) -> F64x2 {
+                        F64x2([self.0[0] $op other.0[0], self.0[1] $op other.0[1]])
+                    }
                 }
-                _ => (),
+            };
+        }
+
+        f64x2_op!(Add, add, +);
+        f64x2_op!(Sub, sub, -);
+        f64x2_op!(Mul, mul, *);
+    }
+
+    /// Filter pairs of used channels that are not in `filtered` yet and return a bitmask of the
+    /// filtered channels.
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    pub fn process<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
+        src: &S,
+        a: &[f64; 5],
+        b: &[f64; 5],
+        filter_state: &mut [[f64; 5]],
+        dest: &mut [f64],
+        dest_index: usize,
+        channel_map: &[Channel],
+        filtered: u64,
+        flush_denormals: bool,
+    ) -> u64 {
+        let channels = channel_map.len();
+        let dest_stride = dest.len() / channels;
+        let mut filtered_pairs = 0;
+
+        let a = a.map(F64x2::splat);
+        let b = b.map(F64x2::splat);
+
+        let mut c = 0;
+        while c + 2 <= channels {
+            if filtered & (0b11 << c) != 0 || channel_map[c..c + 2].contains(&Channel::Unused) {
+                c += 1;
+                continue;
             }
 
-            sum += channel_sum;
+            let (d0, d1) = dest[c * dest_stride..(c + 2) * dest_stride].split_at_mut(dest_stride);
+            let dest = d0[dest_index..].iter_mut().zip(&mut d1[dest_index..]);
+
+            let filter_state = &mut filter_state[c..c + 2];
+            let mut state =
+                [0, 1, 2, 3, 4].map(|i| F64x2::new([filter_state[0][i], filter_state[1][i]]));
+
+            src.foreach_sample2_zipped(c, dest, |src, (d0, d1)| {
+                state[0] = F64x2::new(src.map(|s| s.to_f64()))
+                    - a[1] * state[1]
+                    - a[2] * state[2]
+                    - a[3] * state[3]
+                    - a[4] * state[4];
+                let out = b[0] * state[0]
+                    + b[1] * state[1]
+                    + b[2] * state[2]
+                    + b[3] * state[3]
+                    + b[4] * state[4];
+                [*d0, *d1] = out.to_array();
+
+                state[4] = state[3];
+                state[3] = state[2];
+                state[2] = state[1];
+                state[1] = state[0];
+            });
+
+            for (i, state) in state.iter().enumerate() {
+                [filter_state[0][i], filter_state[1][i]] = state.to_array();
+            }
+
+            if flush_denormals {
+                filter_state
+                    .iter_mut()
+                    .for_each(super::flush_denormal_state);
+            }
+
+            filtered_pairs |= 0b11 << c;
+            c += 2;
         }
 
-        sum /= frames_per_block as f64;
+        filtered_pairs
+    }
 
-        sum
+    /// Sum up the squares of the samples in the given ranges of two channels.
+    #[inline]
+    pub fn sum_squares(channels: [&[f64]; 2], ranges: &[Range<usize>; 2]) -> [f64; 2] {
+        let mut sum = F64x2::splat(0.0);
+        for range in ranges {
+            for (s0, s1) in Iterator::zip(
+                channels[0][range.clone()].iter(),
+                channels[1][range.clone()].iter(),
+            ) {
+                let v = F64x2::new([*s0, *s1]);
+                sum = sum + v * v;
+            }
+        }
+        sum.to_array()
     }
 }
 
@@ -377,13 +1390,13 @@ impl Filter {
     any(target_arch = "x86", target_arch = "x86_64"),
     target_feature = "sse2"
 ))]
-mod ftz {
+pub(crate) mod ftz {
     #[cfg(target_arch = "x86")]
     #[allow(deprecated)]
-    use std::arch::x86::{_mm_getcsr, _mm_setcsr, _MM_FLUSH_ZERO_ON};
+    use core::arch::x86::{_mm_getcsr, _mm_setcsr, _MM_FLUSH_ZERO_ON};
     #[cfg(target_arch = "x86_64")]
     #[allow(deprecated)]
-    use std::arch::x86_64::{_mm_getcsr, _mm_setcsr, _MM_FLUSH_ZERO_ON};
+    use core::arch::x86_64::{_mm_getcsr, _mm_setcsr, _MM_FLUSH_ZERO_ON};
 
     pub struct Ftz(u32);
 
@@ -405,6 +1418,14 @@ mod ftz {
         }
     }
 
+    /// Whether flushing denormals is enabled in the current thread.
+    #[cfg(feature = "rayon")]
+    #[allow(deprecated)]
+    pub fn is_enabled() -> bool {
+        // Safety: Only reads MXCSR
+        unsafe { _mm_getcsr() & _MM_FLUSH_ZERO_ON != 0 }
+    }
+
     pub fn with_ftz<F: FnOnce(Option<&Ftz>) -> T, T>(func: F) -> T {
         // Safety: MXCSR is unset in any case when Ftz goes out of scope and the closure also can't
         // mem::forget() it to prevent running the Drop impl.
@@ -419,10 +1440,16 @@ mod ftz {
     any(target_arch = "x86", target_arch = "x86_64"),
     target_feature = "sse2"
 )),))]
-mod ftz {
+pub(crate) mod ftz {
     pub enum Ftz {}
 
+    /// Whether flushing denormals is enabled in the current thread.
+    #[cfg(feature = "rayon")]
+    pub fn is_enabled() -> bool {
+        false
+    }
+
     pub fn with_ftz<F: FnOnce(Option<&Ftz>) -> T, T>(func: F) -> T {
         func(None)
     }
-}
\ No newline at end of file
+}
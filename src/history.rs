@@ -19,23 +19,36 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
-use crate::{energy_to_loudness, Error};
+use crate::{energy_to_loudness, math, Error, LoudnessStats};
 
-use std::collections::VecDeque;
-use std::fmt;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::{borrow::Cow, boxed::Box, vec, vec::Vec};
+use core::fmt;
 
 // TODO: Create this at compile-time once f64::powf is a const function
 use crate::histogram_bins::BOUNDARIES as HISTOGRAM_BOUNDARIES;
 use crate::histogram_bins::ENERGIES as HISTOGRAM_ENERGIES;
 
-fn find_histogram_index(energy: f64) -> usize {
+/// Relative gate of BS.1770 in LU.
+pub const RELATIVE_GATE: f64 = -10.0;
+
+/// Range of the histogram bins in LUFS.
+pub const HISTOGRAM_LUFS_RANGE: (f64, f64) = (-70.0, 30.0);
+
+/// Resolution of the precomputed histogram bins in bins per LU.
+pub const DEFAULT_HISTOGRAM_RESOLUTION: u32 = 10;
+
+/// Supported range of histogram resolutions in bins per LU, inclusive.
+pub const HISTOGRAM_RESOLUTION_RANGE: (u32, u32) = (1, 100);
+
+fn find_index(boundaries: &[f64], energy: f64) -> usize {
     let mut min = 0;
-    let mut max = 1000;
+    let mut max = boundaries.len() - 1;
 
     // Binary search
     loop {
         let mid = (min + max) / 2;
-        if energy >= HISTOGRAM_BOUNDARIES[mid] {
+        if energy >= boundaries[mid] {
             min = mid;
         } else {
             max = mid;
@@ -49,44 +62,228 @@ fn find_histogram_index(energy: f64) -> usize {
     min
 }
 
-/// Histogram of measured energies. See HISTOGRAM_BOUNDARIES and HISTOGRAM_ENERGIES for
-/// the bins of the histogram.
-pub struct Histogram(Box<[u64; 1000]>);
+fn find_histogram_index(energy: f64) -> usize {
+    find_index(&HISTOGRAM_BOUNDARIES, energy)
+}
+
+/// Sum of floating point values with Neumaier's compensated summation.
+///
+/// Long measurements add up millions of block energies of very different magnitudes, which would
+/// otherwise lose the low bits of the small energies whenever the sum is large.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompensatedSum {
+    sum: f64,
+    /// Accumulated low-order bits lost by `sum`.
+    compensation: f64,
+}
+
+impl CompensatedSum {
+    #[inline]
+    pub fn add(&mut self, value: f64) {
+        let sum = self.sum + value;
+        if self.sum.abs() >= value.abs() {
+            self.compensation += (self.sum - sum) + value;
+        } else {
+            self.compensation += (value - sum) + self.sum;
+        }
+        self.sum = sum;
+    }
+
+    #[inline]
+    pub fn value(&self) -> f64 {
+        self.sum + self.compensation
+    }
+}
+
+impl core::iter::Sum<f64> for CompensatedSum {
+    fn sum<I: Iterator<Item = f64>>(iter: I) -> Self {
+        iter.fold(CompensatedSum::default(), |mut sum, value| {
+            sum.add(value);
+            sum
+        })
+    }
+}
+
+impl<'a> core::iter::Sum<&'a f64> for CompensatedSum {
+    fn sum<I: Iterator<Item = &'a f64>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+/// A bin of the loudness histogram of gating blocks.
+///
+/// The bins cover -70 LUFS to +30 LUFS and are 0.1 LU wide unless a different histogram
+/// resolution is configured. Blocks louder than +30 LUFS are counted in the last bin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistogramBin {
+    /// Lower boundary of the bin in LUFS, inclusive.
+    pub lufs_low: f64,
+    /// Upper boundary of the bin in LUFS, exclusive.
+    pub lufs_high: f64,
+    /// Number of gating blocks in the bin.
+    pub count: u64,
+}
+
+/// Boundaries and center energies of the histogram bins of one resolution.
+///
+/// The default resolution uses the precomputed HISTOGRAM_BOUNDARIES and HISTOGRAM_ENERGIES, all
+/// others are calculated the same way when the histogram is created. Serialized as the number of
+/// bins per LU.
+#[derive(Clone)]
+pub struct HistogramScale {
+    bins_per_lu: u32,
+    boundaries: Cow<'static, [f64]>,
+    energies: Cow<'static, [f64]>,
+}
+
+impl HistogramScale {
+    /// `bins_per_lu` has to be within `HISTOGRAM_RESOLUTION_RANGE`.
+    pub fn new(bins_per_lu: u32) -> Self {
+        if bins_per_lu == DEFAULT_HISTOGRAM_RESOLUTION {
+            return HistogramScale {
+                bins_per_lu,
+                boundaries: Cow::Borrowed(&HISTOGRAM_BOUNDARIES[..]),
+                energies: Cow::Borrowed(&HISTOGRAM_ENERGIES[..]),
+            };
+        }
+
+        let bins =
+            (HISTOGRAM_LUFS_RANGE.1 - HISTOGRAM_LUFS_RANGE.0) as usize * bins_per_lu as usize;
+        let bin_to_energy = |bin: f64| {
+            math::powf(
+                10.0,
+                (HISTOGRAM_LUFS_RANGE.0 + bin / bins_per_lu as f64 + 0.691) / 10.0,
+            )
+        };
+
+        HistogramScale {
+            bins_per_lu,
+            boundaries: (0..=bins).map(|bin| bin_to_energy(bin as f64)).collect(),
+            energies: (0..bins)
+                .map(|bin| bin_to_energy(bin as f64 + 0.5))
+                .collect(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.energies.len()
+    }
+
+    fn index(&self, energy: f64) -> usize {
+        find_index(&self.boundaries, energy)
+    }
+
+    /// Returns the index of the first bin with the energy at its center at or above `energy`.
+    fn first_index_from(&self, energy: f64) -> usize {
+        if energy < self.boundaries[0] {
+            0
+        } else {
+            let index = self.index(energy);
+            if energy > self.energies[index] {
+                index + 1
+            } else {
+                index
+            }
+        }
+    }
+
+    /// Number of bytes allocated for calculated bins.
+    fn heap_size(&self) -> usize {
+        match (&self.boundaries, &self.energies) {
+            (Cow::Borrowed(_), Cow::Borrowed(_)) => 0,
+            _ => (self.boundaries.len() + self.energies.len()) * core::mem::size_of::<f64>(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for HistogramScale {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.bins_per_lu)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HistogramScale {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bins_per_lu = u32::deserialize(deserializer)?;
+
+        if !(HISTOGRAM_RESOLUTION_RANGE.0..=HISTOGRAM_RESOLUTION_RANGE.1).contains(&bins_per_lu) {
+            return Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Unsigned(bins_per_lu.into()),
+                &"1 to 100 histogram bins per LU",
+            ));
+        }
+
+        Ok(HistogramScale::new(bins_per_lu))
+    }
+}
+
+/// Histogram of measured energies, with the bins given by the scale.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Histogram {
+    scale: HistogramScale,
+    counts: Box<[u64]>,
+}
 
 impl Histogram {
-    fn new() -> Self {
-        Histogram(Box::new([0; 1000]))
+    fn new(bins_per_lu: u32) -> Self {
+        let scale = HistogramScale::new(bins_per_lu);
+        let counts = vec![0; scale.len()].into_boxed_slice();
+
+        Histogram { scale, counts }
     }
 
     fn add(&mut self, energy: f64) {
-        let idx = find_histogram_index(energy);
-        self.0[idx] += 1;
+        let idx = self.scale.index(energy);
+        self.counts[idx] += 1;
     }
 
     fn reset(&mut self) {
-        self.0.fill(0);
+        self.counts.fill(0);
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        if self.scale.bins_per_lu != other.scale.bins_per_lu {
+            return Err(Error::InvalidMode);
+        }
+
+        for (count, other_count) in Iterator::zip(self.counts.iter_mut(), other.counts.iter()) {
+            *count += *other_count;
+        }
+
+        Ok(())
     }
 
     fn calc_relative_threshold(&self) -> (u64, f64) {
         let mut above_thresh_counter = 0;
-        let mut relative_threshold = 0.0;
+        let mut relative_threshold = CompensatedSum::default();
 
-        for (count, energy) in Iterator::zip(self.0.iter(), HISTOGRAM_ENERGIES.iter()) {
-            relative_threshold += *count as f64 * *energy;
+        for (count, energy) in Iterator::zip(self.counts.iter(), self.scale.energies.iter()) {
+            relative_threshold.add(*count as f64 * *energy);
             above_thresh_counter += *count;
         }
 
-        (above_thresh_counter, relative_threshold)
+        (above_thresh_counter, relative_threshold.value())
     }
 
-    fn loudness_range(h: &[u64; 1000]) -> f64 {
-        let mut h_sum = [0; 1000];
+    /// Returns the energies at the low and high percentile or `None` if there are no blocks.
+    fn loudness_range_energies(
+        scale: &HistogramScale,
+        h: &[u64],
+        low: f64,
+        high: f64,
+    ) -> Option<(f64, f64)> {
+        let mut h_sum = vec![0; h.len()];
         let mut size = 0;
         let mut power = 0.0;
 
         for ((count, count_sum), energy) in Iterator::zip(
             Iterator::zip(h.iter(), h_sum.iter_mut()),
-            HISTOGRAM_ENERGIES.iter(),
+            scale.energies.iter(),
         ) {
             size += *count;
             *count_sum = size;
@@ -94,27 +291,18 @@ impl Histogram {
         }
 
         if size == 0 {
-            return 0.0;
+            return None;
         }
 
         if power.is_nan() {
-            return f64::NAN;
+            return Some((f64::NAN, f64::NAN));
         }
 
         power /= size as f64;
-        let minus_twenty_decibels = f64::powf(10.0, -20.0 / 10.0);
+        let minus_twenty_decibels = math::powf(10.0, LRA_RELATIVE_GATE / 10.0);
         let integrated = minus_twenty_decibels * power;
 
-        let index = if integrated < HISTOGRAM_BOUNDARIES[0] {
-            0
-        } else {
-            let index = find_histogram_index(integrated);
-            if integrated > HISTOGRAM_ENERGIES[index] {
-                index + 1
-            } else {
-                index
-            }
-        };
+        let index = scale.first_index_from(integrated);
         let before = if let Some(prev_index) = index.checked_sub(1) {
             h_sum.get(prev_index).cloned().unwrap_or(0)
         } else {
@@ -122,66 +310,247 @@ impl Histogram {
         };
         let size = size - before;
         if size == 0 {
-            return 0.0;
+            return None;
         }
 
-        let percentile_low = ((size - 1) as f64 * 0.1 + 0.5) as u64 + before;
-        let percentile_high = ((size - 1) as f64 * 0.95 + 0.5) as u64 + before;
+        let percentile_low = ((size - 1) as f64 * low + 0.5) as u64 + before;
+        let percentile_high = ((size - 1) as f64 * high + 0.5) as u64 + before;
 
-        let j = h_sum[index..]
-            .binary_search(&(percentile_low + 1))
-            .unwrap_or_else(std::convert::identity);
-        let j = match h_sum[..index + j]
-            .iter()
-            .rposition(|&v| v <= percentile_low)
-        {
-            Some(j) => j + 1,
-            None => 0,
-        };
-        let l_en = HISTOGRAM_ENERGIES[j];
+        let l_en = Self::energy_at_rank(scale, &h_sum, index, percentile_low);
+        let h_en = Self::energy_at_rank(scale, &h_sum, index, percentile_high);
 
+        Some((l_en, h_en))
+    }
+
+    /// Returns the energy at the percentile (as fraction) of all blocks or `None` if there are no
+    /// blocks.
+    fn percentile_energy(&self, percentile: f64) -> Option<f64> {
+        let mut h_sum = vec![0; self.counts.len()];
+        let mut size = 0;
+
+        for (count, count_sum) in Iterator::zip(self.counts.iter(), h_sum.iter_mut()) {
+            size += *count;
+            *count_sum = size;
+        }
+
+        if size == 0 {
+            return None;
+        }
+
+        let rank = ((size - 1) as f64 * percentile + 0.5) as u64;
+
+        Some(Self::energy_at_rank(&self.scale, &h_sum, 0, rank))
+    }
+
+    /// Returns the energy of the bin containing the block with the given rank, searching the
+    /// cumulative counts `h_sum` starting at `index`.
+    fn energy_at_rank(scale: &HistogramScale, h_sum: &[u64], index: usize, rank: u64) -> f64 {
         let j = h_sum[index..]
-            .binary_search(&(percentile_high + 1))
-            .unwrap_or_else(std::convert::identity);
-        let j = match h_sum[..index + j]
-            .iter()
-            .rposition(|&v| v <= percentile_high)
-        {
+            .binary_search(&(rank + 1))
+            .unwrap_or_else(core::convert::identity);
+        let j = match h_sum[..index + j].iter().rposition(|&v| v <= rank) {
             Some(j) => j + 1,
             None => 0,
         };
-        let h_en = HISTOGRAM_ENERGIES[j];
 
-        energy_to_loudness(h_en) - energy_to_loudness(l_en)
+        scale.energies[j]
     }
 }
 
+/// Number of fractional bits of the fixed point energies of a [`Queue`](struct.Queue.html).
+///
+/// This is the resolution of the smallest energy that passes the absolute gate, so that all
+/// energies from there up to 2^11 (about +33 LUFS) are represented exactly in 87 bits. Up to 2^40
+/// of them can be summed up in an `i128` without overflow.
+const FIXED_POINT_BITS: i32 = 76;
+
+/// Converts an energy to fixed point, or returns `None` if it is not within the range given by
+/// `FIXED_POINT_BITS`.
+fn to_fixed_point(energy: f64) -> Option<i128> {
+    let bits = energy.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i32 - 1075;
+    let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+    let shift = exponent + FIXED_POINT_BITS;
+
+    if energy.is_sign_negative() || !(0..=34).contains(&shift) {
+        return None;
+    }
+
+    Some(i128::from(mantissa) << shift)
+}
+
+/// Converts a fixed point sum to the nearest energy.
+fn from_fixed_point(sum: i128) -> f64 {
+    sum as f64 / (1u128 << FIXED_POINT_BITS) as f64
+}
+
 /// History of measured energies with a configurable maximum size.
+///
+/// The energies are additionally sorted into the histogram bins, so that gating only has to look
+/// at the individual energies of the bin containing the relative threshold. The bins only keep
+/// the positions of their energies in the queue and the exact sum of the energies in fixed point,
+/// which makes the results independent of the order in which the energies were added or removed.
+#[derive(Clone)]
 pub struct Queue {
     queue: VecDeque<f64>,
     max: usize,
+    /// Sequence number of the next energy, wrapping around.
+    next: u32,
+    /// Non-empty bins by their histogram index.
+    bins: BTreeMap<usize, QueueBin>,
+}
+
+/// Energies of one histogram bin.
+#[derive(Clone, Default)]
+struct QueueBin {
+    /// Sequence numbers of the energies in chronological order.
+    ///
+    /// They wrap around after 2^32 energies, which is fine as long as the queue is shorter than
+    /// that (about 13 years of gating blocks). Without them the bin containing the relative
+    /// threshold could only be gated by scanning the whole queue, which would make
+    /// `loudness_global()` grow linearly with the history again, see `benches/global_latency.rs`.
+    members: VecDeque<u32>,
+    /// Exact sum of all energies that can be converted to fixed point.
+    sum: i128,
+    /// Number of energies that can't be converted to fixed point and have to be summed up one by
+    /// one. These are only louder than about +33 LUFS or not finite.
+    unbounded: usize,
+}
+
+impl QueueBin {
+    fn push(&mut self, seq: u32, energy: f64) {
+        self.members.push_back(seq);
+        match to_fixed_point(energy) {
+            Some(energy) => self.sum += energy,
+            None => self.unbounded += 1,
+        }
+    }
+
+    fn pop(&mut self, energy: f64) {
+        self.members.pop_front();
+        match to_fixed_point(energy) {
+            Some(energy) => self.sum -= energy,
+            None => self.unbounded -= 1,
+        }
+
+        // Each bin keeps its own buffer, so give back memory once the loudness moved on to other
+        // bins. Otherwise the buffers of all bins would keep their maximum size, which can add up
+        // to many times the maximum history.
+        if self.members.capacity() > QueueBin::MIN_CAPACITY
+            && self.members.len() * 4 <= self.members.capacity()
+        {
+            self.members.shrink_to(core::cmp::max(
+                self.members.len() * 2,
+                QueueBin::MIN_CAPACITY,
+            ));
+        }
+    }
+}
+
+impl QueueBin {
+    /// Capacity that is kept in any case to avoid repeated reallocations.
+    const MIN_CAPACITY: usize = 16;
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct QueueRef<'a> {
+    queue: &'a VecDeque<f64>,
+    max: usize,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct QueueState {
+    queue: VecDeque<f64>,
+    max: usize,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Queue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        QueueRef {
+            queue: &self.queue,
+            max: self.max,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Queue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = QueueState::deserialize(deserializer)?;
+
+        let mut q = Queue::new(s.max);
+        for energy in s.queue {
+            q.add(energy);
+        }
+
+        Ok(q)
+    }
 }
 
 impl Queue {
     fn new(max: usize) -> Self {
         Queue {
-            queue: VecDeque::with_capacity(std::cmp::min(max, 5000)),
+            queue: VecDeque::with_capacity(core::cmp::min(max, 5000)),
             max,
+            next: 0,
+            bins: BTreeMap::new(),
         }
     }
 
     fn add(&mut self, energy: f64) {
         // Remove last element to keep the size
         if self.max == self.queue.len() {
-            self.queue.pop_front();
+            self.pop_front();
         }
         self.queue.push_back(energy);
+        self.bins
+            .entry(find_histogram_index(energy))
+            .or_default()
+            .push(self.next, energy);
+        self.next = self.next.wrapping_add(1);
+    }
+
+    fn pop_front(&mut self) {
+        if let Some(energy) = self.queue.pop_front() {
+            let index = find_histogram_index(energy);
+            if let Some(bin) = self.bins.get_mut(&index) {
+                bin.pop(energy);
+                if bin.members.is_empty() {
+                    self.bins.remove(&index);
+                }
+            }
+        }
+    }
+
+    /// Returns an iterator over the energies of the bin in chronological order.
+    fn bin_energies<'a>(&'a self, bin: &'a QueueBin) -> impl Iterator<Item = f64> + 'a {
+        let first = self.next.wrapping_sub(self.queue.len() as u32);
+        bin.members
+            .iter()
+            .map(move |seq| self.queue[seq.wrapping_sub(first) as usize])
+    }
+
+    /// Number of bytes allocated for the energies.
+    fn heap_size(&self) -> usize {
+        self.queue.capacity() * core::mem::size_of::<f64>()
+            + self.bins.len() * core::mem::size_of::<(usize, QueueBin)>()
+            + self
+                .bins
+                .values()
+                .map(|bin| bin.members.capacity() * core::mem::size_of::<u32>())
+                .sum::<usize>()
     }
 
     fn set_max_size(&mut self, max: usize) {
-        if self.queue.len() < max {
-            // FIXME: Use shrink() once stabilized
-            self.queue.resize(max, 0.0);
+        if self.queue.len() > max {
+            // Drop the oldest energies
+            for _ in max..self.queue.len() {
+                self.pop_front();
+            }
             self.queue.shrink_to_fit();
         }
         self.max = max;
@@ -189,19 +558,76 @@ impl Queue {
 
     fn reset(&mut self) {
         self.queue.clear();
+        self.bins.clear();
+        self.next = 0;
+    }
+
+    fn merge(&mut self, other: &Self) {
+        for energy in &other.queue {
+            self.add(*energy);
+        }
     }
 
     fn calc_relative_threshold(&self) -> (u64, f64) {
-        (self.queue.len() as u64, self.queue.iter().sum::<f64>())
+        let mut sum = CompensatedSum::default();
+        sum.add(from_fixed_point(
+            self.bins.values().map(|bin| bin.sum).sum(),
+        ));
+        for bin in self.bins.values().filter(|bin| bin.unbounded > 0) {
+            self.bin_energies(bin)
+                .filter(|energy| to_fixed_point(*energy).is_none())
+                .for_each(|energy| sum.add(energy));
+        }
+
+        (self.queue.len() as u64, sum.value())
     }
 
-    fn loudness_range(q: &[f64]) -> f64 {
+    /// Returns the number and the sum of all energies at or above the threshold. Only the bin
+    /// containing the threshold and bins with energies that can't be converted to fixed point
+    /// are gated energy by energy, all bins above are taken as a whole.
+    fn gated(&self, threshold: f64) -> (u64, f64) {
+        let threshold_index = if threshold < HISTOGRAM_BOUNDARIES[0] {
+            None
+        } else {
+            Some(find_histogram_index(threshold))
+        };
+
+        let mut count = 0;
+        let mut fixed_point_sum = 0;
+        let mut unbounded_sum = CompensatedSum::default();
+        for (index, bin) in self.bins.range(threshold_index.unwrap_or(0)..) {
+            if Some(*index) != threshold_index && bin.unbounded == 0 {
+                count += bin.members.len() as u64;
+                fixed_point_sum += bin.sum;
+                continue;
+            }
+
+            for energy in self.bin_energies(bin).filter(|energy| *energy >= threshold) {
+                count += 1;
+                match to_fixed_point(energy) {
+                    Some(energy) => fixed_point_sum += energy,
+                    None => unbounded_sum.add(energy),
+                }
+            }
+        }
+
+        let mut sum = CompensatedSum::default();
+        sum.add(from_fixed_point(fixed_point_sum));
+        sum.add(unbounded_sum.value());
+
+        (count, sum.value())
+    }
+
+    /// Returns the energies at the low and high percentile or `None` if there are no blocks.
+    ///
+    /// `q` must be sorted.
+    fn loudness_range_energies(q: &[f64], low: f64, high: f64) -> Option<(f64, f64)> {
         if q.is_empty() {
-            return 0.0;
+            return None;
         }
 
-        let power = q.iter().sum::<f64>() / q.len() as f64;
-        let minus_twenty_decibels = f64::powf(10.0, -20.0 / 10.0);
+        let power = q.iter().sum::<CompensatedSum>().value() / q.len() as f64;
+        let minus_twenty_decibels = math::powf(10.0, LRA_RELATIVE_GATE / 10.0);
         let integrated = minus_twenty_decibels * power;
 
         let relgated = q.iter().take_while(|&v| *v < integrated).count();
@@ -209,12 +635,12 @@ impl Queue {
 
         if let Some(relgated_size) = relgated_size.checked_sub(1) {
             let relgated_size = relgated_size as f64;
-            let h_en = q[relgated + (relgated_size * 0.95 + 0.5) as usize];
-            let l_en = q[relgated + (relgated_size * 0.1 + 0.5) as usize];
+            let h_en = q[relgated + (relgated_size * high + 0.5) as usize];
+            let l_en = q[relgated + (relgated_size * low + 0.5) as usize];
 
-            energy_to_loudness(h_en) - energy_to_loudness(l_en)
+            Some((l_en, h_en))
         } else {
-            0.0
+            None
         }
     }
 }
@@ -225,7 +651,7 @@ pub enum HistoryError {
     RelativeThresholdIsNan,
 }
 
-impl std::error::Error for HistoryError {}
+impl core::error::Error for HistoryError {}
 
 impl fmt::Display for HistoryError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -237,7 +663,15 @@ impl fmt::Display for HistoryError {
     }
 }
 
+/// Low and high percentile (as fraction) of the loudness range according to EBU 3342.
+pub const LRA_PERCENTILES: (f64, f64) = (0.1, 0.95);
+
+/// Relative gate of the loudness range calculation of EBU 3342 in LU.
+pub const LRA_RELATIVE_GATE: f64 = -20.0;
+
 /// History of measured energies, either as histogram or a vector.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum History {
     Queue(Queue),
     Histogram(Histogram),
@@ -253,23 +687,111 @@ impl fmt::Debug for History {
 }
 
 impl History {
-    pub fn new(use_histogram: bool, max: usize) -> Self {
+    /// Creates a histogram with `bins_per_lu` or a queue of at most `max` blocks.
+    pub fn new(use_histogram: bool, bins_per_lu: u32, max: usize) -> Self {
         if use_histogram {
-            History::Histogram(Histogram::new())
+            History::Histogram(Histogram::new(bins_per_lu))
         } else {
             History::Queue(Queue::new(max))
         }
     }
 
-    pub fn add(&mut self, energy: f64) {
+    /// Creates an empty history of the same kind, resolution and maximum size.
+    pub fn empty_like(&self) -> Self {
+        match self {
+            History::Histogram(ref h) => History::Histogram(Histogram {
+                scale: h.scale.clone(),
+                counts: vec![0; h.counts.len()].into_boxed_slice(),
+            }),
+            History::Queue(ref q) => History::Queue(Queue::new(q.max)),
+        }
+    }
+
+    /// Adds the energy if it passes the absolute gate and returns whether it was added.
+    pub fn add(&mut self, energy: f64) -> bool {
         if energy < HISTOGRAM_BOUNDARIES[0] {
-            return;
+            return false;
         }
 
         match self {
             History::Histogram(ref mut h) => h.add(energy),
             History::Queue(ref mut q) => q.add(energy),
         }
+
+        true
+    }
+
+    /// Returns an iterator over all stored energies in chronological order, or `None` for
+    /// histograms as they don't store individual energies.
+    pub fn energies(&self) -> Option<impl Iterator<Item = f64> + '_> {
+        match self {
+            History::Histogram(_) => None,
+            History::Queue(ref q) => Some(q.queue.iter().copied()),
+        }
+    }
+
+    /// Returns the number of blocks per histogram bin. Queues are sorted into bins with
+    /// `bins_per_lu`.
+    pub fn histogram_bins(&self, bins_per_lu: u32) -> Vec<HistogramBin> {
+        let mut converted;
+        let h = match self {
+            History::Histogram(ref h) => h,
+            History::Queue(ref q) => {
+                converted = Histogram::new(bins_per_lu);
+                for energy in &q.queue {
+                    converted.add(*energy);
+                }
+                &converted
+            }
+        };
+
+        h.counts
+            .iter()
+            .zip(h.scale.boundaries.windows(2))
+            .map(|(count, boundaries)| HistogramBin {
+                lufs_low: energy_to_loudness(boundaries[0]),
+                lufs_high: energy_to_loudness(boundaries[1]),
+                count: *count,
+            })
+            .collect()
+    }
+
+    /// Returns the energy at the percentile (as fraction) of all blocks, or `None` for queues and
+    /// if there are no blocks.
+    pub fn percentile_energy(&self, percentile: f64) -> Option<f64> {
+        match self {
+            History::Histogram(ref h) => h.percentile_energy(percentile),
+            History::Queue(_) => None,
+        }
+    }
+
+    /// Returns the number of blocks in the histogram bin containing `lufs`, or `None` for queues
+    /// and values outside of `HISTOGRAM_LUFS_RANGE`.
+    pub fn histogram_count_at(&self, lufs: f64) -> Option<u64> {
+        let h = match self {
+            History::Histogram(ref h) => h,
+            History::Queue(_) => return None,
+        };
+
+        if !(HISTOGRAM_LUFS_RANGE.0..HISTOGRAM_LUFS_RANGE.1).contains(&lufs) {
+            return None;
+        }
+
+        let energy = math::powf(10.0, (lufs + 0.691) / 10.0);
+        Some(h.counts[h.scale.index(energy)])
+    }
+
+    /// Check if the (deserialized) history is of the expected kind and resolution.
+    #[cfg(feature = "serde")]
+    pub fn is_compatible(&self, use_histogram: bool, bins_per_lu: u32) -> bool {
+        match self {
+            History::Histogram(ref h) => {
+                use_histogram
+                    && h.scale.bins_per_lu == bins_per_lu
+                    && h.counts.len() == h.scale.len()
+            }
+            History::Queue(_) => !use_histogram,
+        }
     }
 
     pub fn set_max_size(&mut self, max: usize) {
@@ -279,6 +801,27 @@ impl History {
         }
     }
 
+    /// Sorts all stored energies of a queue into a histogram with `bins_per_lu`.
+    pub fn convert_to_histogram(&mut self, bins_per_lu: u32) {
+        if let History::Queue(ref q) = self {
+            let mut h = Histogram::new(bins_per_lu);
+            for energy in &q.queue {
+                h.add(*energy);
+            }
+            *self = History::Histogram(h);
+        }
+    }
+
+    /// Number of bytes allocated for the blocks.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            History::Histogram(ref h) => {
+                h.counts.len() * core::mem::size_of::<u64>() + h.scale.heap_size()
+            }
+            History::Queue(ref q) => q.heap_size(),
+        }
+    }
+
     pub fn reset(&mut self) {
         match self {
             History::Histogram(ref mut h) => h.reset(),
@@ -286,6 +829,18 @@ impl History {
         }
     }
 
+    /// Add all energies of `other` to this history. Both have to be of the same kind and
+    /// resolution.
+    pub fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        match (self, other) {
+            (History::Histogram(ref mut h), History::Histogram(ref other)) => h.merge(other)?,
+            (History::Queue(ref mut q), History::Queue(ref other)) => q.merge(other),
+            _ => return Err(Error::InvalidMode),
+        }
+
+        Ok(())
+    }
+
     fn calc_relative_threshold(&self) -> (u64, f64) {
         match self {
             History::Histogram(ref h) => h.calc_relative_threshold(),
@@ -297,17 +852,27 @@ impl History {
         Self::gated_loudness_multiple(&[self])
     }
 
-    /// Returns an Iterator over (gating_block_count, loudness) tuples.
-    pub fn iter_gating_block_count_and_energy<'a>(
-        s: &'a [&'a Self],
-    ) -> Result<impl Iterator<Item = (u64, f64)> + 'a, HistoryError> {
-        let (above_thresh_counter, relative_threshold) = s.iter().fold((0, 0.0), |mut acc, h| {
-            let (above_thresh_counter, relative_threshold) = h.calc_relative_threshold();
-            acc.0 += above_thresh_counter;
-            acc.1 += relative_threshold;
+    /// Gated loudness with the given relative gate in LU instead of the standard one.
+    pub fn gated_loudness_with_gate(&self, relative_gate: f64) -> f64 {
+        Self::gated_loudness_from(Self::gating_block_count_and_energy_multiple(
+            &[self],
+            relative_gate,
+        ))
+    }
+
+    /// Returns the relative threshold energy.
+    ///
+    /// Histogram bins are included if the energy at their center is at or above the threshold.
+    pub fn relative_gate(s: &[&Self], relative_gate: f64) -> Result<f64, HistoryError> {
+        let (above_thresh_counter, relative_threshold) =
+            s.iter().fold((0, CompensatedSum::default()), |mut acc, h| {
+                let (above_thresh_counter, relative_threshold) = h.calc_relative_threshold();
+                acc.0 += above_thresh_counter;
+                acc.1.add(relative_threshold);
 
-            acc
-        });
+                acc
+            });
+        let relative_threshold = relative_threshold.value();
 
         if above_thresh_counter == 0 {
             return Err(HistoryError::NoBlocksAboveThreshold);
@@ -317,31 +882,30 @@ impl History {
             return Err(HistoryError::RelativeThresholdIsNan);
         }
 
-        let relative_gate = -10.0;
-        let relative_gate_factor = f64::powf(10.0, relative_gate / 10.0);
+        let relative_gate_factor = math::powf(10.0, relative_gate / 10.0);
         let relative_threshold =
             (relative_threshold / above_thresh_counter as f64) * relative_gate_factor;
 
-        let start_index = if relative_threshold < HISTOGRAM_BOUNDARIES[0] {
-            0
-        } else {
-            let start_index = find_histogram_index(relative_threshold);
-            if relative_threshold > HISTOGRAM_ENERGIES[start_index] {
-                start_index + 1
-            } else {
-                start_index
-            }
-        };
+        Ok(relative_threshold)
+    }
+
+    /// Returns an Iterator over (gating_block_count, loudness) tuples.
+    pub fn iter_gating_block_count_and_energy<'a>(
+        s: &'a [&'a Self],
+        relative_gate: f64,
+    ) -> Result<impl Iterator<Item = (u64, f64)> + 'a, HistoryError> {
+        let relative_threshold = Self::relative_gate(s, relative_gate)?;
 
         Ok(s.iter().flat_map(move |h| {
             let mut histogram_iterator = None;
             let mut queue_iterator = None;
             match h {
                 History::Histogram(ref h) => {
+                    let start_index = h.scale.first_index_from(relative_threshold);
                     histogram_iterator = Some(
                         Iterator::zip(
-                            h.0[start_index..].iter(),
-                            HISTOGRAM_ENERGIES[start_index..].iter(),
+                            h.counts[start_index..].iter(),
+                            h.scale.energies[start_index..].iter(),
                         )
                         .map(|(count, energy)| {
                             let loudness = *count as f64 * *energy;
@@ -350,13 +914,7 @@ impl History {
                     )
                 }
                 History::Queue(ref q) => {
-                    queue_iterator = Some(q.queue.iter().filter_map(move |loudness| {
-                        if *loudness >= relative_threshold {
-                            Some((1, *loudness))
-                        } else {
-                            None
-                        }
-                    }))
+                    queue_iterator = Some(core::iter::once(q.gated(relative_threshold)))
                 }
             }
             histogram_iterator
@@ -367,7 +925,14 @@ impl History {
     }
 
     pub fn gated_loudness_multiple(s: &[&Self]) -> f64 {
-        match Self::gating_block_count_and_energy_multiple(s) {
+        Self::gated_loudness_from(Self::gating_block_count_and_energy_multiple(
+            s,
+            RELATIVE_GATE,
+        ))
+    }
+
+    fn gated_loudness_from(gated: Result<(u64, f64), HistoryError>) -> f64 {
+        match gated {
             Ok((above_thresh_counter, gated_loudness)) => {
                 energy_to_loudness(gated_loudness / above_thresh_counter as f64)
             }
@@ -377,17 +942,22 @@ impl History {
     }
 
     pub fn gating_block_count_and_energy(&self) -> Result<(u64, f64), HistoryError> {
-        Self::gating_block_count_and_energy_multiple(&[self])
+        Self::gating_block_count_and_energy_multiple(&[self], RELATIVE_GATE)
     }
 
-    pub fn gating_block_count_and_energy_multiple(s: &[&Self]) -> Result<(u64, f64), HistoryError> {
-        Self::iter_gating_block_count_and_energy(s).and_then(|iter| {
+    pub fn gating_block_count_and_energy_multiple(
+        s: &[&Self],
+        relative_gate: f64,
+    ) -> Result<(u64, f64), HistoryError> {
+        Self::iter_gating_block_count_and_energy(s, relative_gate).and_then(|iter| {
             let (above_thresh_counter, gated_loudness) = iter.fold(
-                (0u64, 0.0f64),
-                |(total_count, total_loudness), (count, loudness)| {
-                    (total_count + count, total_loudness + loudness)
+                (0u64, CompensatedSum::default()),
+                |(total_count, mut total_loudness), (count, loudness)| {
+                    total_loudness.add(loudness);
+                    (total_count + count, total_loudness)
                 },
             );
+            let gated_loudness = gated_loudness.value();
 
             if above_thresh_counter == 0 {
                 Err(HistoryError::NoBlocksAboveThreshold)
@@ -408,8 +978,7 @@ impl History {
             return f64::NAN;
         }
 
-        let relative_gate = -10.0;
-        let relative_gate_factor = f64::powf(10.0, relative_gate / 10.0);
+        let relative_gate_factor = math::powf(10.0, RELATIVE_GATE / 10.0);
         let relative_threshold =
             (relative_threshold / above_thresh_counter as f64) * relative_gate_factor;
 
@@ -423,8 +992,84 @@ impl History {
     }
 
     pub fn loudness_range_multiple(s: &[&Self]) -> Result<f64, Error> {
+        let (low, high) = LRA_PERCENTILES;
+        Ok(
+            match Self::loudness_range_energies_multiple(s, low, high)? {
+                Some((l_en, h_en)) => energy_to_loudness(h_en) - energy_to_loudness(l_en),
+                None => 0.0,
+            },
+        )
+    }
+
+    /// Returns the statistics of all blocks at or above the relative gate in LU, or of all blocks
+    /// without a gate, or `None` if there are no such blocks.
+    ///
+    /// The relative threshold is calculated and applied like for the loudness range.
+    pub fn loudness_stats(&self, relative_gate: Option<f64>) -> Option<LoudnessStats> {
+        let (count, energy) = self.calc_relative_threshold();
+        if count == 0 {
+            return None;
+        }
+
+        let threshold = match relative_gate {
+            Some(relative_gate) => energy / count as f64 * math::powf(10.0, relative_gate / 10.0),
+            None => f64::NEG_INFINITY,
+        };
+
+        match self {
+            History::Histogram(ref h) => {
+                let start_index = h.scale.first_index_from(threshold);
+                let mut energy_sum = CompensatedSum::default();
+                let bins = Iterator::zip(
+                    Iterator::zip(
+                        h.counts[start_index..].iter(),
+                        h.scale.boundaries[start_index..].windows(2),
+                    ),
+                    h.scale.energies[start_index..].iter(),
+                )
+                .filter(|((count, _), _)| **count > 0)
+                .map(|((count, boundaries), energy)| {
+                    energy_sum.add(*count as f64 * *energy);
+                    HistogramBin {
+                        lufs_low: energy_to_loudness(boundaries[0]),
+                        lufs_high: energy_to_loudness(boundaries[1]),
+                        count: *count,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+                LoudnessStats::from_bins(bins, energy_sum.value())
+            }
+            History::Queue(ref q) => {
+                // Like for the loudness range, all blocks are kept if the threshold is NaN
+                let mut energies = q
+                    .queue
+                    .iter()
+                    .copied()
+                    .filter(|energy| threshold.is_nan() || *energy >= threshold)
+                    .collect::<Vec<_>>();
+                energies.sort_unstable_by(f64::total_cmp);
+
+                LoudnessStats::from_energies(&energies)
+            }
+        }
+    }
+
+    /// Returns the energies at the low and high percentile (as fraction) of the loudness range
+    /// calculation or `None` if there are no blocks.
+    pub fn loudness_range_energies(&self, low: f64, high: f64) -> Option<(f64, f64)> {
+        // This can only fail if multiple histories are passed
+        // and have a mix of histograms and queues
+        Self::loudness_range_energies_multiple(&[self], low, high).unwrap()
+    }
+
+    pub fn loudness_range_energies_multiple(
+        s: &[&Self],
+        low: f64,
+        high: f64,
+    ) -> Result<Option<(f64, f64)>, Error> {
         if s.is_empty() {
-            return Ok(0.0);
+            return Ok(None);
         }
 
         match s[0] {
@@ -432,14 +1077,18 @@ impl History {
                 let mut combined;
 
                 let combined = if s.len() == 1 {
-                    &*h.0
+                    &*h.counts
                 } else {
-                    combined = [0; 1000];
-
-                    for h in s {
-                        match h {
-                            History::Histogram(ref h) => {
-                                for (i, o) in Iterator::zip(h.0.iter(), combined.iter_mut()) {
+                    combined = vec![0; h.counts.len()];
+
+                    for other in s {
+                        match other {
+                            History::Histogram(ref other)
+                                if other.scale.bins_per_lu == h.scale.bins_per_lu =>
+                            {
+                                for (i, o) in
+                                    Iterator::zip(other.counts.iter(), combined.iter_mut())
+                                {
                                     *o += *i;
                                 }
                             }
@@ -447,10 +1096,12 @@ impl History {
                         }
                     }
 
-                    &combined
+                    &combined[..]
                 };
 
-                Ok(Histogram::loudness_range(combined))
+                Ok(Histogram::loudness_range_energies(
+                    &h.scale, combined, low, high,
+                ))
             }
             History::Queue(_) => {
                 let mut len = 0;
@@ -487,11 +1138,319 @@ impl History {
                 });
 
                 if contains_nan {
-                    return Ok(f64::NAN);
+                    return Ok(Some((f64::NAN, f64::NAN)));
+                }
+
+                Ok(Queue::loudness_range_energies(&combined, low, high))
+            }
+        }
+    }
+}
+
+/// Per-channel energies of the gating blocks, either summed up per histogram bin or per block.
+///
+/// Blocks are sorted into histogram bins or gated by the total block energy, so that the same
+/// blocks as for the integrated loudness contribute to the per-channel energies.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChannelHistory {
+    Histogram {
+        channels: usize,
+        scale: HistogramScale,
+        /// Number of blocks per histogram bin.
+        counts: Box<[u64]>,
+        /// Sum of the per-channel energies of all blocks per histogram bin.
+        energies: Box<[CompensatedSum]>,
+    },
+    Queue {
+        channels: usize,
+        /// Block energy followed by the per-channel energies, for each block.
+        energies: VecDeque<f64>,
+        /// Maximum number of blocks.
+        max: usize,
+    },
+}
+
+impl fmt::Debug for ChannelHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChannelHistory::Histogram { .. } => {
+                f.debug_struct("ChannelHistory::Histogram").finish()
+            }
+            ChannelHistory::Queue { .. } => f.debug_struct("ChannelHistory::Queue").finish(),
+        }
+    }
+}
+
+impl ChannelHistory {
+    /// Creates a histogram with `bins_per_lu` or a queue of at most `max` blocks.
+    pub fn new(use_histogram: bool, bins_per_lu: u32, channels: usize, max: usize) -> Self {
+        if use_histogram {
+            let scale = HistogramScale::new(bins_per_lu);
+            ChannelHistory::Histogram {
+                channels,
+                counts: vec![0; scale.len()].into_boxed_slice(),
+                energies: vec![CompensatedSum::default(); scale.len() * channels]
+                    .into_boxed_slice(),
+                scale,
+            }
+        } else {
+            ChannelHistory::Queue {
+                channels,
+                energies: VecDeque::with_capacity(core::cmp::min(max, 5000) * (channels + 1)),
+                max,
+            }
+        }
+    }
+
+    /// Creates an empty history of the same kind, resolution, number of channels and maximum size.
+    pub fn empty_like(&self) -> Self {
+        match self {
+            ChannelHistory::Histogram {
+                channels,
+                scale,
+                counts,
+                energies,
+            } => ChannelHistory::Histogram {
+                channels: *channels,
+                scale: scale.clone(),
+                counts: vec![0; counts.len()].into_boxed_slice(),
+                energies: vec![CompensatedSum::default(); energies.len()].into_boxed_slice(),
+            },
+            ChannelHistory::Queue { channels, max, .. } => {
+                ChannelHistory::new(false, DEFAULT_HISTOGRAM_RESOLUTION, *channels, *max)
+            }
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        match self {
+            ChannelHistory::Histogram { channels, .. } => *channels,
+            ChannelHistory::Queue { channels, .. } => *channels,
+        }
+    }
+
+    /// Check if the (deserialized) history is of the expected kind, resolution and size.
+    #[cfg(feature = "serde")]
+    pub fn is_compatible(&self, use_histogram: bool, bins_per_lu: u32, channels: usize) -> bool {
+        match self {
+            ChannelHistory::Histogram {
+                channels: c,
+                scale,
+                counts,
+                energies,
+            } => {
+                use_histogram
+                    && *c == channels
+                    && scale.bins_per_lu == bins_per_lu
+                    && counts.len() == scale.len()
+                    && energies.len() == scale.len() * channels
+            }
+            ChannelHistory::Queue {
+                channels: c,
+                energies,
+                ..
+            } => !use_histogram && *c == channels && energies.len() % (channels + 1) == 0,
+        }
+    }
+
+    /// Number of bytes allocated for the per-channel energies.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            ChannelHistory::Histogram {
+                scale,
+                counts,
+                energies,
+                ..
+            } => {
+                counts.len() * core::mem::size_of::<u64>()
+                    + energies.len() * core::mem::size_of::<CompensatedSum>()
+                    + scale.heap_size()
+            }
+            ChannelHistory::Queue { energies, .. } => {
+                energies.capacity() * core::mem::size_of::<f64>()
+            }
+        }
+    }
+
+    /// Add a block with the given total energy and per-channel energies.
+    pub fn add(&mut self, energy: f64, channel_energies: &[f64]) {
+        if energy < HISTOGRAM_BOUNDARIES[0] {
+            return;
+        }
+
+        match self {
+            ChannelHistory::Histogram {
+                channels,
+                scale,
+                counts,
+                energies,
+            } => {
+                assert_eq!(channel_energies.len(), *channels);
+
+                let idx = scale.index(energy);
+                counts[idx] += 1;
+                for (sum, energy) in Iterator::zip(
+                    energies[idx * *channels..(idx + 1) * *channels].iter_mut(),
+                    channel_energies,
+                ) {
+                    sum.add(*energy);
                 }
+            }
+            ChannelHistory::Queue {
+                channels,
+                energies,
+                max,
+            } => {
+                assert_eq!(channel_energies.len(), *channels);
+
+                // Remove oldest block to keep the size
+                if *max == energies.len() / (*channels + 1) {
+                    energies.drain(..*channels + 1);
+                }
+                energies.push_back(energy);
+                energies.extend(channel_energies);
+            }
+        }
+    }
 
-                Ok(Queue::loudness_range(&combined))
+    pub fn set_max_size(&mut self, max: usize) {
+        if let ChannelHistory::Queue {
+            channels,
+            energies,
+            max: old_max,
+        } = self
+        {
+            let len = energies.len() / (*channels + 1);
+            if len > max {
+                energies.drain(..(len - max) * (*channels + 1));
+            }
+            *old_max = max;
+        }
+    }
+
+    /// Sorts all stored blocks of a queue into a histogram with `bins_per_lu`.
+    pub fn convert_to_histogram(&mut self, bins_per_lu: u32) {
+        let histogram = match self {
+            ChannelHistory::Histogram { .. } => return,
+            ChannelHistory::Queue {
+                channels, energies, ..
+            } => {
+                let mut histogram = ChannelHistory::new(true, bins_per_lu, *channels, 0);
+                for block in energies.make_contiguous().chunks_exact(*channels + 1) {
+                    histogram.add(block[0], &block[1..]);
+                }
+                histogram
+            }
+        };
+        *self = histogram;
+    }
+
+    pub fn reset(&mut self) {
+        match self {
+            ChannelHistory::Histogram {
+                counts, energies, ..
+            } => {
+                counts.fill(0);
+                energies.fill(CompensatedSum::default());
             }
+            ChannelHistory::Queue { energies, .. } => energies.clear(),
         }
     }
-}
\ No newline at end of file
+
+    /// Add all blocks of `other` to this history. Both have to be of the same kind, resolution and
+    /// size.
+    pub fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        if self.channels() != other.channels() {
+            return Err(Error::InvalidMode);
+        }
+
+        match (self, other) {
+            (
+                ChannelHistory::Histogram {
+                    scale,
+                    counts,
+                    energies,
+                    ..
+                },
+                ChannelHistory::Histogram {
+                    scale: other_scale,
+                    counts: other_counts,
+                    energies: other_energies,
+                    ..
+                },
+            ) => {
+                if scale.bins_per_lu != other_scale.bins_per_lu {
+                    return Err(Error::InvalidMode);
+                }
+
+                for (count, other_count) in Iterator::zip(counts.iter_mut(), other_counts.iter()) {
+                    *count += *other_count;
+                }
+                for (energy, other_energy) in
+                    Iterator::zip(energies.iter_mut(), other_energies.iter())
+                {
+                    energy.add(other_energy.sum);
+                    energy.add(other_energy.compensation);
+                }
+            }
+            (
+                this @ ChannelHistory::Queue { .. },
+                ChannelHistory::Queue {
+                    channels,
+                    energies: other_energies,
+                    ..
+                },
+            ) => {
+                let other_energies = other_energies.iter().copied().collect::<Vec<_>>();
+                for block in other_energies.chunks_exact(*channels + 1) {
+                    this.add(block[0], &block[1..]);
+                }
+            }
+            _ => return Err(Error::InvalidMode),
+        }
+
+        Ok(())
+    }
+
+    /// Sums up the per-channel energies of all blocks above the relative gate, as returned by
+    /// [`History::relative_gate`]. Returns the number of blocks and the energy sums.
+    pub fn gated_energies(&self, relative_threshold: f64) -> (u64, Vec<f64>) {
+        let mut sums = vec![CompensatedSum::default(); self.channels()];
+        let mut count = 0;
+
+        match self {
+            ChannelHistory::Histogram {
+                channels,
+                scale,
+                counts,
+                energies,
+            } => {
+                let start_index = scale.first_index_from(relative_threshold);
+                for (bin_count, bin_energies) in Iterator::zip(
+                    counts[start_index..].iter(),
+                    energies[start_index * *channels..].chunks_exact(*channels),
+                ) {
+                    count += *bin_count;
+                    for (sum, energy) in Iterator::zip(sums.iter_mut(), bin_energies) {
+                        sum.add(energy.value());
+                    }
+                }
+            }
+            ChannelHistory::Queue {
+                channels, energies, ..
+            } => {
+                for i in (0..energies.len()).step_by(*channels + 1) {
+                    if energies[i] >= relative_threshold {
+                        count += 1;
+                        for (c, sum) in sums.iter_mut().enumerate() {
+                            sum.add(energies[i + 1 + c]);
+                        }
+                    }
+                }
+            }
+        }
+
+        (count, sums.iter().map(CompensatedSum::value).collect())
+    }
+}
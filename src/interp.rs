@@ -19,11 +19,53 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
+use crate::math;
 use crate::utils::FrameAccumulator;
-use std::f64::consts::PI;
+use alloc::{vec, vec::Vec};
+use core::f64::consts::PI;
 
 const ALMOST_ZERO: f64 = 0.000001;
-const TAPS: usize = 48;
+/// Maximum number of active taps of any interpolator.
+const MAX_ACTIVE_TAPS: usize = 24;
+
+// Precalculated coefficients for the common factors, generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/interp_coeffs.rs"));
+
+/// Precalculated Hanning-windowed sinc filter coefficients with `taps` taps for the given
+/// interpolation factor, if available.
+#[doc(hidden)]
+pub fn precalculated_filter_coefficients(factor: usize, taps: usize) -> Option<&'static [f32]> {
+    match (factor, taps) {
+        (2, 48) => Some(&INTERP_FILTER_COEFFS_2),
+        (4, 48) => Some(&INTERP_FILTER_COEFFS_4),
+        (8, 96) => Some(&INTERP_FILTER_COEFFS_8),
+        _ => None,
+    }
+}
+
+/// Calculate the Hanning-windowed sinc filter coefficients with `taps` taps for the given
+/// interpolation factor.
+#[doc(hidden)]
+pub fn filter_coefficients(factor: usize, taps: usize) -> Vec<f32> {
+    let mut coeffs = vec![0f32; taps];
+    for (j, coeff) in coeffs.iter_mut().enumerate() {
+        let j = j as f64;
+        // Calculate Hanning window,
+        let window = taps + 1;
+        // Ignore one tap. (Last tap is zero anyways, and we want to hit an even multiple of taps)
+        let window = (window - 1) as f64;
+        let w = 0.5 * (1.0 - math::cos(2.0 * PI * j / window));
+
+        // Calculate sinc and apply hanning window
+        let m = j - window / 2.0;
+        *coeff = if m.abs() > ALMOST_ZERO {
+            w * math::sin(m * PI / factor as f64) / (m * PI / factor as f64)
+        } else {
+            w
+        } as f32;
+    }
+    coeffs
+}
 
 /// A circular buffer offering fixed-length continous views into data
 /// This is enabled by writing data twice, also to a "shadow"-buffer following the primary buffer,
@@ -31,15 +73,15 @@ const TAPS: usize = 48;
 /// predictable length into the data, unlocking some more optimizations
 #[derive(Clone, Debug)]
 struct RollingBuffer<T, const N: usize> {
-    buf: [T; TAPS],
+    buf: [T; 2 * MAX_ACTIVE_TAPS],
     position: usize,
 }
 
 impl<T: Default + Copy, const N: usize> RollingBuffer<T, N> {
     fn new() -> Self {
-        assert!(N * 2 <= TAPS);
+        assert!(N <= MAX_ACTIVE_TAPS);
 
-        let buf: [T; TAPS] = [Default::default(); TAPS];
+        let buf: [T; 2 * MAX_ACTIVE_TAPS] = [Default::default(); 2 * MAX_ACTIVE_TAPS];
 
         Self { buf, position: N }
     }
@@ -77,7 +119,7 @@ pub struct InterpF<const ACTIVE_TAPS: usize, const FACTOR: usize, F: FrameAccumu
 
 impl<const ACTIVE_TAPS: usize, const FACTOR: usize, F> Default for InterpF<ACTIVE_TAPS, FACTOR, F>
 where
-    F: FrameAccumulator + Default,
+    F: FrameAccumulator<Sample = f32> + Default,
 {
     fn default() -> Self {
         Self::new()
@@ -86,27 +128,22 @@ where
 
 impl<const ACTIVE_TAPS: usize, const FACTOR: usize, F> InterpF<ACTIVE_TAPS, FACTOR, F>
 where
-    F: FrameAccumulator + Default,
+    F: FrameAccumulator<Sample = f32> + Default,
 {
     pub fn new() -> Self {
-        assert_eq!(ACTIVE_TAPS * FACTOR, TAPS);
+        let computed;
+        let coeffs = match precalculated_filter_coefficients(FACTOR, ACTIVE_TAPS * FACTOR) {
+            Some(coeffs) => coeffs,
+            None => {
+                computed = filter_coefficients(FACTOR, ACTIVE_TAPS * FACTOR);
+                &computed
+            }
+        };
+        assert_eq!(coeffs.len(), ACTIVE_TAPS * FACTOR);
 
         let mut filter: [[_; FACTOR]; ACTIVE_TAPS] = [[0f32; FACTOR]; ACTIVE_TAPS];
-        for (j, coeff) in filter.iter_mut().flat_map(|x| x.iter_mut()).enumerate() {
-            let j = j as f64;
-            // Calculate Hanning window,
-            let window = TAPS + 1;
-            // Ignore one tap. (Last tap is zero anyways, and we want to hit an even multiple of 48)
-            let window = (window - 1) as f64;
-            let w = 0.5 * (1.0 - f64::cos(2.0 * PI * j / window));
-
-            // Calculate sinc and apply hanning window
-            let m = j - window / 2.0;
-            *coeff = if m.abs() > ALMOST_ZERO {
-                w * f64::sin(m * PI / FACTOR as f64) / (m * PI / FACTOR as f64)
-            } else {
-                w
-            } as f32;
+        for (dst, src) in Iterator::zip(filter.iter_mut().flat_map(|x| x.iter_mut()), coeffs) {
+            *dst = *src;
         }
 
         Self {
@@ -119,10 +156,16 @@ where
         // Write in Frames in reverse, to enable forward-scanning with filter
         self.buffer.push_front(frame);
 
-        let mut output: [F; FACTOR] = [Default::default(); FACTOR];
-
         let buf = self.buffer.as_ref();
 
+        if crate::utils::simd_enabled() {
+            if let Some(output) = simd::interpolate(&self.filter, buf) {
+                return output;
+            }
+        }
+
+        let mut output: [F; FACTOR] = [Default::default(); FACTOR];
+
         for (filter_coeffs, input_frame) in Iterator::zip(self.filter.iter(), buf) {
             for (output_frame, coeff) in Iterator::zip(output.iter_mut(), filter_coeffs) {
                 output_frame.scale_add(input_frame, *coeff);
@@ -135,4 +178,197 @@ where
     pub fn reset(&mut self) {
         self.buffer = RollingBuffer::new();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+mod simd {
+    use core::arch::aarch64::*;
+
+    use crate::utils::Frame;
+
+    /// Interpolate with NEON if the factor is 4 or 8 and there are at most 8 channels, otherwise
+    /// return `None`.
+    ///
+    /// The output phases of each channel are calculated at once, 4 per vector. Each lane does
+    /// exactly the same operations in the same order as the scalar implementation, so the results
+    /// are identical.
+    #[inline]
+    pub fn interpolate<const ACTIVE_TAPS: usize, const FACTOR: usize, F>(
+        filter: &[[f32; FACTOR]; ACTIVE_TAPS],
+        buf: &[F; ACTIVE_TAPS],
+    ) -> Option<[F; FACTOR]>
+    where
+        F: Frame<Sample = f32>,
+    {
+        if (FACTOR != 4 && FACTOR != 8) || F::CHANNELS > 8 {
+            return None;
+        }
+
+        let mut output = [[0f32; 8]; 8];
+
+        // Safety: NEON is enabled at compile-time and all loads and stores are within the
+        // arrays as FACTOR is 4 or 8
+        unsafe {
+            let mut acc = [[vdupq_n_f32(0.0); 2]; 8];
+
+            for (filter_coeffs, input_frame) in Iterator::zip(filter.iter(), buf) {
+                for (i, coeffs) in filter_coeffs.chunks_exact(4).enumerate() {
+                    let coeffs = vld1q_f32(coeffs.as_ptr());
+                    for (c, acc) in acc.iter_mut().enumerate().take(F::CHANNELS) {
+                        let input = *input_frame.channel_unchecked(c);
+                        #[cfg(feature = "precision-true-peak")]
+                        {
+                            acc[i] = vfmaq_n_f32(acc[i], coeffs, input);
+                        }
+                        #[cfg(not(feature = "precision-true-peak"))]
+                        {
+                            acc[i] = vmlaq_n_f32(acc[i], coeffs, input);
+                        }
+                    }
+                }
+            }
+
+            for (acc, output) in Iterator::zip(acc.iter(), output.iter_mut()) {
+                vst1q_f32(output.as_mut_ptr(), acc[0]);
+                vst1q_f32(output.as_mut_ptr().add(4), acc[1]);
+            }
+        }
+
+        Some(core::array::from_fn(|phase| {
+            F::from_fn(|c| output[c][phase])
+        }))
+    }
+}
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse"
+))]
+mod simd {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    use crate::utils::Frame;
+
+    /// Interpolate with SSE if the factor is 4 or 8 and there are at most 8 channels, otherwise
+    /// return `None`. With the `precision-true-peak` feature FMA has to be enabled at
+    /// compile-time, too.
+    ///
+    /// The output phases of each channel are calculated at once, 4 per vector. Each lane does
+    /// exactly the same operations in the same order as the scalar implementation, so the results
+    /// are identical.
+    #[inline]
+    pub fn interpolate<const ACTIVE_TAPS: usize, const FACTOR: usize, F>(
+        filter: &[[f32; FACTOR]; ACTIVE_TAPS],
+        buf: &[F; ACTIVE_TAPS],
+    ) -> Option<[F; FACTOR]>
+    where
+        F: Frame<Sample = f32>,
+    {
+        if (FACTOR != 4 && FACTOR != 8)
+            || F::CHANNELS > 8
+            || cfg!(all(
+                feature = "precision-true-peak",
+                not(target_feature = "fma")
+            ))
+        {
+            return None;
+        }
+
+        let mut output = [[0f32; 8]; 8];
+
+        // Safety: SSE, and FMA if used, are enabled at compile-time and all loads and stores are
+        // within the arrays as FACTOR is 4 or 8
+        unsafe {
+            let mut acc = [[_mm_setzero_ps(); 2]; 8];
+
+            for (filter_coeffs, input_frame) in Iterator::zip(filter.iter(), buf) {
+                for (i, coeffs) in filter_coeffs.chunks_exact(4).enumerate() {
+                    let coeffs = _mm_loadu_ps(coeffs.as_ptr());
+                    for (c, acc) in acc.iter_mut().enumerate().take(F::CHANNELS) {
+                        let input = _mm_set1_ps(*input_frame.channel_unchecked(c));
+                        #[cfg(feature = "precision-true-peak")]
+                        {
+                            acc[i] = _mm_fmadd_ps(coeffs, input, acc[i]);
+                        }
+                        #[cfg(not(feature = "precision-true-peak"))]
+                        {
+                            acc[i] = _mm_add_ps(acc[i], _mm_mul_ps(coeffs, input));
+                        }
+                    }
+                }
+            }
+
+            for (acc, output) in Iterator::zip(acc.iter(), output.iter_mut()) {
+                _mm_storeu_ps(output.as_mut_ptr(), acc[0]);
+                _mm_storeu_ps(output.as_mut_ptr().add(4), acc[1]);
+            }
+        }
+
+        Some(core::array::from_fn(|phase| {
+            F::from_fn(|c| output[c][phase])
+        }))
+    }
+}
+
+#[cfg(not(any(
+    all(target_arch = "aarch64", target_feature = "neon"),
+    all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "sse"
+    )
+)))]
+mod simd {
+    use crate::utils::Frame;
+
+    /// No SIMD implementation available, the scalar implementation is used.
+    #[inline(always)]
+    pub fn interpolate<const ACTIVE_TAPS: usize, const FACTOR: usize, F>(
+        _filter: &[[f32; FACTOR]; ACTIVE_TAPS],
+        _buf: &[F; ACTIVE_TAPS],
+    ) -> Option<[F; FACTOR]>
+    where
+        F: Frame<Sample = f32>,
+    {
+        None
+    }
+}
+
+// Only the buffered input frames are serialized, newest first. The filter coefficients are
+// recalculated and the position in the rolling buffer is reconstructed when deserializing.
+#[cfg(feature = "serde")]
+impl<const ACTIVE_TAPS: usize, const FACTOR: usize, F> serde::Serialize
+    for InterpF<ACTIVE_TAPS, FACTOR, F>
+where
+    F: FrameAccumulator + serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.buffer.as_ref())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const ACTIVE_TAPS: usize, const FACTOR: usize, F> serde::Deserialize<'de>
+    for InterpF<ACTIVE_TAPS, FACTOR, F>
+where
+    F: FrameAccumulator<Sample = f32> + Default + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let frames = Vec::<F>::deserialize(deserializer)?;
+        if frames.len() != ACTIVE_TAPS {
+            return Err(serde::de::Error::invalid_length(
+                frames.len(),
+                &"one frame per active filter tap",
+            ));
+        }
+
+        let mut interp = Self::new();
+        for frame in frames.into_iter().rev() {
+            interp.buffer.push_front(frame);
+        }
+
+        Ok(interp)
+    }
+}
@@ -31,27 +31,94 @@
 //!  Features:
 //!   * Implements M, S and I modes ([EBU - TECH 3341](https://tech.ebu.ch/docs/tech/tech3341.pdf))
 //!   * Implements loudness range measurement ([EBU - TECH 3342](https://tech.ebu.ch/docs/tech/tech3342.pdf))
-//!   * True peak scanning
+//!   * True peak scanning, also standalone via [`TruePeak`](struct.TruePeak.html)
 //!   * Supports all samplerates by recalculation of the filter coefficients
+//!
+//!  Optional cargo features:
+//!   * `std` (enabled by default): Without it the crate is `no_std` and only requires `alloc`
+//!   * `wasm`: JavaScript bindings via `wasm-bindgen`, see the [`wasm`](wasm/index.html) module
+//!   * `serde`: Serialization of the complete [`EbuR128`](struct.EbuR128.html) state and of
+//!     [`Mode`](struct.Mode.html) and [`Channel`](enum.Channel.html)
+//!   * `json`: Export loudness reports as JSON with
+//!     [`EbuR128::export_json`](struct.EbuR128.html#method.export_json)
+//!   * `dasp`: Add frames of the [`dasp`](https://docs.rs/dasp) crates with
+//!     [`EbuR128::add_dasp_frames`](struct.EbuR128.html#method.add_dasp_frames). Without `std`
+//!     this currently requires a nightly toolchain
+//!   * `symphonia`: Add decoded buffers of the [`symphonia`](https://docs.rs/symphonia) crates with
+//!     [`EbuR128::add_audio_buffer`](struct.EbuR128.html#method.add_audio_buffer)
+//!   * `hound`: Analyze a WAV file read with [`hound`](https://docs.rs/hound) in one call with
+//!     [`analyze_wav`](fn.analyze_wav.html)
+//!   * `cpal`: Live metering of `cpal` input streams, see the [`live`](live/index.html) module
+//!   * `rayon`: Filter and interpolate the channels of large buffers in parallel, with identical
+//!     results
+//!   * `tokio`: Analyze a `Stream` of samples without blocking the async runtime, see
+//!     [`EbuR128::add_frames_stream`](struct.EbuR128.html#method.add_frames_stream)
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 mod ebur128;
 pub use self::ebur128::*;
 pub use self::utils::energy_to_loudness;
+#[doc(hidden)]
+pub use self::utils::set_simd_enabled;
+
+mod loudness;
+pub use self::loudness::LoudnessValue;
 
 pub(crate) mod interp;
+#[doc(hidden)]
+pub use self::interp::{
+    filter_coefficients as interp_filter_coefficients,
+    precalculated_filter_coefficients as precalculated_interp_filter_coefficients,
+};
 
-pub(crate) mod true_peak;
+mod true_peak;
+pub use self::true_peak::{TruePeak, TruePeakEvent, TruePeakOversampling};
 
 pub(crate) mod history;
+pub use self::history::HistogramBin;
+
+mod stats;
+pub use self::stats::LoudnessStats;
 
 #[allow(clippy::excessive_precision)]
 mod histogram_bins;
 
 pub(crate) mod filter;
+#[doc(hidden)]
+pub use self::filter::simd_filter_channels;
+pub use self::filter::{filter_coefficients, response_db, KWeightingCoefficients};
 
 pub(crate) mod utils;
 
-pub(crate) use utils::{Interleaved, Planar, Samples};
+pub(crate) mod math;
+
+mod report;
+
+pub(crate) use utils::{Interleaved, InterleavedI24, Planar, PlanarI24, Samples};
+
+// The analyzers must be usable from other threads, e.g. for analyzing in the background
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<EbuR128>();
+    assert_send_sync::<TruePeak>();
+};
+
+#[cfg(feature = "tokio")]
+mod stream;
+
+#[cfg(feature = "hound")]
+mod wav;
+#[cfg(feature = "hound")]
+pub use self::wav::{analyze_wav, Analysis};
+
+#[cfg(feature = "cpal")]
+pub mod live;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 #[cfg(feature = "capi")]
 #[allow(clippy::missing_safety_doc)]
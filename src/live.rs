@@ -0,0 +1,221 @@
+//! Live metering of [`cpal`](https://docs.rs/cpal) input streams.
+//!
+//! The audio callback only converts the samples to `f32` and pushes them into a lock-free
+//! single-producer single-consumer ring buffer, without any allocations or locking. The actual
+//! analysis happens on the application thread whenever [`Meter::poll`](struct.Meter.html#method.poll)
+//! is called, e.g. once per frame of the user interface.
+//!
+//! Because of this the measurements lag behind the input by up to the polling interval, in
+//! addition to the buffering of the audio device. The ring buffer holds 1s of audio by default,
+//! so `poll()` has to be called at least that often. Otherwise complete buffers of the device are
+//! dropped and counted in [`Meter::dropped_frames`](struct.Meter.html#method.dropped_frames).
+//!
+//! ```no_run
+//! use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+//! use ebur128::{live::Meter, Mode};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let device = cpal::default_host()
+//!     .default_input_device()
+//!     .ok_or("No input device")?;
+//! let config = device.default_input_config()?;
+//!
+//! let (mut meter, stream) = Meter::build_input_stream(&device, &config, Mode::S)?;
+//! stream.play()?;
+//!
+//! loop {
+//!     std::thread::sleep(std::time::Duration::from_millis(100));
+//!     meter.poll()?;
+//!     println!("{:.1} LUFS", meter.shortterm()?);
+//! }
+//! # }
+//! ```
+
+use crate::{EbuR128, Error, Mode};
+
+use cpal::traits::DeviceTrait;
+use cpal::{FromSample, Sample, SizedSample};
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Default duration of audio in ms that fits into the ring buffer.
+const DEFAULT_BUFFER_MS: u32 = 1000;
+
+/// Live loudness meter, see the [module documentation](index.html).
+pub struct Meter {
+    ebu: EbuR128,
+    consumer: rtrb::Consumer<f32>,
+    dropped_frames: Arc<AtomicU64>,
+    stream_failed: Arc<AtomicBool>,
+}
+
+/// Producer side of a [`Meter`](struct.Meter.html) to be used in the audio callback.
+pub struct MeterInput {
+    producer: rtrb::Producer<f32>,
+    channels: usize,
+    dropped_frames: Arc<AtomicU64>,
+}
+
+impl MeterInput {
+    /// Push interleaved samples of any `cpal` sample format.
+    ///
+    /// This never blocks or allocates. If the ring buffer does not have enough space for all
+    /// samples they are dropped completely.
+    pub fn push<T: Sample>(&mut self, data: &[T])
+    where
+        f32: FromSample<T>,
+    {
+        match self.producer.write_chunk_uninit(data.len()) {
+            Ok(chunk) => {
+                chunk.fill_from_iter(data.iter().map(|s| s.to_sample::<f32>()));
+            }
+            Err(_) => {
+                self.dropped_frames
+                    .fetch_add((data.len() / self.channels) as u64, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl Meter {
+    /// Create a new meter and the input to push samples into from the audio callback.
+    ///
+    /// The ring buffer holds `buffer_ms` ms of audio. Fails with the same errors as
+    /// [`EbuR128::new`](../struct.EbuR128.html#method.new).
+    pub fn new(
+        channels: u32,
+        rate: u32,
+        mode: Mode,
+        buffer_ms: u32,
+    ) -> Result<(Meter, MeterInput), Error> {
+        let ebu = EbuR128::new(channels, rate, mode)?;
+
+        let frames = core::cmp::max(u64::from(rate) * u64::from(buffer_ms) / 1000, 1);
+        let (producer, consumer) = rtrb::RingBuffer::new(frames as usize * channels as usize);
+        let dropped_frames = Arc::new(AtomicU64::new(0));
+
+        Ok((
+            Meter {
+                ebu,
+                consumer,
+                dropped_frames: dropped_frames.clone(),
+                stream_failed: Arc::new(AtomicBool::new(false)),
+            },
+            MeterInput {
+                producer,
+                channels: channels as usize,
+                dropped_frames,
+            },
+        ))
+    }
+
+    /// Build an input stream on `device` that feeds a new meter.
+    ///
+    /// The number of channels, sample rate and sample format are taken from `config`, e.g. from
+    /// `DeviceTrait::default_input_config()`. The stream has to be started with
+    /// `StreamTrait::play()` afterwards.
+    ///
+    /// Fails with `Error::Io` if the stream can't be built or the sample format is not supported,
+    /// and otherwise with the same errors as [`EbuR128::new`](../struct.EbuR128.html#method.new).
+    pub fn build_input_stream(
+        device: &cpal::Device,
+        config: &cpal::SupportedStreamConfig,
+        mode: Mode,
+    ) -> Result<(Meter, cpal::Stream), Error> {
+        let (meter, input) = Meter::new(
+            u32::from(config.channels()),
+            config.sample_rate().0,
+            mode,
+            DEFAULT_BUFFER_MS,
+        )?;
+
+        let failed = meter.stream_failed.clone();
+        let sample_format = config.sample_format();
+        let config = config.config();
+        let stream = match sample_format {
+            cpal::SampleFormat::I8 => build::<i8>(device, &config, input, failed),
+            cpal::SampleFormat::I16 => build::<i16>(device, &config, input, failed),
+            cpal::SampleFormat::I32 => build::<i32>(device, &config, input, failed),
+            cpal::SampleFormat::I64 => build::<i64>(device, &config, input, failed),
+            cpal::SampleFormat::U8 => build::<u8>(device, &config, input, failed),
+            cpal::SampleFormat::U16 => build::<u16>(device, &config, input, failed),
+            cpal::SampleFormat::U32 => build::<u32>(device, &config, input, failed),
+            cpal::SampleFormat::U64 => build::<u64>(device, &config, input, failed),
+            cpal::SampleFormat::F32 => build::<f32>(device, &config, input, failed),
+            cpal::SampleFormat::F64 => build::<f64>(device, &config, input, failed),
+            _ => Err(Error::Io),
+        }?;
+
+        Ok((meter, stream))
+    }
+
+    /// Analyze all samples that were pushed since the last call.
+    ///
+    /// Fails with `Error::Io` if the stream reported an error.
+    pub fn poll(&mut self) -> Result<(), Error> {
+        if self.stream_failed.load(Ordering::Relaxed) {
+            return Err(Error::Io);
+        }
+
+        let slots = self.consumer.slots();
+        match self.consumer.read_chunk(slots) {
+            Ok(chunk) => self.ebu.add_samples_iter(chunk),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Get the momentary loudness (last 400ms) in LUFS.
+    pub fn momentary(&self) -> Result<f64, Error> {
+        self.ebu.loudness_momentary()
+    }
+
+    /// Get the short-term loudness (last 3s) in LUFS.
+    pub fn shortterm(&self) -> Result<f64, Error> {
+        self.ebu.loudness_shortterm()
+    }
+
+    /// Get the integrated loudness in LUFS.
+    pub fn integrated(&self) -> Result<f64, Error> {
+        self.ebu.loudness_global()
+    }
+
+    /// Get the maximum true peak of all channels in dBTP.
+    pub fn true_peak(&self) -> Result<f64, Error> {
+        self.ebu.true_peak_max_all_channels()
+    }
+
+    /// Number of frames that were dropped because the ring buffer was full.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames.load(Ordering::Relaxed)
+    }
+
+    /// Get the analyzer for all other measurements.
+    pub fn analyzer(&self) -> &EbuR128 {
+        &self.ebu
+    }
+
+    /// Reset the analyzer, e.g. to start a new integrated loudness measurement.
+    pub fn reset(&mut self) {
+        self.ebu.reset();
+    }
+}
+
+fn build<T: SizedSample>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut input: MeterInput,
+    failed: Arc<AtomicBool>,
+) -> Result<cpal::Stream, Error>
+where
+    f32: FromSample<T>,
+{
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| input.push(data),
+            move |_| failed.store(true, Ordering::Relaxed),
+            None,
+        )
+        .map_err(|_| Error::Io)
+}
@@ -0,0 +1,98 @@
+// Copyright (c) 2011 Jan Kokemüller
+// Copyright (c) 2020 Sebastian Dröge <sebastian@centricular.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::{Add, Sub};
+
+/// A loudness value in LUFS.
+///
+/// Displays as e.g. `-23.0 LUFS`, with one decimal unless a precision is given in the format
+/// string. Can be compared with plain `f64` values in LUFS, and gains in dB can be added or
+/// subtracted.
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct LoudnessValue(pub f64);
+
+impl LoudnessValue {
+    /// Get the loudness in LUFS.
+    pub fn to_lufs(self) -> f64 {
+        self.0
+    }
+}
+
+impl From<f64> for LoudnessValue {
+    fn from(lufs: f64) -> Self {
+        LoudnessValue(lufs)
+    }
+}
+
+impl From<LoudnessValue> for f64 {
+    fn from(v: LoudnessValue) -> Self {
+        v.0
+    }
+}
+
+impl fmt::Display for LoudnessValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let precision = f.precision().unwrap_or(1);
+        write!(f, "{:.*} LUFS", precision, self.0)
+    }
+}
+
+impl PartialEq<f64> for LoudnessValue {
+    fn eq(&self, other: &f64) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialOrd<f64> for LoudnessValue {
+    fn partial_cmp(&self, other: &f64) -> Option<Ordering> {
+        self.0.partial_cmp(other)
+    }
+}
+
+impl Add<f64> for LoudnessValue {
+    type Output = LoudnessValue;
+
+    fn add(self, gain: f64) -> LoudnessValue {
+        LoudnessValue(self.0 + gain)
+    }
+}
+
+impl Sub<f64> for LoudnessValue {
+    type Output = LoudnessValue;
+
+    fn sub(self, gain: f64) -> LoudnessValue {
+        LoudnessValue(self.0 - gain)
+    }
+}
+
+/// The difference of two loudness values in LU.
+impl Sub for LoudnessValue {
+    type Output = f64;
+
+    fn sub(self, other: LoudnessValue) -> f64 {
+        self.0 - other.0
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright (c) 2011 Jan Kokemüller
+// Copyright (c) 2020 Sebastian Dröge <sebastian@centricular.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Floating point functions that are not available in `core`, using `libm` without `std`.
+
+#[cfg(feature = "std")]
+mod imp {
+    #[inline]
+    pub fn powf(x: f64, y: f64) -> f64 {
+        x.powf(y)
+    }
+
+    #[inline]
+    pub fn log10(x: f64) -> f64 {
+        x.log10()
+    }
+
+    #[inline]
+    pub fn round(x: f64) -> f64 {
+        x.round()
+    }
+
+    #[inline]
+    pub fn sqrt(x: f64) -> f64 {
+        x.sqrt()
+    }
+
+    #[inline]
+    pub fn tan(x: f64) -> f64 {
+        x.tan()
+    }
+
+    #[inline]
+    pub fn sin(x: f64) -> f64 {
+        x.sin()
+    }
+
+    #[inline]
+    pub fn cos(x: f64) -> f64 {
+        x.cos()
+    }
+
+    #[inline(always)]
+    #[allow(dead_code)]
+    pub fn mul_addf(x: f32, y: f32, z: f32) -> f32 {
+        x.mul_add(y, z)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod imp {
+    pub use libm::{cos, log10, pow as powf, round, sin, sqrt, tan};
+
+    #[inline(always)]
+    #[allow(dead_code)]
+    pub fn mul_addf(x: f32, y: f32, z: f32) -> f32 {
+        libm::fmaf(x, y, z)
+    }
+}
+
+pub(crate) use self::imp::*;
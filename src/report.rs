@@ -0,0 +1,114 @@
+// Copyright (c) 2011 Jan Kokemüller
+// Copyright (c) 2020 Sebastian Dröge <sebastian@centricular.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use crate::{math, EbuR128, Error};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Loudness report as exported by [`EbuR128::export_json`](struct.EbuR128.html#method.export_json)
+/// and [`EbuR128::export_csv`](struct.EbuR128.html#method.export_csv).
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+struct Report {
+    integrated_lufs: f64,
+    loudness_range_lu: f64,
+    true_peak_dbtp: f64,
+    sample_peak_dbfs: f64,
+    measurement_duration_s: f64,
+    true_peak: Vec<f64>,
+}
+
+impl EbuR128 {
+    fn report(&self) -> Result<Report, Error> {
+        let true_peak = self
+            .true_peak_per_channel()?
+            .into_iter()
+            .map(|peak| 20.0 * math::log10(peak))
+            .collect::<Vec<_>>();
+        let sample_peak = self
+            .sample_peak_per_channel()?
+            .into_iter()
+            .fold(0.0, f64::max);
+
+        Ok(Report {
+            integrated_lufs: self.loudness_global()?,
+            loudness_range_lu: self.loudness_range()?,
+            true_peak_dbtp: true_peak.iter().copied().fold(-f64::INFINITY, f64::max),
+            sample_peak_dbfs: 20.0 * math::log10(sample_peak),
+            measurement_duration_s: self.frames_processed() as f64 / self.rate() as f64,
+            true_peak,
+        })
+    }
+
+    /// Export a loudness report as JSON object.
+    ///
+    /// The report contains the integrated loudness in LUFS (`integrated_lufs`), the loudness
+    /// range in LU (`loudness_range_lu`), the maximum true peak in dBTP (`true_peak_dbtp`), the
+    /// maximum sample peak in dBFS (`sample_peak_dbfs`), the duration of the processed audio in
+    /// seconds (`measurement_duration_s`) and the true peak per channel in dBTP (`true_peak`),
+    /// with the units of EBU TECH 3341 and 3342. Values of `-f64::INFINITY`, e.g. for silence, are
+    /// exported as `null`.
+    ///
+    /// Fails with `Error::InvalidMode` unless `Mode::I`, `Mode::LRA`, `Mode::SAMPLE_PEAK` and
+    /// `Mode::TRUE_PEAK` are all set and with `Error::NoChange` if no frames were processed yet.
+    #[cfg(feature = "json")]
+    pub fn export_json(&self) -> Result<String, Error> {
+        serde_json::to_string(&self.report()?).map_err(|_| Error::NoMem)
+    }
+
+    /// Export a loudness report as CSV.
+    ///
+    /// The first line contains the column names and the second line the values of the report
+    /// described in [`EbuR128::export_json`](struct.EbuR128.html#method.export_json), with one
+    /// column `true_peak_<channel>` per channel. Values of `-f64::INFINITY` are exported as
+    /// `-inf`.
+    ///
+    /// Fails with `Error::InvalidMode` unless `Mode::I`, `Mode::LRA`, `Mode::SAMPLE_PEAK` and
+    /// `Mode::TRUE_PEAK` are all set and with `Error::NoChange` if no frames were processed yet.
+    pub fn export_csv(&self) -> Result<String, Error> {
+        let report = self.report()?;
+
+        let mut csv = String::from(
+            "integrated_lufs,loudness_range_lu,true_peak_dbtp,sample_peak_dbfs,measurement_duration_s",
+        );
+        for channel in 0..report.true_peak.len() {
+            write!(csv, ",true_peak_{}", channel).unwrap();
+        }
+
+        write!(
+            csv,
+            "\n{},{},{},{},{}",
+            report.integrated_lufs,
+            report.loudness_range_lu,
+            report.true_peak_dbtp,
+            report.sample_peak_dbfs,
+            report.measurement_duration_s
+        )
+        .unwrap();
+        for true_peak in &report.true_peak {
+            write!(csv, ",{}", true_peak).unwrap();
+        }
+        csv.push('\n');
+
+        Ok(csv)
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright (c) 2011 Jan Kokemüller
+// Copyright (c) 2020 Sebastian Dröge <sebastian@centricular.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use crate::{energy_to_loudness, math, Error, HistogramBin};
+
+use alloc::vec::Vec;
+
+/// Statistics of the loudness distribution of gating blocks.
+///
+/// Returned by
+/// [`EbuR128::shortterm_statistics`](struct.EbuR128.html#method.shortterm_statistics). Without
+/// `Mode::HISTOGRAM` all values are calculated from the loudness of the individual blocks. With
+/// it each block is represented by the center of its histogram bin, except for the percentiles,
+/// which are interpolated within the bins.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoudnessStats {
+    /// Number of blocks.
+    pub count: u64,
+    /// Loudness of the mean energy of the blocks in LUFS, i.e. averaged like the integrated
+    /// loudness.
+    pub energy_mean: f64,
+    /// Arithmetic mean of the loudness of the blocks in LUFS.
+    pub mean: f64,
+    /// Median loudness of the blocks in LUFS, see
+    /// [`LoudnessStats::percentile`](#method.percentile).
+    pub median: f64,
+    /// Standard deviation of the loudness of the blocks in LU.
+    pub stddev: f64,
+    distribution: Distribution,
+}
+
+/// Loudness of the blocks in LUFS.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Distribution {
+    /// Loudness of each block in ascending order.
+    Blocks(Vec<f64>),
+    /// Non-empty histogram bins in ascending order.
+    Bins(Vec<HistogramBin>),
+}
+
+impl LoudnessStats {
+    /// Calculates the statistics of the given block energies, sorted in ascending order. Returns
+    /// `None` if there are no blocks.
+    pub(crate) fn from_energies(energies: &[f64]) -> Option<Self> {
+        if energies.is_empty() {
+            return None;
+        }
+
+        let count = energies.len() as u64;
+        let energy_sum = energies.iter().sum::<crate::history::CompensatedSum>();
+        let loudness = energies
+            .iter()
+            .map(|energy| energy_to_loudness(*energy))
+            .collect::<Vec<_>>();
+
+        let mean = loudness.iter().sum::<f64>() / count as f64;
+        let variance = loudness
+            .iter()
+            .map(|loudness| (loudness - mean) * (loudness - mean))
+            .sum::<f64>()
+            / count as f64;
+
+        Some(Self::new(
+            count,
+            energy_sum.value(),
+            mean,
+            variance,
+            Distribution::Blocks(loudness),
+        ))
+    }
+
+    /// Calculates the statistics of the given non-empty histogram bins in ascending order and the
+    /// sum of the energies at the bin centers of all blocks. Returns `None` if there are no blocks.
+    pub(crate) fn from_bins(bins: Vec<HistogramBin>, energy_sum: f64) -> Option<Self> {
+        let count = bins.iter().map(|bin| bin.count).sum::<u64>();
+        if count == 0 {
+            return None;
+        }
+
+        let center = |bin: &HistogramBin| (bin.lufs_low + bin.lufs_high) / 2.0;
+        let mean = bins
+            .iter()
+            .map(|bin| bin.count as f64 * center(bin))
+            .sum::<f64>()
+            / count as f64;
+        let variance = bins
+            .iter()
+            .map(|bin| bin.count as f64 * (center(bin) - mean) * (center(bin) - mean))
+            .sum::<f64>()
+            / count as f64;
+
+        Some(Self::new(
+            count,
+            energy_sum,
+            mean,
+            variance,
+            Distribution::Bins(bins),
+        ))
+    }
+
+    fn new(
+        count: u64,
+        energy_sum: f64,
+        mean: f64,
+        variance: f64,
+        distribution: Distribution,
+    ) -> Self {
+        let mut stats = LoudnessStats {
+            count,
+            energy_mean: energy_to_loudness(energy_sum / count as f64),
+            mean,
+            median: 0.0,
+            stddev: math::sqrt(variance),
+            distribution,
+        };
+        stats.median = stats.percentile_unchecked(50.0);
+
+        stats
+    }
+
+    /// Get the loudness in LUFS at `percentile` (0 to 100) of the blocks, e.g. 99 for the loudness
+    /// that only 1% of the blocks exceed.
+    ///
+    /// Without `Mode::HISTOGRAM` this interpolates linearly between the blocks of the two closest
+    /// ranks, so 0 and 100 are the quietest and the loudest block. With `Mode::HISTOGRAM` the
+    /// blocks are assumed to be spread evenly over their histogram bins. Unlike the nearest-rank
+    /// percentiles of the loudness range this changes continuously with `percentile`.
+    ///
+    /// Fails with `Error::OutOfRange` if `percentile` is outside of 0 to 100.
+    pub fn percentile(&self, percentile: f64) -> Result<f64, Error> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(Error::OutOfRange);
+        }
+
+        Ok(self.percentile_unchecked(percentile))
+    }
+
+    fn percentile_unchecked(&self, percentile: f64) -> f64 {
+        match self.distribution {
+            Distribution::Blocks(ref loudness) => {
+                let rank = (loudness.len() - 1) as f64 * percentile / 100.0;
+                let index = rank as usize;
+                match loudness.get(index + 1) {
+                    Some(next) => {
+                        loudness[index] + (next - loudness[index]) * (rank - index as f64)
+                    }
+                    None => loudness[index],
+                }
+            }
+            Distribution::Bins(ref bins) => {
+                let rank = self.count as f64 * percentile / 100.0;
+                let mut below = 0.0;
+                for bin in bins {
+                    let count = bin.count as f64;
+                    if below + count >= rank {
+                        return bin.lufs_low
+                            + (bin.lufs_high - bin.lufs_low) * (rank - below) / count;
+                    }
+                    below += count;
+                }
+
+                bins.last().map_or(f64::NAN, |bin| bin.lufs_high)
+            }
+        }
+    }
+}
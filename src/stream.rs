@@ -0,0 +1,73 @@
+// Copyright (c) 2011 Jan Kokemüller
+// Copyright (c) 2020 Sebastian Dröge <sebastian@centricular.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use crate::{EbuR128, Error};
+
+use core::future;
+use core::pin::Pin;
+use futures_core::Stream;
+
+impl EbuR128 {
+    /// Add interleaved frames from a `Stream` of chunks to be processed.
+    ///
+    /// Each chunk is processed with [`EbuR128::add_frames_f32`](struct.EbuR128.html#method.add_frames_f32)
+    /// on the blocking thread pool of `tokio` so that the filtering does not block the async
+    /// runtime. For this the analyzer is moved into the returned future and handed back together
+    /// with the result once the stream has ended or a chunk failed to be processed. If the
+    /// future is dropped before, the analyzer is dropped too.
+    ///
+    /// Must be awaited from within a `tokio` runtime.
+    ///
+    /// ```no_run
+    /// # async fn analyze(chunks: impl futures_core::Stream<Item = Vec<f32>> + Unpin) {
+    /// use ebur128::{EbuR128, Mode};
+    ///
+    /// let ebu = EbuR128::new(2, 48_000, Mode::I).unwrap();
+    /// let (ebu, res) = ebu.add_frames_stream(chunks).await;
+    /// res.unwrap();
+    /// println!("{:.1} LUFS", ebu.loudness_global().unwrap());
+    /// # }
+    /// ```
+    pub async fn add_frames_stream<S>(mut self, mut stream: S) -> (Self, Result<(), Error>)
+    where
+        S: Stream<Item = Vec<f32>> + Unpin,
+    {
+        while let Some(chunk) = future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            let (ebu, res) = match tokio::task::spawn_blocking(move || {
+                let res = self.add_frames_f32(&chunk);
+                (self, res)
+            })
+            .await
+            {
+                Ok(res) => res,
+                Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+                Err(_) => panic!("Runtime shut down while processing"),
+            };
+
+            self = ebu;
+            if res.is_err() {
+                return (self, res);
+            }
+        }
+
+        (self, Ok(()))
+    }
+}
@@ -19,14 +19,18 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
+use crate::ebur128::{Error, MAX_CHANNELS, MAX_RATE};
 use crate::interp::InterpF;
+use crate::math;
+use crate::utils::Frame;
 use crate::utils::{FrameAccumulator, Sample};
-use dasp_frame::Frame;
+use alloc::{boxed::Box, vec, vec::Vec};
 use smallvec::{smallvec, SmallVec};
 
 use UpsamplingScanner::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum UpsamplingScanner {
     Mono2F(InterpF<24, 2, [f32; 1]>),
     Stereo2F(InterpF<24, 2, [f32; 2]>),
@@ -38,22 +42,47 @@ enum UpsamplingScanner {
     Quad4F(InterpF<12, 4, [f32; 4]>),
     Surround4F(InterpF<12, 4, [f32; 6]>),
     OctoSurround4F(InterpF<12, 4, [f32; 8]>),
+    Mono8F(InterpF<12, 8, [f32; 1]>),
+    Stereo8F(InterpF<12, 8, [f32; 2]>),
+    Quad8F(InterpF<12, 8, [f32; 4]>),
+    Surround8F(InterpF<12, 8, [f32; 6]>),
+    OctoSurround8F(InterpF<12, 8, [f32; 8]>),
     Generic2F(Box<[InterpF<24, 2, [f32; 1]>]>),
     Generic4F(Box<[InterpF<12, 4, [f32; 1]>]>),
+    Generic8F(Box<[InterpF<12, 8, [f32; 1]>]>),
+}
+
+/// Oversampling factor for the true peak measurement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TruePeakOversampling {
+    /// Select the factor depending on the samplerate as recommended by ITU BS.1770-4: 4x below
+    /// 96kHz, 2x below 192kHz and no oversampling at higher samplerates.
+    #[default]
+    Auto,
+    /// 2x oversampling.
+    X2,
+    /// 4x oversampling.
+    X4,
+    /// 8x oversampling. This is more accurate than 4x for heavily clipped signals at 44.1kHz and
+    /// 48kHz but also twice as expensive.
+    X8,
 }
 
 impl UpsamplingScanner {
-    fn new(rate: u32, channels: u32) -> Option<Self> {
+    fn new(rate: u32, channels: u32, oversampling: TruePeakOversampling) -> Option<Self> {
         enum Factor {
+            Eight,
             Four,
             Two,
         }
-        let interp_factor = if rate < 96_000 {
-            Factor::Four
-        } else if rate < 192_000 {
-            Factor::Two
-        } else {
-            return None;
+        let interp_factor = match oversampling {
+            TruePeakOversampling::Auto if rate < 96_000 => Factor::Four,
+            TruePeakOversampling::Auto if rate < 192_000 => Factor::Two,
+            TruePeakOversampling::Auto => return None,
+            TruePeakOversampling::X2 => Factor::Two,
+            TruePeakOversampling::X4 => Factor::Four,
+            TruePeakOversampling::X8 => Factor::Eight,
         };
 
         Some(match (channels as usize, interp_factor) {
@@ -67,15 +96,80 @@ impl UpsamplingScanner {
             (4, Factor::Four) => Quad4F(InterpF::new()),
             (6, Factor::Four) => Surround4F(InterpF::new()),
             (8, Factor::Four) => OctoSurround4F(InterpF::new()),
+            (1, Factor::Eight) => Mono8F(InterpF::new()),
+            (2, Factor::Eight) => Stereo8F(InterpF::new()),
+            (4, Factor::Eight) => Quad8F(InterpF::new()),
+            (6, Factor::Eight) => Surround8F(InterpF::new()),
+            (8, Factor::Eight) => OctoSurround8F(InterpF::new()),
             (c, Factor::Two) => Generic2F(vec![InterpF::new(); c].into()),
             (c, Factor::Four) => Generic4F(vec![InterpF::new(); c].into()),
+            (c, Factor::Eight) => Generic8F(vec![InterpF::new(); c].into()),
         })
     }
 
-    pub fn check_true_peak<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
+    fn check_true_peak<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
         &mut self,
         src: S,
         peaks: &mut [f64],
+    ) {
+        // Interpolate the channels in parallel if each has its own interpolator and there are
+        // enough frames. Each channel's state stays with its channel so the results are identical.
+        #[cfg(feature = "rayon")]
+        if src.frames() >= crate::filter::PARALLEL_MIN_FRAMES {
+            macro_rules! tp_parallel_impl {
+                ( $interpolators:expr ) => {{
+                    use rayon::prelude::*;
+
+                    assert!(
+                        src.channels() == $interpolators.len() && src.channels() == peaks.len()
+                    );
+                    // Flushing denormals has to be enabled in each thread like in the caller
+                    let ftz = crate::filter::ftz::is_enabled();
+                    let src = &src;
+                    $interpolators
+                        .par_iter_mut()
+                        .zip(peaks.par_iter_mut())
+                        .enumerate()
+                        .for_each(|(c, (interpolator, channel_peak))| {
+                            let mut scan = || {
+                                src.foreach_sample(c, |s| {
+                                    for [new_sample] in &interpolator.interpolate([s.to_f32()]) {
+                                        let new_sample = new_sample.abs() as f64;
+                                        if new_sample > *channel_peak {
+                                            *channel_peak = new_sample;
+                                        }
+                                    }
+                                })
+                            };
+                            if ftz {
+                                crate::filter::ftz::with_ftz(|_| scan())
+                            } else {
+                                scan()
+                            }
+                        });
+                    return;
+                }};
+            }
+
+            match self {
+                Generic2F(interpolators) => tp_parallel_impl!(interpolators),
+                Generic4F(interpolators) => tp_parallel_impl!(interpolators),
+                Generic8F(interpolators) => tp_parallel_impl!(interpolators),
+                _ => (),
+            }
+        }
+
+        self.check_true_peak_with_output(src, peaks, |_, _, _| ())
+    }
+
+    /// Like `check_true_peak()` but additionally calls `output` with the channel, the index and the
+    /// value of each interpolated sample.
+    #[inline(always)]
+    fn check_true_peak_with_output<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
+        &mut self,
+        src: S,
+        peaks: &mut [f64],
+        mut output: impl FnMut(usize, usize, f32),
     ) {
         macro_rules! tp_specialized_impl {
             ( $channels:expr, $interpolator:expr ) => {{
@@ -83,10 +177,15 @@ impl UpsamplingScanner {
                 assert!(src.channels() == CHANNELS && peaks.len() == CHANNELS);
                 let mut tmp_peaks = <[f32; CHANNELS]>::from_fn(|i| peaks[i] as f32);
 
+                let mut index = 0;
                 src.foreach_frame(|frame: [T; CHANNELS]| {
-                    let frame_f32: [f32; CHANNELS] = Frame::map(frame, |s| s.to_sample::<f32>());
+                    let frame_f32: [f32; CHANNELS] = frame.map(|s| s.to_f32());
                     for new_frame in &$interpolator.interpolate(frame_f32) {
-                        tmp_peaks.retain_max_samples(&Frame::map(*new_frame, |s| s.abs()));
+                        tmp_peaks.retain_max_samples(&new_frame.map(|s| s.abs()));
+                        for (c, s) in new_frame.iter().enumerate() {
+                            output(c, index, *s);
+                        }
+                        index += 1;
                     }
                 });
                 for (dst, src) in Iterator::zip(peaks.into_iter(), &tmp_peaks) {
@@ -101,8 +200,12 @@ impl UpsamplingScanner {
                 for (c, (interpolator, channel_peak)) in
                     Iterator::zip($interpolators.iter_mut(), peaks.iter_mut()).enumerate()
                 {
+                    let output = &mut output;
+                    let mut index = 0;
                     src.foreach_sample(c, move |s| {
-                        for [new_sample] in &interpolator.interpolate([s.to_sample::<f32>()]) {
+                        for [new_sample] in &interpolator.interpolate([s.to_f32()]) {
+                            output(c, index, *new_sample);
+                            index += 1;
                             let new_sample = new_sample.abs() as f64;
                             if new_sample > *channel_peak {
                                 *channel_peak = new_sample;
@@ -124,11 +227,61 @@ impl UpsamplingScanner {
             Quad4F(interpolator) => tp_specialized_impl!(4, interpolator),
             Surround4F(interpolator) => tp_specialized_impl!(6, interpolator),
             OctoSurround4F(interpolator) => tp_specialized_impl!(8, interpolator),
+            Mono8F(interpolator) => tp_specialized_impl!(1, interpolator),
+            Stereo8F(interpolator) => tp_specialized_impl!(2, interpolator),
+            Quad8F(interpolator) => tp_specialized_impl!(4, interpolator),
+            Surround8F(interpolator) => tp_specialized_impl!(6, interpolator),
+            OctoSurround8F(interpolator) => tp_specialized_impl!(8, interpolator),
             Generic2F(interpolators) => tp_generic_impl!(interpolators),
             Generic4F(interpolators) => tp_generic_impl!(interpolators),
+            Generic8F(interpolators) => tp_generic_impl!(interpolators),
+        }
+    }
+
+    /// Number of interpolated samples per input sample.
+    fn factor(&self) -> usize {
+        match self {
+            Mono2F(_) | Stereo2F(_) | Quad2F(_) | Surround2F(_) | OctoSurround2F(_) => 2,
+            Mono4F(_) | Stereo4F(_) | Quad4F(_) | Surround4F(_) | OctoSurround4F(_) => 4,
+            Mono8F(_) | Stereo8F(_) | Quad8F(_) | Surround8F(_) | OctoSurround8F(_) => 8,
+            Generic2F(_) => 2,
+            Generic4F(_) => 4,
+            Generic8F(_) => 8,
         }
     }
 
+    /// Delay of the interpolated signal in input frames.
+    fn delay(&self) -> usize {
+        // Half of the active taps of the linear phase interpolation filter
+        match self {
+            Mono2F(_) | Stereo2F(_) | Quad2F(_) | Surround2F(_) | OctoSurround2F(_) => 12,
+            Generic2F(_) => 12,
+            _ => 6,
+        }
+    }
+
+    /// Check if this scanner is the one that would be created for the given configuration.
+    #[cfg(feature = "serde")]
+    fn is_compatible(&self, rate: u32, channels: u32, oversampling: TruePeakOversampling) -> bool {
+        let Some(expected) = Self::new(rate, channels, oversampling) else {
+            return false;
+        };
+
+        if core::mem::discriminant(self) != core::mem::discriminant(&expected) {
+            return false;
+        }
+
+        match self {
+            Generic2F(interpolators) => interpolators.len() == channels as usize,
+            Generic4F(interpolators) => interpolators.len() == channels as usize,
+            Generic8F(interpolators) => interpolators.len() == channels as usize,
+            _ => true,
+        }
+    }
+
+    /// Number of frames of silence needed to drain the filter for all interpolation factors.
+    const FLUSH_FRAMES: usize = 24;
+
     fn reset(&mut self) {
         match self {
             Mono2F(interpolator) => interpolator.reset(),
@@ -141,38 +294,498 @@ impl UpsamplingScanner {
             Quad4F(interpolator) => interpolator.reset(),
             Surround4F(interpolator) => interpolator.reset(),
             OctoSurround4F(interpolator) => interpolator.reset(),
+            Mono8F(interpolator) => interpolator.reset(),
+            Stereo8F(interpolator) => interpolator.reset(),
+            Quad8F(interpolator) => interpolator.reset(),
+            Surround8F(interpolator) => interpolator.reset(),
+            OctoSurround8F(interpolator) => interpolator.reset(),
             Generic2F(interpolators) => interpolators.iter_mut().for_each(InterpF::reset),
             Generic4F(interpolators) => interpolators.iter_mut().for_each(InterpF::reset),
+            Generic8F(interpolators) => interpolators.iter_mut().for_each(InterpF::reset),
+        }
+    }
+}
+
+/// Maximum number of stored true peak events.
+const MAX_TRUE_PEAK_EVENTS: usize = 10_000;
+
+/// A true peak excursion above the configured event threshold.
+///
+/// Consecutive interpolated samples above the threshold are combined into a single event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TruePeakEvent {
+    /// Channel of the excursion.
+    pub channel: u32,
+    /// Index of the input frame at which the maximum of the excursion happened, counted from the
+    /// first processed frame.
+    pub frame_index: u64,
+    /// Maximum true peak of the excursion.
+    pub peak: f64,
+}
+
+/// Detection of true peak events.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct TruePeakEvents {
+    /// Threshold as linear amplitude or `None` if no events are detected.
+    threshold: Option<f64>,
+    /// Number of input frames processed so far.
+    frames: u64,
+    /// Currently ongoing event per channel.
+    open: Box<[Option<TruePeakEvent>]>,
+    /// Finished events.
+    events: Vec<TruePeakEvent>,
+    /// Whether events were dropped because of `MAX_TRUE_PEAK_EVENTS`.
+    overflowed: bool,
+}
+
+impl TruePeakEvents {
+    fn new(channels: u32, threshold: Option<f64>) -> Self {
+        TruePeakEvents {
+            threshold,
+            frames: 0,
+            open: vec![None; channels as usize].into_boxed_slice(),
+            events: Vec::new(),
+            overflowed: false,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.frames = 0;
+        self.open.fill(None);
+        self.events.clear();
+        self.overflowed = false;
+    }
+
+    /// Check an interpolated sample of the given channel at the given input frame.
+    #[inline(always)]
+    fn check(&mut self, threshold: f64, channel: usize, frame_index: u64, sample: f32) {
+        let peak = sample.abs() as f64;
+        if peak > threshold {
+            match self.open[channel] {
+                Some(ref mut event) => {
+                    if peak > event.peak {
+                        event.peak = peak;
+                        event.frame_index = frame_index;
+                    }
+                }
+                None => {
+                    self.open[channel] = Some(TruePeakEvent {
+                        channel: channel as u32,
+                        frame_index,
+                        peak,
+                    });
+                }
+            }
+        } else if let Some(event) = self.open[channel].take() {
+            self.push(event);
+        }
+    }
+
+    /// Finish all ongoing events.
+    fn close(&mut self) {
+        for c in 0..self.open.len() {
+            if let Some(event) = self.open[c].take() {
+                self.push(event);
+            }
+        }
+    }
+
+    fn push(&mut self, event: TruePeakEvent) {
+        if self.events.len() < MAX_TRUE_PEAK_EVENTS {
+            self.events.push(event);
+        } else {
+            self.overflowed = true;
         }
     }
 }
 
-/// True peak measurement.
-#[derive(Debug)]
+/// Standalone true peak measurement.
+///
+/// Measures the inter-sample peaks of all channels without any loudness measurement, e.g. for
+/// use in a limiter. The signal is oversampled 4x below 96kHz and 2x below 192kHz, exactly like
+/// [`EbuR128`](struct.EbuR128.html) does with [`Mode::TRUE_PEAK`](struct.Mode.html). At higher
+/// samplerates the sample peak is used as true peak. A different oversampling factor can be
+/// selected with [`TruePeak::set_oversampling`](struct.TruePeak.html#method.set_oversampling).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TruePeak {
-    /// Interpolator/resampler.
-    interp: UpsamplingScanner,
+    /// The sample rate.
+    rate: u32,
+    /// The number of channels.
+    channels: u32,
+    /// The selected oversampling factor.
+    oversampling: TruePeakOversampling,
+    /// Interpolator/resampler. `None` if no oversampling is done for this samplerate.
+    interp: Option<UpsamplingScanner>,
+    /// Maximum true peak per channel.
+    true_peak: Box<[f64]>,
+    /// Detected true peak events.
+    events: TruePeakEvents,
 }
 
 impl TruePeak {
-    pub fn new(rate: u32, channels: u32) -> Option<Self> {
-        UpsamplingScanner::new(rate, channels).map(|interp| Self { interp })
+    /// Create a new instance for the given samplerate and number of channels.
+    pub fn new(rate: u32, channels: u32) -> Result<Self, Error> {
+        if channels == 0 || channels > MAX_CHANNELS {
+            return Err(Error::NoMem);
+        }
+
+        if !(16..=MAX_RATE).contains(&rate) {
+            return Err(Error::NoMem);
+        }
+
+        Ok(TruePeak {
+            rate,
+            channels,
+            oversampling: TruePeakOversampling::Auto,
+            interp: UpsamplingScanner::new(rate, channels, TruePeakOversampling::Auto),
+            true_peak: vec![0.0; channels as usize].into_boxed_slice(),
+            events: TruePeakEvents::new(channels, None),
+        })
+    }
+
+    /// Get the configured number of channels.
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    /// Get the selected oversampling factor.
+    pub fn oversampling(&self) -> TruePeakOversampling {
+        self.oversampling
+    }
+
+    /// Select the oversampling factor.
+    ///
+    /// This resets the interpolator state but keeps the peaks measured so far. Default is
+    /// `TruePeakOversampling::Auto`.
+    pub fn set_oversampling(&mut self, oversampling: TruePeakOversampling) {
+        self.oversampling = oversampling;
+        self.interp = UpsamplingScanner::new(self.rate, self.channels, oversampling);
+        self.events.close();
+    }
+
+    /// Set the threshold in dBTP above which true peak events are recorded.
+    ///
+    /// Every excursion of the oversampled signal above the threshold is recorded as one
+    /// [`TruePeakEvent`](struct.TruePeakEvent.html) per channel. At most 10000 events are stored,
+    /// further events are dropped and
+    /// [`TruePeak::events_overflowed`](struct.TruePeak.html#method.events_overflowed) returns
+    /// `true`. Events are only finished once the signal falls below the threshold again or when
+    /// calling [`TruePeak::flush`](struct.TruePeak.html#method.flush).
+    ///
+    /// Setting the threshold forgets all previous events. By default no events are recorded,
+    /// which corresponds to a threshold of `f64::INFINITY`.
+    pub fn set_event_threshold(&mut self, dbtp: f64) {
+        let threshold = if dbtp == f64::INFINITY {
+            None
+        } else {
+            Some(math::powf(10.0, dbtp / 20.0))
+        };
+
+        let frames = self.events.frames;
+        self.events = TruePeakEvents::new(self.channels, threshold);
+        self.events.frames = frames;
+    }
+
+    /// Get all finished true peak events, ordered by the time they finished.
+    pub fn events(&self) -> &[TruePeakEvent] {
+        &self.events.events
+    }
+
+    /// Whether true peak events were dropped because too many were detected.
+    pub fn events_overflowed(&self) -> bool {
+        self.events.overflowed
+    }
+
+    /// Get the effective oversampling factor.
+    ///
+    /// This is 1 if no oversampling is done, which is the case with
+    /// `TruePeakOversampling::Auto` at 192kHz and above.
+    pub fn oversampling_factor(&self) -> usize {
+        self.interp.as_ref().map_or(1, UpsamplingScanner::factor)
+    }
+
+    /// Number of bytes used by this analyzer, including the interpolator state and the events.
+    pub(crate) fn memory_usage(&self) -> usize {
+        use core::mem::size_of;
+
+        let interp = match self.interp {
+            Some(UpsamplingScanner::Generic2F(ref interp)) => core::mem::size_of_val(&**interp),
+            Some(UpsamplingScanner::Generic4F(ref interp)) => core::mem::size_of_val(&**interp),
+            Some(UpsamplingScanner::Generic8F(ref interp)) => core::mem::size_of_val(&**interp),
+            _ => 0,
+        };
+
+        size_of::<Self>()
+            + interp
+            + self.true_peak.len() * size_of::<f64>()
+            + self.events.open.len() * size_of::<Option<TruePeakEvent>>()
+            + self.events.events.capacity() * size_of::<TruePeakEvent>()
     }
 
+    /// Delay of the interpolated signal in input frames.
+    pub(crate) fn delay(&self) -> usize {
+        self.interp.as_ref().map_or(0, UpsamplingScanner::delay)
+    }
+
+    /// Reset to initial state.
+    ///
+    /// This forgets all measured peaks and events and the interpolator state.
     pub fn reset(&mut self) {
-        self.interp.reset();
+        if let Some(ref mut interp) = self.interp {
+            interp.reset();
+        }
+        self.true_peak.fill(0.0);
+        self.events.reset();
+    }
+
+    /// Reset the true peak maxima but keep the interpolator state and the true peak events.
+    pub fn reset_peaks(&mut self) {
+        self.true_peak.fill(0.0);
+    }
+
+    /// Reset the true peak maximum of one channel.
+    pub(crate) fn reset_channel_peak(&mut self, channel: usize) {
+        self.true_peak[channel] = 0.0;
+    }
+
+    /// Reset the true peak maxima and events but keep the interpolator state.
+    pub(crate) fn reset_measurement(&mut self) {
+        self.true_peak.fill(0.0);
+        self.events.reset();
+    }
+
+    /// Get maximum true peak of the selected channel from all frames that have been processed.
+    pub fn true_peak(&self, channel_number: u32) -> Result<f64, Error> {
+        if channel_number >= self.channels {
+            return Err(Error::InvalidChannelIndex);
+        }
+
+        Ok(self.true_peak[channel_number as usize])
+    }
+
+    /// Process interleaved frames.
+    pub fn process_i16(&mut self, frames: &[i16]) -> Result<(), Error> {
+        self.process(crate::Interleaved::new(frames, self.channels as usize)?)
+    }
+
+    /// Process interleaved frames.
+    pub fn process_i32(&mut self, frames: &[i32]) -> Result<(), Error> {
+        self.process(crate::Interleaved::new(frames, self.channels as usize)?)
+    }
+
+    /// Process interleaved frames.
+    pub fn process_f32(&mut self, frames: &[f32]) -> Result<(), Error> {
+        self.process(crate::Interleaved::new(frames, self.channels as usize)?)
+    }
+
+    /// Process interleaved frames.
+    pub fn process_f64(&mut self, frames: &[f64]) -> Result<(), Error> {
+        self.process(crate::Interleaved::new(frames, self.channels as usize)?)
+    }
+
+    /// Process planar frames.
+    pub fn process_planar_i16(&mut self, frames: &[&[i16]]) -> Result<(), Error> {
+        self.process(crate::Planar::new(frames)?)
+    }
+
+    /// Process planar frames.
+    pub fn process_planar_i32(&mut self, frames: &[&[i32]]) -> Result<(), Error> {
+        self.process(crate::Planar::new(frames)?)
+    }
+
+    /// Process planar frames.
+    pub fn process_planar_f32(&mut self, frames: &[&[f32]]) -> Result<(), Error> {
+        self.process(crate::Planar::new(frames)?)
+    }
+
+    /// Process planar frames.
+    pub fn process_planar_f64(&mut self, frames: &[&[f64]]) -> Result<(), Error> {
+        self.process(crate::Planar::new(frames)?)
+    }
+
+    fn process<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
+        &mut self,
+        src: S,
+    ) -> Result<(), Error> {
+        if src.channels() != self.channels as usize {
+            return Err(Error::InvalidChannelCount);
+        }
+
+        self.update_sample_peaks(&src);
+        Self::scan_peaks(&mut self.interp, &mut self.events, src, &mut self.true_peak);
+
+        Ok(())
+    }
+
+    /// Update `peaks` with the interpolated samples, detect events and call `output` with the
+    /// channel, the index and the value of each interpolated sample.
+    #[inline(always)]
+    fn scan<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
+        interp: &mut Option<UpsamplingScanner>,
+        events: &mut TruePeakEvents,
+        src: S,
+        peaks: &mut [f64],
+        mut output: impl FnMut(usize, usize, f32),
+    ) {
+        let frames = src.frames() as u64;
+
+        match (interp, events.threshold) {
+            (Some(interp), None) => interp.check_true_peak_with_output(src, peaks, output),
+            (Some(interp), Some(threshold)) => {
+                let factor = interp.factor();
+                let start = events.frames;
+                let delay = interp.delay() as u64;
+
+                interp.check_true_peak_with_output(src, peaks, |c, index, s| {
+                    output(c, index, s);
+                    let frame_index = (start + (index / factor) as u64).saturating_sub(delay);
+                    events.check(threshold, c, frame_index, s);
+                });
+            }
+            (None, Some(threshold)) => {
+                let start = events.frames;
+                for c in 0..src.channels() {
+                    let mut index = start;
+                    src.foreach_sample(c, |s| {
+                        events.check(threshold, c, index, s.to_f32());
+                        index += 1;
+                    });
+                }
+            }
+            (None, None) => (),
+        }
+
+        events.frames += frames;
+    }
+
+    /// Like `scan()` but only updates `peaks` and detects events.
+    fn scan_peaks<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
+        interp: &mut Option<UpsamplingScanner>,
+        events: &mut TruePeakEvents,
+        src: S,
+        peaks: &mut [f64],
+    ) {
+        match (interp, events.threshold) {
+            (Some(interp), None) => {
+                events.frames += src.frames() as u64;
+                interp.check_true_peak(src, peaks);
+            }
+            (interp, _) => Self::scan(interp, events, src, peaks, |_, _, _| ()),
+        }
+    }
+
+    /// The true peak is never below the sample peak, so update the peaks with the samples, too.
+    fn update_sample_peaks<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(&mut self, src: &S) {
+        for (c, true_peak) in self.true_peak.iter_mut().enumerate() {
+            let mut max = 0.0;
+            src.foreach_sample(c, |sample| {
+                let v = sample.as_f64_raw().abs();
+                if v > max {
+                    max = v;
+                }
+            });
+
+            max /= T::MAX_AMPLITUDE;
+            if max > *true_peak {
+                *true_peak = max;
+            }
+        }
     }
 
-    pub fn check_true_peak<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
+    /// Process interleaved frames and output the oversampled signal.
+    ///
+    /// Works like [`TruePeak::process_f32`](struct.TruePeak.html#method.process_f32) but
+    /// additionally stores the interleaved oversampled frames in `out`, replacing its previous
+    /// content. `out` will contain `frames.len() * oversampling_factor()` samples, i.e. input
+    /// frames × factor × channels. Its allocation is reused if it is large enough.
+    ///
+    /// The oversampled signal is delayed by the interpolation filter compared to the input.
+    /// If no oversampling is done then the input is copied as is.
+    pub fn process_with_output(&mut self, frames: &[f32], out: &mut Vec<f32>) -> Result<(), Error> {
+        let src = crate::Interleaved::new(frames, self.channels as usize)?;
+        let channels = self.channels as usize;
+        let factor = self.oversampling_factor();
+
+        out.clear();
+        out.resize(frames.len() * factor, 0.0);
+
+        self.update_sample_peaks(&src);
+        if self.interp.is_none() {
+            out.copy_from_slice(frames);
+        }
+
+        let out = &mut out[..];
+        Self::scan(
+            &mut self.interp,
+            &mut self.events,
+            src,
+            &mut self.true_peak,
+            |c, index, s| {
+                out[index * channels + c] = s;
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Finish the measurement at the end of the stream.
+    ///
+    /// Feeds silence through the interpolator so that inter-sample peaks right after the last
+    /// processed frames are considered, too, and finishes all ongoing true peak events.
+    pub fn flush(&mut self) {
+        let mut true_peak = core::mem::take(&mut self.true_peak);
+        self.flush_into(&mut true_peak);
+        self.true_peak = true_peak;
+    }
+
+    /// Check if the (deserialized) state matches the given configuration.
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_compatible(&self, rate: u32, channels: u32) -> bool {
+        let interp_compatible = match self.interp {
+            Some(ref interp) => interp.is_compatible(rate, channels, self.oversampling),
+            None => UpsamplingScanner::new(rate, channels, self.oversampling).is_none(),
+        };
+
+        self.rate == rate
+            && self.channels == channels
+            && self.true_peak.len() == channels as usize
+            && self.events.open.len() == channels as usize
+            && interp_compatible
+    }
+
+    /// Update the given peaks with the interpolated samples, without updating the stored peaks.
+    pub(crate) fn check_true_peak<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(
         &mut self,
         src: S,
         peaks: &mut [f64],
     ) {
-        self.interp.check_true_peak(src, peaks)
+        Self::scan_peaks(&mut self.interp, &mut self.events, src, peaks)
+    }
+
+    /// Drain the interpolator into the given peaks, without updating the stored peaks, and finish
+    /// all ongoing true peak events.
+    pub(crate) fn flush_into(&mut self, peaks: &mut [f64]) {
+        if self.interp.is_some() {
+            let zeros = vec![0.0f32; UpsamplingScanner::FLUSH_FRAMES * peaks.len()];
+            let src = crate::Interleaved::new(&zeros[..], peaks.len()).unwrap();
+            self.check_true_peak(src, peaks);
+        }
+        self.events.close();
     }
 
-    pub fn seed<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(&mut self, src: S) {
-        let mut true_peaks: SmallVec<[f64; 16]> = smallvec![0.0; src.channels()];
-        self.interp.check_true_peak(src, &mut true_peaks)
+    /// Take over the oversampling factor and the event threshold from `other`.
+    pub(crate) fn copy_settings(&mut self, other: &TruePeak) {
+        self.set_oversampling(other.oversampling);
+        self.events = TruePeakEvents::new(self.channels, other.events.threshold);
     }
-}
\ No newline at end of file
+
+    pub(crate) fn seed<'a, T: Sample + 'a, S: crate::Samples<'a, T>>(&mut self, src: S) {
+        if let Some(ref mut interp) = self.interp {
+            let mut true_peaks: SmallVec<[f64; 16]> = smallvec![0.0; src.channels()];
+            interp.check_true_peak(src, &mut true_peaks)
+        }
+    }
+}
@@ -18,19 +18,63 @@
 // LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
-use dasp_frame::Frame;
+use crate::math;
+
+use core::convert::TryInto;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, Ordering};
 
 /// Convert linear energy to logarithmic loudness.
 pub fn energy_to_loudness(energy: f64) -> f64 {
-    10.0 * f64::log10(energy) - 0.691
+    10.0 * math::log10(energy) - 0.691
+}
+
+static SIMD_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable the SIMD implementations for the whole process, e.g. to compare them with
+/// the scalar implementations in benchmarks and tests. Enabled by default.
+#[doc(hidden)]
+pub fn set_simd_enabled(enabled: bool) {
+    SIMD_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the SIMD implementations should be used.
+#[inline]
+pub(crate) fn simd_enabled() -> bool {
+    SIMD_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Find the first NaN or infinite sample, returning its frame index and channel.
+pub(crate) fn find_invalid_sample<'a, T: Sample + 'a, S: Samples<'a, T>>(
+    src: &S,
+) -> Option<(usize, usize)> {
+    let mut first: Option<(usize, usize)> = None;
+    for c in 0..src.channels() {
+        let mut frame_index = 0;
+        let mut invalid = None;
+        src.foreach_sample(c, |sample| {
+            if invalid.is_none() && !sample.is_finite() {
+                invalid = Some(frame_index);
+            }
+            frame_index += 1;
+        });
+
+        if let Some(frame_index) = invalid {
+            if first.is_none_or(|(first_index, _)| frame_index < first_index) {
+                first = Some((frame_index, c));
+            }
+        }
+    }
+
+    first
 }
 
 /// Trait for abstracting over interleaved and planar samples.
-pub trait Samples<'a, S: Sample + 'a>: Sized {
+pub trait Samples<'a, S: Sample + 'a>: Sized + Sync {
     /// Call the given closure for each sample of the given channel.
     // FIXME: Workaround for TrustedLen / TrustedRandomAccess being unstable
     // and because of that we wouldn't get nice optimizations
-    fn foreach_sample(&self, channel: usize, func: impl FnMut(&'a S));
+    fn foreach_sample(&self, channel: usize, func: impl FnMut(S));
 
     /// Call the given closure for each sample of the given channel.
     // FIXME: Workaround for TrustedLen / TrustedRandomAccess being unstable
@@ -39,7 +83,31 @@ pub trait Samples<'a, S: Sample + 'a>: Sized {
         &self,
         channel: usize,
         iter: impl Iterator<Item = U>,
-        func: impl FnMut(&'a S, U),
+        func: impl FnMut(S, U),
+    );
+
+    /// Call the given closure for each frame with the samples of the four channels starting at
+    /// the given channel.
+    // Only used by the SIMD filter implementation
+    #[cfg_attr(
+        not(any(target_arch = "x86", target_arch = "x86_64")),
+        allow(dead_code)
+    )]
+    fn foreach_sample4_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        func: impl FnMut([S; 4], U),
+    );
+
+    /// Call the given closure for each frame with the samples of the two channels starting at
+    /// the given channel.
+    // Only used by the SIMD filter implementation
+    fn foreach_sample2_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        func: impl FnMut([S; 2], U),
     );
 
     fn foreach_frame<F: Frame<Sample = S>>(&self, func: impl FnMut(F));
@@ -69,7 +137,7 @@ impl<'a, S> Interleaved<'a, S> {
             return Err(crate::Error::NoMem);
         }
 
-        if data.len() % channels != 0 {
+        if !data.len().is_multiple_of(channels) {
             return Err(crate::Error::NoMem);
         }
 
@@ -79,11 +147,11 @@ impl<'a, S> Interleaved<'a, S> {
 
 impl<'a, S: Sample> Samples<'a, S> for Interleaved<'a, S> {
     #[inline]
-    fn foreach_sample(&self, channel: usize, mut func: impl FnMut(&'a S)) {
+    fn foreach_sample(&self, channel: usize, mut func: impl FnMut(S)) {
         assert!(channel < self.channels);
 
         for v in self.data.chunks_exact(self.channels) {
-            func(&v[channel])
+            func(v[channel])
         }
     }
 
@@ -92,12 +160,43 @@ impl<'a, S: Sample> Samples<'a, S> for Interleaved<'a, S> {
         &self,
         channel: usize,
         iter: impl Iterator<Item = U>,
-        mut func: impl FnMut(&'a S, U),
+        mut func: impl FnMut(S, U),
     ) {
         assert!(channel < self.channels);
 
         for (v, u) in Iterator::zip(self.data.chunks_exact(self.channels), iter) {
-            func(&v[channel], u)
+            func(v[channel], u)
+        }
+    }
+
+    // Inlined into the AVX2 filter to be compiled with its target features
+    #[inline(always)]
+    fn foreach_sample4_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([S; 4], U),
+    ) {
+        assert!(channel + 4 <= self.channels);
+
+        for (v, u) in Iterator::zip(self.data.chunks_exact(self.channels), iter) {
+            let v = &v[channel..channel + 4];
+            func([v[0], v[1], v[2], v[3]], u)
+        }
+    }
+
+    #[inline]
+    fn foreach_sample2_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([S; 2], U),
+    ) {
+        assert!(channel + 2 <= self.channels);
+
+        for (v, u) in Iterator::zip(self.data.chunks_exact(self.channels), iter) {
+            let v = &v[channel..channel + 2];
+            func([v[0], v[1]], u)
         }
     }
 
@@ -105,7 +204,7 @@ impl<'a, S: Sample> Samples<'a, S> for Interleaved<'a, S> {
     fn foreach_frame<F: Frame<Sample = S>>(&self, mut func: impl FnMut(F)) {
         assert_eq!(F::CHANNELS, self.channels);
         for f in self.data.chunks_exact(self.channels) {
-            func(F::from_samples(&mut f.iter().copied()).unwrap());
+            func(F::from_fn(|c| f[c]));
         }
     }
 
@@ -138,6 +237,8 @@ impl<'a, S: Sample> Samples<'a, S> for Interleaved<'a, S> {
 }
 
 /// Struct representing planar samples.
+///
+/// Samples are read directly from the per-channel slices, no interleaved copy is made.
 pub struct Planar<'a, S> {
     data: &'a [&'a [S]],
     start: usize,
@@ -148,11 +249,11 @@ impl<'a, S> Planar<'a, S> {
     /// Create a new wrapper around the planar channels and do a sanity check.
     pub fn new(data: &'a [&'a [S]]) -> Result<Self, crate::Error> {
         if data.is_empty() {
-            return Err(crate::Error::NoMem);
+            return Err(crate::Error::InvalidChannelCount);
         }
 
         if data.iter().any(|d| data[0].len() != d.len()) {
-            return Err(crate::Error::NoMem);
+            return Err(crate::Error::InvalidFrameCount);
         }
 
         Ok(Planar {
@@ -165,11 +266,11 @@ impl<'a, S> Planar<'a, S> {
 
 impl<'a, S: Sample> Samples<'a, S> for Planar<'a, S> {
     #[inline]
-    fn foreach_sample(&self, channel: usize, mut func: impl FnMut(&'a S)) {
+    fn foreach_sample(&self, channel: usize, mut func: impl FnMut(S)) {
         assert!(channel < self.data.len());
 
         for v in &self.data[channel][self.start..self.end] {
-            func(v)
+            func(*v)
         }
     }
 
@@ -178,12 +279,50 @@ impl<'a, S: Sample> Samples<'a, S> for Planar<'a, S> {
         &self,
         channel: usize,
         iter: impl Iterator<Item = U>,
-        mut func: impl FnMut(&'a S, U),
+        mut func: impl FnMut(S, U),
     ) {
         assert!(channel < self.data.len());
 
         for (v, u) in Iterator::zip(self.data[channel][self.start..self.end].iter(), iter) {
-            func(v, u)
+            func(*v, u)
+        }
+    }
+
+    // Inlined into the AVX2 filter to be compiled with its target features
+    #[inline(always)]
+    fn foreach_sample4_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([S; 4], U),
+    ) {
+        assert!(channel + 4 <= self.data.len());
+
+        let d = &self.data[channel..channel + 4];
+        let (d0, d1, d2, d3) = (
+            &d[0][self.start..self.end],
+            &d[1][self.start..self.end],
+            &d[2][self.start..self.end],
+            &d[3][self.start..self.end],
+        );
+        for ((((v0, v1), v2), v3), u) in d0.iter().zip(d1).zip(d2).zip(d3).zip(iter) {
+            func([*v0, *v1, *v2, *v3], u)
+        }
+    }
+
+    #[inline]
+    fn foreach_sample2_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([S; 2], U),
+    ) {
+        assert!(channel + 2 <= self.data.len());
+
+        let d = &self.data[channel..channel + 2];
+        let (d0, d1) = (&d[0][self.start..self.end], &d[1][self.start..self.end]);
+        for ((v0, v1), u) in d0.iter().zip(d1).zip(iter) {
+            func([*v0, *v1], u)
         }
     }
 
@@ -225,44 +364,908 @@ impl<'a, S: Sample> Samples<'a, S> for Planar<'a, S> {
     }
 }
 
-pub trait Sample:
-    dasp_sample::Sample + dasp_sample::Duplex<f32> + dasp_sample::Duplex<f64>
-{
-    const MAX_AMPLITUDE: f64;
+/// Storage format of 24 bit samples.
+pub trait I24Storage: Copy + Sync {
+    /// Number of storage elements per sample.
+    const WIDTH: usize;
 
-    fn as_f64_raw(self) -> f64;
+    /// Decode the sample from the first `WIDTH` elements.
+    fn decode(s: &[Self]) -> I24;
 }
 
-impl Sample for f32 {
-    const MAX_AMPLITUDE: f64 = 1.0;
+/// Packed 24 bit little-endian samples, 3 bytes per sample.
+impl I24Storage for u8 {
+    const WIDTH: usize = 3;
 
     #[inline(always)]
-    fn as_f64_raw(self) -> f64 {
-        self as f64
+    fn decode(s: &[Self]) -> I24 {
+        I24::new_unchecked(i32::from_le_bytes([0, s[0], s[1], s[2]]) >> 8)
     }
 }
-impl Sample for f64 {
-    const MAX_AMPLITUDE: f64 = 1.0;
+
+/// 24 bit samples sign-extended to 32 bit.
+impl I24Storage for i32 {
+    const WIDTH: usize = 1;
 
     #[inline(always)]
-    fn as_f64_raw(self) -> f64 {
-        self
+    fn decode(s: &[Self]) -> I24 {
+        I24::new_unchecked(s[0])
     }
 }
-impl Sample for i16 {
-    const MAX_AMPLITUDE: f64 = -(Self::MIN as f64);
 
+/// Struct representing interleaved 24 bit samples.
+pub struct InterleavedI24<'a, E> {
+    /// Interleaved sample data, `E::WIDTH` elements per sample.
+    data: &'a [E],
+    /// Number of channels.
+    channels: usize,
+}
+
+impl<'a, E: I24Storage> InterleavedI24<'a, E> {
+    /// Create a new wrapper around the interleaved channels and do a sanity check.
+    pub fn new(data: &'a [E], channels: usize) -> Result<Self, crate::Error> {
+        if channels == 0 {
+            return Err(crate::Error::NoMem);
+        }
+
+        if !data.len().is_multiple_of(E::WIDTH * channels) {
+            return Err(crate::Error::NoMem);
+        }
+
+        Ok(InterleavedI24 { data, channels })
+    }
+}
+
+impl<'a, E: I24Storage> Samples<'a, I24> for InterleavedI24<'a, E> {
+    #[inline]
+    fn foreach_sample(&self, channel: usize, mut func: impl FnMut(I24)) {
+        assert!(channel < self.channels);
+
+        for v in self.data.chunks_exact(E::WIDTH * self.channels) {
+            func(E::decode(&v[E::WIDTH * channel..]))
+        }
+    }
+
+    #[inline]
+    fn foreach_sample_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut(I24, U),
+    ) {
+        assert!(channel < self.channels);
+
+        for (v, u) in Iterator::zip(self.data.chunks_exact(E::WIDTH * self.channels), iter) {
+            func(E::decode(&v[E::WIDTH * channel..]), u)
+        }
+    }
+
+    // Inlined into the AVX2 filter to be compiled with its target features
     #[inline(always)]
-    fn as_f64_raw(self) -> f64 {
-        self as f64
+    fn foreach_sample4_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([I24; 4], U),
+    ) {
+        assert!(channel + 4 <= self.channels);
+
+        for (v, u) in Iterator::zip(self.data.chunks_exact(E::WIDTH * self.channels), iter) {
+            let v = &v[E::WIDTH * channel..E::WIDTH * (channel + 4)];
+            func(
+                [
+                    E::decode(v),
+                    E::decode(&v[E::WIDTH..]),
+                    E::decode(&v[E::WIDTH * 2..]),
+                    E::decode(&v[E::WIDTH * 3..]),
+                ],
+                u,
+            )
+        }
+    }
+
+    #[inline]
+    fn foreach_sample2_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([I24; 2], U),
+    ) {
+        assert!(channel + 2 <= self.channels);
+
+        for (v, u) in Iterator::zip(self.data.chunks_exact(E::WIDTH * self.channels), iter) {
+            let v = &v[E::WIDTH * channel..E::WIDTH * (channel + 2)];
+            func([E::decode(v), E::decode(&v[E::WIDTH..])], u)
+        }
+    }
+
+    #[inline]
+    fn foreach_frame<F: Frame<Sample = I24>>(&self, mut func: impl FnMut(F)) {
+        assert_eq!(F::CHANNELS, self.channels);
+        for f in self.data.chunks_exact(E::WIDTH * self.channels) {
+            func(F::from_fn(|c| E::decode(&f[E::WIDTH * c..])));
+        }
+    }
+
+    #[inline]
+    fn frames(&self) -> usize {
+        self.data.len() / (E::WIDTH * self.channels)
+    }
+
+    #[inline]
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    #[inline]
+    fn split_at(self, sample: usize) -> (Self, Self) {
+        assert!(E::WIDTH * sample * self.channels <= self.data.len());
+
+        let (fst, snd) = self.data.split_at(E::WIDTH * sample * self.channels);
+        (
+            InterleavedI24 {
+                data: fst,
+                channels: self.channels,
+            },
+            InterleavedI24 {
+                data: snd,
+                channels: self.channels,
+            },
+        )
     }
 }
-impl Sample for i32 {
-    const MAX_AMPLITUDE: f64 = -(Self::MIN as f64);
+
+/// Decoding of one sample from its byte representation.
+pub trait ByteDecoder: Sync {
+    /// Decoded sample type.
+    type Sample: Sample;
+
+    /// Number of bytes per sample.
+    const WIDTH: usize;
+
+    /// Decode the sample from the first `WIDTH` bytes.
+    fn decode(s: &[u8]) -> Self::Sample;
+}
+
+macro_rules! byte_decoder {
+    ($name:ident, $sample:ty, $from_bytes:ident) => {
+        pub struct $name;
+
+        impl ByteDecoder for $name {
+            type Sample = $sample;
+            const WIDTH: usize = core::mem::size_of::<$sample>();
+
+            #[inline(always)]
+            fn decode(s: &[u8]) -> $sample {
+                <$sample>::$from_bytes(s[..Self::WIDTH].try_into().unwrap())
+            }
+        }
+    };
+}
+
+byte_decoder!(S16Le, i16, from_le_bytes);
+byte_decoder!(S16Be, i16, from_be_bytes);
+byte_decoder!(S32Le, i32, from_le_bytes);
+byte_decoder!(S32Be, i32, from_be_bytes);
+byte_decoder!(F32Le, f32, from_le_bytes);
+byte_decoder!(F32Be, f32, from_be_bytes);
+byte_decoder!(F64Le, f64, from_le_bytes);
+byte_decoder!(F64Be, f64, from_be_bytes);
+
+/// Packed 24 bit little-endian samples.
+pub struct S24Le;
+
+impl ByteDecoder for S24Le {
+    type Sample = I24;
+    const WIDTH: usize = 3;
 
     #[inline(always)]
-    fn as_f64_raw(self) -> f64 {
-        self as f64
+    fn decode(s: &[u8]) -> I24 {
+        u8::decode(s)
+    }
+}
+
+/// Packed 24 bit big-endian samples.
+pub struct S24Be;
+
+impl ByteDecoder for S24Be {
+    type Sample = I24;
+    const WIDTH: usize = 3;
+
+    #[inline(always)]
+    fn decode(s: &[u8]) -> I24 {
+        I24::new_unchecked(i32::from_be_bytes([s[0], s[1], s[2], 0]) >> 8)
+    }
+}
+
+/// Struct representing interleaved samples stored as bytes.
+pub struct InterleavedBytes<'a, D> {
+    /// Interleaved sample data, `D::WIDTH` bytes per sample.
+    data: &'a [u8],
+    /// Number of channels.
+    channels: usize,
+    decoder: PhantomData<D>,
+}
+
+impl<'a, D: ByteDecoder> InterleavedBytes<'a, D> {
+    /// Create a new wrapper around the interleaved channels and do a sanity check.
+    pub fn new(data: &'a [u8], channels: usize) -> Result<Self, crate::Error> {
+        if channels == 0 {
+            return Err(crate::Error::NoMem);
+        }
+
+        if !data.len().is_multiple_of(D::WIDTH * channels) {
+            return Err(crate::Error::NoMem);
+        }
+
+        Ok(InterleavedBytes {
+            data,
+            channels,
+            decoder: PhantomData,
+        })
+    }
+}
+
+impl<'a, D: ByteDecoder> Samples<'a, D::Sample> for InterleavedBytes<'a, D>
+where
+    D::Sample: 'a,
+{
+    #[inline]
+    fn foreach_sample(&self, channel: usize, mut func: impl FnMut(D::Sample)) {
+        assert!(channel < self.channels);
+
+        for v in self.data.chunks_exact(D::WIDTH * self.channels) {
+            func(D::decode(&v[D::WIDTH * channel..]))
+        }
+    }
+
+    #[inline]
+    fn foreach_sample_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut(D::Sample, U),
+    ) {
+        assert!(channel < self.channels);
+
+        for (v, u) in Iterator::zip(self.data.chunks_exact(D::WIDTH * self.channels), iter) {
+            func(D::decode(&v[D::WIDTH * channel..]), u)
+        }
+    }
+
+    // Inlined into the AVX2 filter to be compiled with its target features
+    #[inline(always)]
+    fn foreach_sample4_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([D::Sample; 4], U),
+    ) {
+        assert!(channel + 4 <= self.channels);
+
+        for (v, u) in Iterator::zip(self.data.chunks_exact(D::WIDTH * self.channels), iter) {
+            let v = &v[D::WIDTH * channel..D::WIDTH * (channel + 4)];
+            func(
+                [
+                    D::decode(v),
+                    D::decode(&v[D::WIDTH..]),
+                    D::decode(&v[D::WIDTH * 2..]),
+                    D::decode(&v[D::WIDTH * 3..]),
+                ],
+                u,
+            )
+        }
+    }
+
+    #[inline]
+    fn foreach_sample2_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([D::Sample; 2], U),
+    ) {
+        assert!(channel + 2 <= self.channels);
+
+        for (v, u) in Iterator::zip(self.data.chunks_exact(D::WIDTH * self.channels), iter) {
+            let v = &v[D::WIDTH * channel..D::WIDTH * (channel + 2)];
+            func([D::decode(v), D::decode(&v[D::WIDTH..])], u)
+        }
+    }
+
+    #[inline]
+    fn foreach_frame<F: Frame<Sample = D::Sample>>(&self, mut func: impl FnMut(F)) {
+        assert_eq!(F::CHANNELS, self.channels);
+        for f in self.data.chunks_exact(D::WIDTH * self.channels) {
+            func(F::from_fn(|c| D::decode(&f[D::WIDTH * c..])));
+        }
+    }
+
+    #[inline]
+    fn frames(&self) -> usize {
+        self.data.len() / (D::WIDTH * self.channels)
+    }
+
+    #[inline]
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    #[inline]
+    fn split_at(self, sample: usize) -> (Self, Self) {
+        assert!(D::WIDTH * sample * self.channels <= self.data.len());
+
+        let (fst, snd) = self.data.split_at(D::WIDTH * sample * self.channels);
+        (
+            InterleavedBytes {
+                data: fst,
+                channels: self.channels,
+                decoder: PhantomData,
+            },
+            InterleavedBytes {
+                data: snd,
+                channels: self.channels,
+                decoder: PhantomData,
+            },
+        )
+    }
+}
+
+/// Struct representing `dasp_frame::Frame`s of any sample type, converted to `f64`.
+#[cfg(feature = "dasp")]
+pub struct DaspFrames<'a, F> {
+    data: &'a [F],
+}
+
+#[cfg(feature = "dasp")]
+impl<'a, F: dasp_frame::Frame> DaspFrames<'a, F> {
+    /// Create a new wrapper around the frames.
+    pub fn new(data: &'a [F]) -> Self {
+        DaspFrames { data }
+    }
+
+    #[inline(always)]
+    fn sample(frame: &F, channel: usize) -> f64
+    where
+        F::Sample: dasp_sample::ToSample<f64>,
+    {
+        dasp_sample::Sample::to_sample(*frame.channel(channel).unwrap())
+    }
+}
+
+#[cfg(feature = "dasp")]
+impl<'a, F> Samples<'a, f64> for DaspFrames<'a, F>
+where
+    F: dasp_frame::Frame + Sync,
+    F::Sample: dasp_sample::ToSample<f64>,
+{
+    #[inline]
+    fn foreach_sample(&self, channel: usize, mut func: impl FnMut(f64)) {
+        assert!(channel < F::CHANNELS);
+
+        for f in self.data {
+            func(Self::sample(f, channel))
+        }
+    }
+
+    #[inline]
+    fn foreach_sample_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut(f64, U),
+    ) {
+        assert!(channel < F::CHANNELS);
+
+        for (f, u) in Iterator::zip(self.data.iter(), iter) {
+            func(Self::sample(f, channel), u)
+        }
+    }
+
+    // Inlined into the AVX2 filter to be compiled with its target features
+    #[inline(always)]
+    fn foreach_sample4_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([f64; 4], U),
+    ) {
+        assert!(channel + 4 <= F::CHANNELS);
+
+        for (f, u) in Iterator::zip(self.data.iter(), iter) {
+            func(
+                [
+                    Self::sample(f, channel),
+                    Self::sample(f, channel + 1),
+                    Self::sample(f, channel + 2),
+                    Self::sample(f, channel + 3),
+                ],
+                u,
+            )
+        }
+    }
+
+    #[inline]
+    fn foreach_sample2_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([f64; 2], U),
+    ) {
+        assert!(channel + 2 <= F::CHANNELS);
+
+        for (f, u) in Iterator::zip(self.data.iter(), iter) {
+            func([Self::sample(f, channel), Self::sample(f, channel + 1)], u)
+        }
+    }
+
+    #[inline]
+    fn foreach_frame<F2: Frame<Sample = f64>>(&self, mut func: impl FnMut(F2)) {
+        assert_eq!(F2::CHANNELS, F::CHANNELS);
+        for f in self.data {
+            func(F2::from_fn(|c| Self::sample(f, c)));
+        }
+    }
+
+    #[inline]
+    fn frames(&self) -> usize {
+        self.data.len()
+    }
+
+    #[inline]
+    fn channels(&self) -> usize {
+        F::CHANNELS
+    }
+
+    #[inline]
+    fn split_at(self, sample: usize) -> (Self, Self) {
+        let (fst, snd) = self.data.split_at(sample);
+        (DaspFrames { data: fst }, DaspFrames { data: snd })
+    }
+}
+
+/// Struct representing planar samples that are converted to another sample type while reading.
+#[cfg(feature = "symphonia")]
+pub struct PlanarConverted<'a, T, S> {
+    data: &'a [&'a [T]],
+    start: usize,
+    end: usize,
+    convert: fn(T) -> S,
+}
+
+#[cfg(feature = "symphonia")]
+impl<'a, T, S> PlanarConverted<'a, T, S> {
+    /// Create a new wrapper around the planar channels and do a sanity check.
+    pub fn new(data: &'a [&'a [T]], convert: fn(T) -> S) -> Result<Self, crate::Error> {
+        if data.is_empty() {
+            return Err(crate::Error::InvalidChannelCount);
+        }
+
+        if data.iter().any(|d| data[0].len() != d.len()) {
+            return Err(crate::Error::InvalidFrameCount);
+        }
+
+        Ok(PlanarConverted {
+            data,
+            start: 0,
+            end: data[0].len(),
+            convert,
+        })
+    }
+}
+
+#[cfg(feature = "symphonia")]
+impl<'a, T: Copy + Sync, S: Sample + 'a> Samples<'a, S> for PlanarConverted<'a, T, S> {
+    #[inline]
+    fn foreach_sample(&self, channel: usize, mut func: impl FnMut(S)) {
+        assert!(channel < self.data.len());
+
+        for v in &self.data[channel][self.start..self.end] {
+            func((self.convert)(*v))
+        }
+    }
+
+    #[inline]
+    fn foreach_sample_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut(S, U),
+    ) {
+        assert!(channel < self.data.len());
+
+        for (v, u) in Iterator::zip(self.data[channel][self.start..self.end].iter(), iter) {
+            func((self.convert)(*v), u)
+        }
+    }
+
+    // Inlined into the AVX2 filter to be compiled with its target features
+    #[inline(always)]
+    fn foreach_sample4_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([S; 4], U),
+    ) {
+        assert!(channel + 4 <= self.data.len());
+
+        let d = &self.data[channel..channel + 4];
+        let (d0, d1, d2, d3) = (
+            &d[0][self.start..self.end],
+            &d[1][self.start..self.end],
+            &d[2][self.start..self.end],
+            &d[3][self.start..self.end],
+        );
+        let c = self.convert;
+        for ((((v0, v1), v2), v3), u) in d0.iter().zip(d1).zip(d2).zip(d3).zip(iter) {
+            func([c(*v0), c(*v1), c(*v2), c(*v3)], u)
+        }
+    }
+
+    #[inline]
+    fn foreach_sample2_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([S; 2], U),
+    ) {
+        assert!(channel + 2 <= self.data.len());
+
+        let d = &self.data[channel..channel + 2];
+        let (d0, d1) = (&d[0][self.start..self.end], &d[1][self.start..self.end]);
+        let c = self.convert;
+        for ((v0, v1), u) in d0.iter().zip(d1).zip(iter) {
+            func([c(*v0), c(*v1)], u)
+        }
+    }
+
+    #[inline]
+    fn foreach_frame<F: Frame<Sample = S>>(&self, mut func: impl FnMut(F)) {
+        let channels = self.data.len();
+        assert_eq!(F::CHANNELS, channels);
+        for f in self.start..self.end {
+            func(F::from_fn(|c| (self.convert)(self.data[c][f])));
+        }
+    }
+
+    #[inline]
+    fn frames(&self) -> usize {
+        self.end - self.start
+    }
+
+    #[inline]
+    fn channels(&self) -> usize {
+        self.data.len()
+    }
+
+    #[inline]
+    fn split_at(self, sample: usize) -> (Self, Self) {
+        assert!(self.start + sample <= self.end);
+
+        (
+            PlanarConverted {
+                data: self.data,
+                start: self.start,
+                end: self.start + sample,
+                convert: self.convert,
+            },
+            PlanarConverted {
+                data: self.data,
+                start: self.start + sample,
+                end: self.end,
+                convert: self.convert,
+            },
+        )
+    }
+}
+
+/// Struct representing planar 24 bit samples.
+pub struct PlanarI24<'a, E> {
+    /// One slice per channel, `E::WIDTH` elements per sample.
+    data: &'a [&'a [E]],
+    start: usize,
+    end: usize,
+}
+
+impl<'a, E: I24Storage> PlanarI24<'a, E> {
+    /// Create a new wrapper around the planar channels and do a sanity check.
+    pub fn new(data: &'a [&'a [E]]) -> Result<Self, crate::Error> {
+        if data.is_empty() {
+            return Err(crate::Error::InvalidChannelCount);
+        }
+
+        if data
+            .iter()
+            .any(|d| data[0].len() != d.len() || !d.len().is_multiple_of(E::WIDTH))
+        {
+            return Err(crate::Error::InvalidFrameCount);
+        }
+
+        Ok(PlanarI24 {
+            data,
+            start: 0,
+            end: data[0].len() / E::WIDTH,
+        })
+    }
+}
+
+impl<'a, E: I24Storage> Samples<'a, I24> for PlanarI24<'a, E> {
+    #[inline]
+    fn foreach_sample(&self, channel: usize, mut func: impl FnMut(I24)) {
+        assert!(channel < self.data.len());
+
+        for v in
+            self.data[channel][E::WIDTH * self.start..E::WIDTH * self.end].chunks_exact(E::WIDTH)
+        {
+            func(E::decode(v))
+        }
+    }
+
+    #[inline]
+    fn foreach_sample_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut(I24, U),
+    ) {
+        assert!(channel < self.data.len());
+
+        for (v, u) in Iterator::zip(
+            self.data[channel][E::WIDTH * self.start..E::WIDTH * self.end].chunks_exact(E::WIDTH),
+            iter,
+        ) {
+            func(E::decode(v), u)
+        }
+    }
+
+    // Inlined into the AVX2 filter to be compiled with its target features
+    #[inline(always)]
+    fn foreach_sample4_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([I24; 4], U),
+    ) {
+        assert!(channel + 4 <= self.data.len());
+
+        let range = E::WIDTH * self.start..E::WIDTH * self.end;
+        let d = &self.data[channel..channel + 4];
+        let (d0, d1, d2, d3) = (
+            d[0][range.clone()].chunks_exact(E::WIDTH),
+            d[1][range.clone()].chunks_exact(E::WIDTH),
+            d[2][range.clone()].chunks_exact(E::WIDTH),
+            d[3][range].chunks_exact(E::WIDTH),
+        );
+        for ((((v0, v1), v2), v3), u) in d0.zip(d1).zip(d2).zip(d3).zip(iter) {
+            func(
+                [E::decode(v0), E::decode(v1), E::decode(v2), E::decode(v3)],
+                u,
+            )
+        }
+    }
+
+    #[inline]
+    fn foreach_sample2_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut([I24; 2], U),
+    ) {
+        assert!(channel + 2 <= self.data.len());
+
+        let range = E::WIDTH * self.start..E::WIDTH * self.end;
+        let d = &self.data[channel..channel + 2];
+        let (d0, d1) = (
+            d[0][range.clone()].chunks_exact(E::WIDTH),
+            d[1][range].chunks_exact(E::WIDTH),
+        );
+        for ((v0, v1), u) in d0.zip(d1).zip(iter) {
+            func([E::decode(v0), E::decode(v1)], u)
+        }
+    }
+
+    #[inline]
+    fn foreach_frame<F: Frame<Sample = I24>>(&self, mut func: impl FnMut(F)) {
+        let channels = self.data.len();
+        assert_eq!(F::CHANNELS, channels);
+        for f in self.start..self.end {
+            func(F::from_fn(|c| E::decode(&self.data[c][E::WIDTH * f..])));
+        }
+    }
+
+    #[inline]
+    fn frames(&self) -> usize {
+        self.end - self.start
+    }
+
+    #[inline]
+    fn channels(&self) -> usize {
+        self.data.len()
+    }
+
+    #[inline]
+    fn split_at(self, sample: usize) -> (Self, Self) {
+        assert!(self.start + sample <= self.end);
+
+        (
+            PlanarI24 {
+                data: self.data,
+                start: self.start,
+                end: self.start + sample,
+            },
+            PlanarI24 {
+                data: self.data,
+                start: self.start + sample,
+                end: self.end,
+            },
+        )
+    }
+}
+
+/// A signed 24 bit sample stored in the low bits of an `i32`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct I24(i32);
+
+impl I24 {
+    /// Create a sample without checking that `s` is within the 24 bit range.
+    #[inline(always)]
+    pub const fn new_unchecked(s: i32) -> Self {
+        I24(s)
+    }
+
+    #[inline(always)]
+    pub const fn inner(self) -> i32 {
+        self.0
+    }
+}
+
+pub trait Sample: Copy + PartialOrd + Sync {
+    /// The sample value of silence.
+    const EQUILIBRIUM: Self;
+    const MAX_AMPLITUDE: f64;
+
+    fn as_f64_raw(self) -> f64;
+
+    /// Convert to `f32` scaled to ±1.0.
+    fn to_f32(self) -> f32;
+
+    /// Convert to `f64` scaled to ±1.0.
+    #[inline(always)]
+    fn to_f64(self) -> f64 {
+        self.as_f64_raw() / Self::MAX_AMPLITUDE
+    }
+
+    /// Whether the sample is neither NaN nor infinite.
+    #[inline(always)]
+    fn is_finite(self) -> bool {
+        true
+    }
+}
+
+impl Sample for f32 {
+    const EQUILIBRIUM: Self = 0.0;
+    const MAX_AMPLITUDE: f64 = 1.0;
+
+    #[inline(always)]
+    fn as_f64_raw(self) -> f64 {
+        self as f64
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    #[inline(always)]
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+}
+impl Sample for f64 {
+    const EQUILIBRIUM: Self = 0.0;
+    const MAX_AMPLITUDE: f64 = 1.0;
+
+    #[inline(always)]
+    fn as_f64_raw(self) -> f64 {
+        self
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    #[inline(always)]
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    #[inline(always)]
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+}
+impl Sample for i16 {
+    const EQUILIBRIUM: Self = 0;
+    const MAX_AMPLITUDE: f64 = -(Self::MIN as f64);
+
+    #[inline(always)]
+    fn as_f64_raw(self) -> f64 {
+        self as f64
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32 / 32_768.0
+    }
+}
+impl Sample for I24 {
+    const EQUILIBRIUM: Self = I24(0);
+    const MAX_AMPLITUDE: f64 = 8_388_608.0;
+
+    #[inline(always)]
+    fn as_f64_raw(self) -> f64 {
+        self.inner() as f64
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self.inner() as f32 / 8_388_608.0
+    }
+}
+impl Sample for i32 {
+    const EQUILIBRIUM: Self = 0;
+    const MAX_AMPLITUDE: f64 = -(Self::MIN as f64);
+
+    #[inline(always)]
+    fn as_f64_raw(self) -> f64 {
+        self as f64
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32 / 2_147_483_648.0
+    }
+}
+
+/// A frame of one sample per channel.
+pub trait Frame: Copy {
+    type Sample: Copy;
+    const CHANNELS: usize;
+
+    /// Create a frame from the sample of each channel.
+    fn from_fn(f: impl FnMut(usize) -> Self::Sample) -> Self;
+
+    /// Get the sample of `channel` without bounds checks.
+    ///
+    /// # Safety
+    ///
+    /// `channel` must be less than `CHANNELS`.
+    // Only used by the SIMD interpolator implementations
+    #[cfg_attr(
+        not(any(
+            all(
+                any(target_arch = "x86", target_arch = "x86_64"),
+                target_feature = "sse"
+            ),
+            all(target_arch = "aarch64", target_feature = "neon")
+        )),
+        allow(dead_code)
+    )]
+    unsafe fn channel_unchecked(&self, channel: usize) -> &Self::Sample;
+}
+
+impl<S: Copy, const N: usize> Frame for [S; N] {
+    type Sample = S;
+    const CHANNELS: usize = N;
+
+    #[inline(always)]
+    fn from_fn(f: impl FnMut(usize) -> S) -> Self {
+        core::array::from_fn(f)
+    }
+
+    #[inline(always)]
+    unsafe fn channel_unchecked(&self, channel: usize) -> &S {
+        self.get_unchecked(channel)
     }
 }
 
@@ -274,7 +1277,7 @@ pub trait FrameAccumulator: Frame {
 
 impl<F: Frame, S> FrameAccumulator for F
 where
-    S: SampleAccumulator + std::fmt::Debug,
+    S: SampleAccumulator + core::fmt::Debug,
     F: IndexMut<Target = S>,
 {
     #[inline(always)]
@@ -336,7 +1339,7 @@ impl SampleAccumulator for f32 {
     fn scale_add(&mut self, other: Self, coeff: f32) {
         #[cfg(feature = "precision-true-peak")]
         {
-            *self = other.mul_add(coeff, *self);
+            *self = math::mul_addf(other, coeff, *self);
         }
         #[cfg(not(feature = "precision-true-peak"))]
         {
@@ -0,0 +1,96 @@
+//! JavaScript bindings via `wasm-bindgen`.
+//!
+//! Build with e.g. `cargo rustc --release --lib --crate-type cdylib --target
+//! wasm32-unknown-unknown --features wasm` and run `wasm-bindgen` on the resulting module.
+//!
+//! The mode is passed as the bits of [`Mode`](../struct.Mode.html), e.g. `0x05` for `Mode::I`
+//! or `0x3f` for all of `Mode::M`, `Mode::S`, `Mode::I`, `Mode::LRA`, `Mode::SAMPLE_PEAK` and
+//! `Mode::TRUE_PEAK`. Errors are thrown as JavaScript `Error`s.
+//!
+//! Analyzing an `AudioBuffer` from the Web Audio API:
+//!
+//! ```js
+//! import { EbuR128 } from "./ebur128.js";
+//!
+//! function analyze(audioBuffer) {
+//!     const ebu = new EbuR128(audioBuffer.numberOfChannels, audioBuffer.sampleRate, 0x3f);
+//!     const planes = [];
+//!     for (let c = 0; c < audioBuffer.numberOfChannels; c++) {
+//!         planes.push(audioBuffer.getChannelData(c));
+//!     }
+//!
+//!     // Interleave the channels in chunks of one second
+//!     const chunk = new Float32Array(audioBuffer.sampleRate * planes.length);
+//!     for (let start = 0; start < audioBuffer.length; start += audioBuffer.sampleRate) {
+//!         const frames = Math.min(audioBuffer.sampleRate, audioBuffer.length - start);
+//!         for (let i = 0; i < frames; i++) {
+//!             for (let c = 0; c < planes.length; c++) {
+//!                 chunk[i * planes.length + c] = planes[c][start + i];
+//!             }
+//!         }
+//!         ebu.addFramesF32(chunk.subarray(0, frames * planes.length));
+//!     }
+//!
+//!     const result = {
+//!         integrated: ebu.loudnessGlobal(),
+//!         range: ebu.loudnessRange(),
+//!         truePeak: planes.map((_, c) => ebu.truePeak(c)),
+//!     };
+//!     ebu.free();
+//!
+//!     return result;
+//! }
+//! ```
+
+use crate::ebur128;
+
+use wasm_bindgen::prelude::*;
+
+impl From<ebur128::Error> for JsValue {
+    fn from(err: ebur128::Error) -> Self {
+        JsError::new(&err.to_string()).into()
+    }
+}
+
+/// EBU R128 loudness analyzer.
+#[wasm_bindgen]
+pub struct EbuR128(ebur128::EbuR128);
+
+#[wasm_bindgen]
+impl EbuR128 {
+    /// Create a new instance with the given configuration.
+    #[wasm_bindgen(constructor)]
+    pub fn new(channels: u32, samplerate: u32, mode: u8) -> Result<EbuR128, JsValue> {
+        let e = ebur128::EbuR128::new(
+            channels,
+            samplerate,
+            ebur128::Mode::from_bits_truncate(mode),
+        )?;
+
+        Ok(EbuR128(e))
+    }
+
+    /// Add interleaved frames to be processed.
+    #[wasm_bindgen(js_name = addFramesF32)]
+    pub fn add_frames_f32(&mut self, frames: &[f32]) -> Result<(), JsValue> {
+        Ok(self.0.add_frames_f32(frames)?)
+    }
+
+    /// Get global integrated loudness in LUFS.
+    #[wasm_bindgen(js_name = loudnessGlobal)]
+    pub fn loudness_global(&self) -> Result<f64, JsValue> {
+        Ok(self.0.loudness_global()?)
+    }
+
+    /// Get loudness range (LRA) of programme in LU.
+    #[wasm_bindgen(js_name = loudnessRange)]
+    pub fn loudness_range(&self) -> Result<f64, JsValue> {
+        Ok(self.0.loudness_range()?)
+    }
+
+    /// Get maximum true peak of the selected channel.
+    #[wasm_bindgen(js_name = truePeak)]
+    pub fn true_peak(&self, channel: u32) -> Result<f64, JsValue> {
+        Ok(self.0.true_peak(channel)?)
+    }
+}
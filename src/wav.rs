@@ -0,0 +1,164 @@
+// Copyright (c) 2011 Jan Kokemüller
+// Copyright (c) 2020 Sebastian Dröge <sebastian@centricular.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use crate::{EbuR128, EbuR128Summary, Error, Mode};
+
+use std::io::Read;
+
+/// Number of frames read from the file and processed at once.
+const CHUNK_FRAMES: usize = 4096;
+
+/// Results of [`analyze_wav`](fn.analyze_wav.html).
+///
+/// Values that are not measured with the selected [`Mode`](struct.Mode.html) are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    /// Integrated loudness, loudness range, maximum momentary and short-term loudness and the
+    /// maximum peaks of all channels.
+    pub summary: EbuR128Summary,
+    /// Sample peak per channel in dBFS if `Mode::SAMPLE_PEAK` is set.
+    pub sample_peak: Option<Vec<f64>>,
+    /// True peak per channel in dBTP if `Mode::TRUE_PEAK` is set.
+    pub true_peak: Option<Vec<f64>>,
+}
+
+/// Analyze a complete WAV file.
+///
+/// The samples are read and processed in chunks, so the file is never loaded into memory
+/// completely. 8 to 32 bit integer and 32 bit floating point samples are supported and give the
+/// same results as adding all frames with the function for the corresponding sample format, e.g.
+/// [`EbuR128::add_frames_i24_unpacked`](struct.EbuR128.html#method.add_frames_i24_unpacked) for
+/// 24 bit samples. The measurement is flushed at the end of the file, see
+/// [`EbuR128::flush`](struct.EbuR128.html#method.flush), so that the last incomplete block and
+/// the inter-sample peaks right at the end are considered.
+///
+/// Fails with `Error::Io` if reading the file fails, with `Error::Format` if the file is
+/// malformed or its format is not supported, and with `Error::NoChange` if the file contains no
+/// frames.
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let reader = hound::WavReader::open("input.wav")?;
+/// let analysis = ebur128::analyze_wav(reader, ebur128::Mode::I | ebur128::Mode::LRA)?;
+/// println!("{}", analysis.summary);
+/// # Ok(())
+/// # }
+/// ```
+pub fn analyze_wav<R: Read>(
+    mut reader: hound::WavReader<R>,
+    mode: Mode,
+) -> Result<Analysis, Error> {
+    let spec = reader.spec();
+    let mut ebu = EbuR128::new(u32::from(spec.channels), spec.sample_rate, mode)?;
+
+    match (spec.sample_format, spec.bits_per_sample) {
+        (hound::SampleFormat::Int, bits @ 1..=16) => {
+            let shift = 16 - bits;
+            add_samples(
+                &mut ebu,
+                &mut reader,
+                |s: i16| s << shift,
+                EbuR128::add_frames_i16,
+            )?
+        }
+        (hound::SampleFormat::Int, bits @ 17..=24) => {
+            let shift = 24 - bits;
+            add_samples(
+                &mut ebu,
+                &mut reader,
+                |s: i32| s << shift,
+                EbuR128::add_frames_i24_unpacked,
+            )?
+        }
+        (hound::SampleFormat::Int, bits @ 25..=32) => {
+            let shift = 32 - bits;
+            add_samples(
+                &mut ebu,
+                &mut reader,
+                |s: i32| s << shift,
+                EbuR128::add_frames_i32,
+            )?
+        }
+        (hound::SampleFormat::Float, 32) => {
+            add_samples(&mut ebu, &mut reader, |s: f32| s, EbuR128::add_frames_f32)?
+        }
+        _ => return Err(Error::Format),
+    }
+
+    ebu.flush()?;
+
+    let sample_peak = if mode.contains(Mode::SAMPLE_PEAK) {
+        Some(
+            ebu.sample_peak_per_channel()?
+                .into_iter()
+                .map(|peak| 20.0 * f64::log10(peak))
+                .collect(),
+        )
+    } else {
+        None
+    };
+    let true_peak = if mode.contains(Mode::TRUE_PEAK) {
+        Some(
+            ebu.true_peak_per_channel()?
+                .into_iter()
+                .map(|peak| 20.0 * f64::log10(peak))
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    Ok(Analysis {
+        summary: ebu.summary()?,
+        sample_peak,
+        true_peak,
+    })
+}
+
+/// Read all samples of the file, scale them to the full range of `S` with `convert` and add
+/// them in chunks with `add`.
+fn add_samples<R: Read, S: hound::Sample + Copy>(
+    ebu: &mut EbuR128,
+    reader: &mut hound::WavReader<R>,
+    convert: impl Fn(S) -> S,
+    add: fn(&mut EbuR128, &[S]) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let mut chunk = Vec::with_capacity(CHUNK_FRAMES * ebu.channels() as usize);
+
+    for sample in reader.samples::<S>() {
+        chunk.push(convert(sample.map_err(map_hound_error)?));
+
+        if chunk.len() == chunk.capacity() {
+            add(ebu, &chunk)?;
+            chunk.clear();
+        }
+    }
+
+    add(ebu, &chunk)
+}
+
+/// Map reading errors to `Error::Io` and all others, e.g. malformed files, to `Error::Format`.
+fn map_hound_error(err: hound::Error) -> Error {
+    match err {
+        hound::Error::IoError(_) => Error::Io,
+        _ => Error::Format,
+    }
+}
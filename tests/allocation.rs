@@ -0,0 +1,99 @@
+use ebur128::{EbuR128, Mode};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+const RATE: u32 = 48_000;
+
+/// Counts the allocations of the current thread, so that other tests and the test harness don't
+/// interfere.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+fn noise(seconds: usize, channels: usize) -> Vec<f32> {
+    let mut state = 0x1234_5678_u32;
+    (0..seconds * RATE as usize * channels)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            noise * (0.05 + 0.5 * (i as f32 / RATE as f32 / 7.0).sin().abs())
+        })
+        .collect()
+}
+
+#[test]
+fn histogram_mode_does_not_allocate() {
+    let samples = noise(60, 2);
+    let i16_samples = samples
+        .iter()
+        .map(|s| (s * i16::MAX as f32) as i16)
+        .collect::<Vec<_>>();
+    let (left, right): (Vec<f32>, Vec<f32>) = samples.chunks_exact(2).map(|f| (f[0], f[1])).unzip();
+
+    let mode = Mode::I | Mode::LRA | Mode::SAMPLE_PEAK | Mode::TRUE_PEAK | Mode::HISTOGRAM;
+    let mut ebu = EbuR128::new(2, RATE, mode).unwrap();
+    let mut reference = EbuR128::new(2, RATE, mode).unwrap();
+    reference.add_frames_f32(&samples).unwrap();
+
+    // Large buffers are processed in parallel with the `rayon` feature, which allocates
+    let max_frames = if cfg!(feature = "rayon") {
+        4095
+    } else {
+        samples.len()
+    };
+    assert_eq!(
+        allocations(|| {
+            // Many small and a few large buffers, including partial blocks
+            for chunk in samples[..samples.len() / 2].chunks(2 * 480 + 2) {
+                ebu.add_frames_f32(chunk).unwrap();
+            }
+            for chunk in samples[samples.len() / 2..].chunks(2 * max_frames) {
+                ebu.add_frames_f32(chunk).unwrap();
+            }
+            for chunk in i16_samples.chunks(2 * max_frames) {
+                ebu.add_frames_i16(chunk).unwrap();
+            }
+            for (left, right) in left.chunks(max_frames).zip(right.chunks(max_frames)) {
+                ebu.add_frames_planar_f32(&[left, right]).unwrap();
+            }
+        }),
+        0
+    );
+
+    // The measurement itself is unaffected
+    let mut expected = reference;
+    expected.add_frames_i16(&i16_samples).unwrap();
+    expected.add_frames_planar_f32(&[&left, &right]).unwrap();
+    assert_eq!(ebu.loudness_global(), expected.loudness_global());
+    assert_eq!(ebu.loudness_range(), expected.loudness_range());
+    assert_eq!(ebu.true_peak(1), expected.true_peak(1));
+    assert!(ebu.loudness_global().unwrap().is_finite());
+}
@@ -0,0 +1,48 @@
+use ebur128::{EbuR128, EbuR128Builder, Error, Mode};
+
+#[test]
+fn fluent_aliases() {
+    let ebu = EbuR128Builder::new()
+        .with_channels(2)
+        .with_rate(48_000)
+        .with_mode(Mode::I | Mode::LRA)
+        .with_history_ms(60_000)
+        .build()
+        .unwrap();
+    assert_eq!(ebu.channels(), 2);
+    assert_eq!(ebu.rate(), 48_000);
+    assert_eq!(ebu.mode().bits(), (Mode::I | Mode::LRA).bits());
+    assert_eq!(ebu.max_history(), 60_000);
+
+    let ebu = EbuR128Builder::new()
+        .with_channels(2)
+        .with_rate(48_000)
+        .with_mode(Mode::I)
+        .with_history_ms(u64::MAX)
+        .build()
+        .unwrap();
+    assert_eq!(ebu.max_history(), u32::MAX as usize);
+}
+
+#[test]
+fn new_is_the_default_configuration() {
+    let ebu = EbuR128::new(2, 48_000, Mode::I).unwrap();
+    assert_eq!(ebu.max_history(), usize::MAX);
+
+    for (channels, rate, mode, err) in [
+        (0, 48_000, Mode::I, Error::NoMem),
+        (2, 1, Mode::I, Error::NoMem),
+        (2, 48_000, Mode::empty(), Error::InvalidMode),
+    ] {
+        assert_eq!(EbuR128::new(channels, rate, mode).unwrap_err(), err);
+        assert_eq!(
+            EbuR128Builder::new()
+                .with_channels(channels)
+                .with_rate(rate)
+                .with_mode(mode)
+                .build()
+                .unwrap_err(),
+            err
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use ebur128::{EbuR128, Error, Mode, SampleFormat};
+
+const RATE: u32 = 48_000;
+const CHANNELS: u32 = 3;
+
+fn noise(seconds: usize) -> Vec<f64> {
+    let mut state = 0x1234_5678_u32;
+    (0..seconds * RATE as usize * CHANNELS as usize)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f64 / (1 << 24) as f64 - 0.5;
+            noise * (0.1 + 1.8 * (i as f64 / RATE as f64 / 5.0).sin().abs())
+        })
+        .collect()
+}
+
+/// Measures the samples given as bytes, starting at an odd address so that they are unaligned,
+/// and compares with the typed function.
+fn compare(format: SampleFormat, bytes: &[u8], add_typed: impl FnOnce(&mut EbuR128)) {
+    let mode = Mode::I | Mode::SAMPLE_PEAK | Mode::TRUE_PEAK;
+    let mut expected = EbuR128::new(CHANNELS, RATE, mode).unwrap();
+    add_typed(&mut expected);
+
+    let mut buffer = vec![0u8; bytes.len() + 1];
+    buffer[1..].copy_from_slice(bytes);
+    let bytes = &buffer[1..];
+
+    let frame = CHANNELS as usize * format.bytes_per_sample();
+    let mut ebu = EbuR128::new(CHANNELS, RATE, mode).unwrap();
+    for chunk in bytes.chunks(frame * 1237) {
+        ebu.add_frames_bytes(chunk, format).unwrap();
+    }
+
+    assert_eq!(ebu.frames_processed(), expected.frames_processed());
+    assert_eq!(ebu.loudness_global(), expected.loudness_global());
+    assert_eq!(ebu.loudness_momentary(), expected.loudness_momentary());
+    for c in 0..CHANNELS {
+        assert_eq!(ebu.sample_peak(c), expected.sample_peak(c));
+        assert_eq!(ebu.true_peak(c), expected.true_peak(c));
+    }
+    assert!(ebu.loudness_global().unwrap().is_finite());
+
+    // Incomplete frames are rejected without processing anything
+    let mut ebu = EbuR128::new(CHANNELS, RATE, mode).unwrap();
+    assert_eq!(
+        ebu.add_frames_bytes(&bytes[..frame + 1], format),
+        Err(Error::NoMem)
+    );
+    assert_eq!(ebu.frames_processed(), 0);
+}
+
+#[test]
+fn bytes_match_typed_samples() {
+    // Includes samples outside of ±1.0, which are clamped for the integer formats
+    let samples = noise(5);
+
+    let i16_samples = samples
+        .iter()
+        .map(|s| (s * 32768.0).clamp(-32768.0, 32767.0) as i16)
+        .collect::<Vec<_>>();
+    for &(format, be) in &[(SampleFormat::S16Le, false), (SampleFormat::S16Be, true)] {
+        let bytes = i16_samples
+            .iter()
+            .flat_map(|s| if be { s.to_be_bytes() } else { s.to_le_bytes() })
+            .collect::<Vec<_>>();
+        compare(format, &bytes, |ebu| {
+            ebu.add_frames_i16(&i16_samples).unwrap()
+        });
+    }
+
+    let i24_samples = samples
+        .iter()
+        .map(|s| (s * 8_388_608.0).clamp(-8_388_608.0, 8_388_607.0) as i32)
+        .collect::<Vec<_>>();
+    for &(format, be) in &[(SampleFormat::S24Le, false), (SampleFormat::S24Be, true)] {
+        let bytes = i24_samples
+            .iter()
+            .flat_map(|s| {
+                let b = s.to_le_bytes();
+                if be {
+                    [b[2], b[1], b[0]]
+                } else {
+                    [b[0], b[1], b[2]]
+                }
+            })
+            .collect::<Vec<_>>();
+        compare(format, &bytes, |ebu| {
+            ebu.add_frames_i24_unpacked(&i24_samples).unwrap()
+        });
+    }
+
+    let i32_samples = samples
+        .iter()
+        .map(|s| (s * 2_147_483_648.0) as i32)
+        .collect::<Vec<_>>();
+    for &(format, be) in &[(SampleFormat::S32Le, false), (SampleFormat::S32Be, true)] {
+        let bytes = i32_samples
+            .iter()
+            .flat_map(|s| if be { s.to_be_bytes() } else { s.to_le_bytes() })
+            .collect::<Vec<_>>();
+        compare(format, &bytes, |ebu| {
+            ebu.add_frames_i32(&i32_samples).unwrap()
+        });
+    }
+
+    let f32_samples = samples.iter().map(|s| *s as f32).collect::<Vec<_>>();
+    for &(format, be) in &[(SampleFormat::F32Le, false), (SampleFormat::F32Be, true)] {
+        let bytes = f32_samples
+            .iter()
+            .flat_map(|s| if be { s.to_be_bytes() } else { s.to_le_bytes() })
+            .collect::<Vec<_>>();
+        compare(format, &bytes, |ebu| {
+            ebu.add_frames_f32(&f32_samples).unwrap()
+        });
+    }
+
+    for &(format, be) in &[(SampleFormat::F64Le, false), (SampleFormat::F64Be, true)] {
+        let bytes = samples
+            .iter()
+            .flat_map(|s| if be { s.to_be_bytes() } else { s.to_le_bytes() })
+            .collect::<Vec<_>>();
+        compare(format, &bytes, |ebu| ebu.add_frames_f64(&samples).unwrap());
+    }
+}
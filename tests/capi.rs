@@ -0,0 +1,242 @@
+#![cfg(feature = "capi")]
+
+use ebur128::capi::*;
+use ebur128::{Channel, EbuR128, Mode};
+
+use std::os::raw::{c_int, c_uint, c_ulong, c_void};
+use std::ptr;
+
+const RATE: u32 = 48_000;
+const CHANNELS: u32 = 2;
+
+fn noise(seconds: usize) -> Vec<f64> {
+    let mut state = 0x1234_5678_u32;
+    (0..seconds * RATE as usize * CHANNELS as usize)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f64 / (1 << 24) as f64 - 0.5;
+            noise * (0.1 + 0.8 * (i as f64 / RATE as f64 / 5.0).sin().abs())
+        })
+        .collect()
+}
+
+fn deinterleave<T: Copy>(samples: &[T]) -> Vec<Vec<T>> {
+    (0..CHANNELS as usize)
+        .map(|c| {
+            samples
+                .iter()
+                .skip(c)
+                .step_by(CHANNELS as usize)
+                .copied()
+                .collect()
+        })
+        .collect()
+}
+
+/// Global loudness, sample peak and true peak of both channels.
+unsafe fn results(state: *mut State) -> Vec<f64> {
+    let mut results = vec![0.0; 5];
+    assert_eq!(
+        ebur128_loudness_global(state, &mut results[0]),
+        EBUR128_SUCCESS
+    );
+    for c in 0..CHANNELS {
+        let i = 1 + 2 * c as usize;
+        assert_eq!(
+            ebur128_sample_peak(state, c, &mut results[i]),
+            EBUR128_SUCCESS
+        );
+        assert_eq!(
+            ebur128_true_peak(state, c, &mut results[i + 1]),
+            EBUR128_SUCCESS
+        );
+    }
+    results
+}
+
+/// Feeds the samples once interleaved and once planar through the C API and compares the results.
+unsafe fn round_trip<T: Copy>(
+    samples: &[T],
+    interleaved: unsafe extern "C" fn(*mut State, *const T, usize) -> i32,
+    planar: unsafe extern "C" fn(*mut State, *const *const T, usize) -> i32,
+) {
+    let mode = EBUR128_MODE_I | EBUR128_MODE_SAMPLE_PEAK | EBUR128_MODE_TRUE_PEAK;
+    let mut a = ebur128_init(CHANNELS, RATE.into(), mode);
+    let mut b = ebur128_init(CHANNELS, RATE.into(), mode);
+    assert!(!a.is_null() && !b.is_null());
+
+    let frames = samples.len() / CHANNELS as usize;
+    assert_eq!(interleaved(a, samples.as_ptr(), frames), EBUR128_SUCCESS);
+
+    let planes = deinterleave(samples);
+    // Several calls with odd lengths
+    let mut offset = 0;
+    for len in [1, 4799, 12345, frames] {
+        let len = len.min(frames - offset);
+        let pointers = planes
+            .iter()
+            .map(|plane| plane[offset..].as_ptr())
+            .collect::<Vec<_>>();
+        assert_eq!(planar(b, pointers.as_ptr(), len), EBUR128_SUCCESS);
+        offset += len;
+    }
+    assert_eq!(offset, frames);
+    assert_eq!(ebur128_frames_processed(a), ebur128_frames_processed(b));
+
+    let results_a = results(a);
+    assert!(results_a[0].is_finite());
+    assert_eq!(results_a, results(b));
+
+    // Null pointers are rejected instead of dereferenced
+    assert_ne!(planar(b, ptr::null(), frames), EBUR128_SUCCESS);
+    let pointers = [planes[0].as_ptr(), ptr::null()];
+    assert_ne!(planar(b, pointers.as_ptr(), frames), EBUR128_SUCCESS);
+    assert_eq!(ebur128_frames_processed(a), ebur128_frames_processed(b));
+
+    ebur128_destroy(&mut a);
+    ebur128_destroy(&mut b);
+    assert!(a.is_null() && b.is_null());
+}
+
+#[test]
+fn planar_matches_interleaved() {
+    let samples = noise(10);
+    unsafe {
+        round_trip(
+            &samples
+                .iter()
+                .map(|s| (s * i16::MAX as f64) as i16)
+                .collect::<Vec<_>>(),
+            ebur128_add_frames_short,
+            ebur128_add_frames_planar_i16,
+        );
+        round_trip(
+            &samples
+                .iter()
+                .map(|s| (s * i32::MAX as f64) as i32)
+                .collect::<Vec<_>>(),
+            ebur128_add_frames_int,
+            ebur128_add_frames_planar_i32,
+        );
+        round_trip(
+            &samples.iter().map(|s| *s as f32).collect::<Vec<_>>(),
+            ebur128_add_frames_float,
+            ebur128_add_frames_planar_f32,
+        );
+        round_trip(
+            &samples,
+            ebur128_add_frames_double,
+            ebur128_add_frames_planar_f64,
+        );
+    }
+}
+
+/// `ebur128_state` as declared in libebur128's ebur128.h.
+#[repr(C)]
+struct LibState {
+    mode: c_int,
+    channels: c_uint,
+    samplerate: c_ulong,
+    d: *mut c_void,
+}
+
+#[test]
+fn state_fields_as_in_libebur128() {
+    unsafe {
+        let mut state = ebur128_init(2, 44_100, EBUR128_MODE_S);
+        let fields = &*(state as *const LibState);
+        assert_eq!(fields.mode, EBUR128_MODE_S);
+        assert_eq!(fields.channels, 2);
+        assert_eq!(fields.samplerate, 44_100);
+        assert!(!fields.d.is_null());
+
+        assert_eq!(
+            ebur128_change_parameters(state, 6, RATE.into()),
+            EBUR128_SUCCESS
+        );
+        let fields = &*(state as *const LibState);
+        assert_eq!(fields.channels, 6);
+        assert_eq!(fields.samplerate, RATE as c_ulong);
+        ebur128_destroy(&mut state);
+    }
+}
+
+#[test]
+fn matches_rust_api() {
+    let samples = noise(20)
+        .iter()
+        .map(|s| (s * i16::MAX as f64) as i16)
+        .collect::<Vec<_>>();
+    let mode = Mode::I | Mode::LRA | Mode::SAMPLE_PEAK | Mode::TRUE_PEAK;
+
+    let mut ebu = EbuR128::new(CHANNELS, RATE, mode).unwrap();
+    ebu.set_channel(1, Channel::Center).unwrap();
+    ebu.set_max_window(5000).unwrap();
+
+    unsafe {
+        let mut state = ebur128_init(CHANNELS, RATE.into(), mode.bits().into());
+        assert_eq!(ebur128_set_channel(state, 1, 3), EBUR128_SUCCESS);
+        assert_eq!(ebur128_set_max_window(state, 5000), EBUR128_SUCCESS);
+        assert_eq!(
+            ebur128_set_channel(state, 2, 1),
+            EBUR128_ERROR_INVALID_CHANNEL_INDEX
+        );
+        assert_eq!(
+            ebur128_init(0, RATE.into(), EBUR128_MODE_I),
+            ptr::null_mut()
+        );
+
+        for (i, chunk) in samples.chunks(2 * 4801).enumerate() {
+            ebu.add_frames_i16(chunk).unwrap();
+            assert_eq!(
+                ebur128_add_frames_short(state, chunk.as_ptr(), chunk.len() / 2),
+                EBUR128_SUCCESS
+            );
+
+            // The short-term and momentary loudness throughout the stream
+            if i % 7 == 0 {
+                let mut out = 0.0;
+                assert_eq!(ebur128_loudness_momentary(state, &mut out), EBUR128_SUCCESS);
+                assert_eq!(out, ebu.loudness_momentary().unwrap());
+                assert_eq!(ebur128_loudness_shortterm(state, &mut out), EBUR128_SUCCESS);
+                assert_eq!(out, ebu.loudness_shortterm().unwrap());
+                assert_eq!(
+                    ebur128_loudness_window(state, 5000, &mut out),
+                    EBUR128_SUCCESS
+                );
+                assert_eq!(out, ebu.loudness_window(5000).unwrap());
+            }
+        }
+
+        let mut out = 0.0;
+        assert_eq!(ebur128_loudness_global(state, &mut out), EBUR128_SUCCESS);
+        assert_eq!(out, ebu.loudness_global().unwrap());
+        assert_eq!(ebur128_loudness_range(state, &mut out), EBUR128_SUCCESS);
+        assert_eq!(out, ebu.loudness_range().unwrap());
+        assert!(out > 0.0);
+        for c in 0..CHANNELS {
+            assert_eq!(ebur128_sample_peak(state, c, &mut out), EBUR128_SUCCESS);
+            assert_eq!(out, ebu.sample_peak(c).unwrap());
+            assert_eq!(ebur128_true_peak(state, c, &mut out), EBUR128_SUCCESS);
+            assert_eq!(out, ebu.true_peak(c).unwrap());
+        }
+        assert_eq!(
+            ebur128_sample_peak(state, CHANNELS, &mut out),
+            EBUR128_ERROR_INVALID_CHANNEL_INDEX
+        );
+
+        // Errors use libebur128's codes
+        assert_eq!(
+            ebur128_change_parameters(state, CHANNELS, RATE.into()),
+            EBUR128_ERROR_NO_CHANGE
+        );
+        let mut shortterm = ebur128_init(CHANNELS, RATE.into(), EBUR128_MODE_S);
+        assert_eq!(
+            ebur128_loudness_global(shortterm, &mut out),
+            EBUR128_ERROR_INVALID_MODE
+        );
+
+        ebur128_destroy(&mut state);
+        ebur128_destroy(&mut shortterm);
+    }
+}
@@ -0,0 +1,50 @@
+use ebur128::{EbuR128, Mode};
+
+const RATE: u32 = 48_000;
+
+fn noise(seconds: usize, seed: u32) -> Vec<f32> {
+    let mut state = seed;
+    (0..2 * seconds * RATE as usize)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            noise * (0.05 + 0.5 * (i as f32 / RATE as f32 / 7.0).sin().abs())
+        })
+        .collect()
+}
+
+fn assert_same(a: &EbuR128, b: &EbuR128) {
+    assert_eq!(a.loudness_global(), b.loudness_global());
+    assert_eq!(a.loudness_momentary(), b.loudness_momentary());
+    assert_eq!(a.loudness_shortterm(), b.loudness_shortterm());
+    assert_eq!(a.loudness_range(), b.loudness_range());
+    for channel in 0..2 {
+        assert_eq!(a.sample_peak(channel), b.sample_peak(channel));
+        assert_eq!(a.true_peak(channel), b.true_peak(channel));
+    }
+}
+
+#[test]
+fn clone_mid_stream() {
+    let mode = Mode::I | Mode::LRA | Mode::SAMPLE_PEAK | Mode::TRUE_PEAK;
+    for mode in [mode, mode | Mode::HISTOGRAM] {
+        let mut ebu = EbuR128::new(2, RATE, mode).unwrap();
+        // Stop in the middle of a 100ms segment so that the partial block, the filter state and
+        // the true peak interpolator all have to be cloned
+        ebu.add_frames_f32(&noise(20, 1)[..2 * (20 * RATE as usize - 1234)])
+            .unwrap();
+
+        let mut clone = ebu.clone();
+        assert_same(&ebu, &clone);
+
+        let rest = noise(30, 2);
+        ebu.add_frames_f32(&rest).unwrap();
+        clone.add_frames_f32(&rest).unwrap();
+        assert_same(&ebu, &clone);
+        assert!(ebu.loudness_global().unwrap().is_finite());
+
+        // Both instances continue independently
+        clone.add_frames_f32(&vec![0.0; 2 * RATE as usize]).unwrap();
+        assert_ne!(ebu.loudness_momentary(), clone.loudness_momentary());
+    }
+}
@@ -0,0 +1,142 @@
+use ebur128::{Channel, EbuR128, Mode};
+
+const RATE: u32 = 48_000;
+
+/// 1kHz sine segments, given as the level of each channel in dBFS and the duration in seconds.
+fn tone(segments: &[(&[f64], f64)]) -> Vec<f32> {
+    let mut samples = Vec::new();
+    let mut n = 0;
+    for &(levels, seconds) in segments {
+        let amplitudes = levels
+            .iter()
+            .map(|db| 10f64.powf(db / 20.0))
+            .collect::<Vec<_>>();
+        for _ in 0..(seconds * RATE as f64).round() as usize {
+            let s = (2.0 * std::f64::consts::PI * 1000.0 * n as f64 / RATE as f64).sin();
+            samples.extend(amplitudes.iter().map(|a| (a * s) as f32));
+            n += 1;
+        }
+    }
+    samples
+}
+
+/// The same level on both channels of a stereo signal.
+fn stereo(segments: &[(f64, f64)]) -> Vec<f32> {
+    let levels = segments.iter().map(|&(db, _)| [db, db]).collect::<Vec<_>>();
+    tone(
+        &levels
+            .iter()
+            .zip(segments)
+            .map(|(levels, &(_, seconds))| (&levels[..], seconds))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Measures the samples with both history backends, fed in odd chunks.
+fn analyze(channels: u32, samples: &[f32]) -> Vec<EbuR128> {
+    let mode = Mode::M | Mode::S | Mode::I | Mode::LRA;
+    [mode, mode | Mode::HISTOGRAM]
+        .iter()
+        .map(|&mode| {
+            let mut ebu = EbuR128::new(channels, RATE, mode).unwrap();
+            for chunk in samples.chunks(channels as usize * 1237) {
+                ebu.add_frames_f32(chunk).unwrap();
+            }
+            ebu
+        })
+        .collect()
+}
+
+fn assert_close(value: f64, expected: f64, tolerance: f64, case: &str) {
+    assert!(
+        (value - expected).abs() <= tolerance,
+        "{}: {} instead of {} +/- {}",
+        case,
+        value,
+        expected,
+        tolerance
+    );
+}
+
+// The synthetic signals of EBU Tech 3341 and 3342 with their expected results and tolerances
+
+#[test]
+fn ebu_tech_3341_constant_levels() {
+    // Cases 1 and 2
+    for &level in &[-23.0, -33.0] {
+        for ebu in analyze(2, &stereo(&[(level, 20.0)])) {
+            let case = format!("{} dBFS", level);
+            assert_close(ebu.loudness_momentary().unwrap(), level, 0.1, &case);
+            assert_close(ebu.loudness_shortterm().unwrap(), level, 0.1, &case);
+            assert_close(ebu.loudness_global().unwrap(), level, 0.1, &case);
+        }
+    }
+}
+
+#[test]
+fn ebu_tech_3341_integrated() {
+    let cases: [(&str, Vec<f32>); 3] = [
+        (
+            "case 3",
+            stereo(&[(-36.0, 10.0), (-23.0, 60.0), (-36.0, 10.0)]),
+        ),
+        (
+            "case 4",
+            stereo(&[
+                (-72.0, 10.0),
+                (-36.0, 10.0),
+                (-23.0, 60.0),
+                (-36.0, 10.0),
+                (-72.0, 10.0),
+            ]),
+        ),
+        (
+            "case 5",
+            stereo(&[(-26.0, 20.0), (-20.0, 20.1), (-26.0, 20.0)]),
+        ),
+    ];
+    for (case, samples) in &cases {
+        for ebu in analyze(2, samples) {
+            assert_close(ebu.loudness_global().unwrap(), -23.0, 0.1, case);
+        }
+    }
+
+    // Case 6: 5.0 channels, the surround channels are weighted with +1.5 dB
+    let samples = tone(&[(&[-28.0, -28.0, -24.0, -30.0, -30.0], 20.0)]);
+    let mut ebu = EbuR128::new(5, RATE, Mode::I).unwrap();
+    ebu.set_channel_map(&[
+        Channel::Left,
+        Channel::Right,
+        Channel::Center,
+        Channel::LeftSurround,
+        Channel::RightSurround,
+    ])
+    .unwrap();
+    ebu.add_frames_f32(&samples).unwrap();
+    assert_close(ebu.loudness_global().unwrap(), -23.0, 0.1, "case 6");
+}
+
+#[test]
+fn ebu_tech_3342_loudness_range() {
+    let cases = [
+        ("case 1", stereo(&[(-20.0, 20.0), (-30.0, 20.0)]), 10.0),
+        ("case 2", stereo(&[(-20.0, 20.0), (-15.0, 20.0)]), 5.0),
+        ("case 3", stereo(&[(-40.0, 20.0), (-20.0, 20.0)]), 20.0),
+        (
+            "case 4",
+            stereo(&[
+                (-50.0, 20.0),
+                (-35.0, 20.0),
+                (-20.0, 20.0),
+                (-35.0, 20.0),
+                (-50.0, 20.0),
+            ]),
+            15.0,
+        ),
+    ];
+    for (case, samples, expected) in &cases {
+        for ebu in analyze(2, samples) {
+            assert_close(ebu.loudness_range().unwrap(), *expected, 1.0, case);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use ebur128::{energy_to_loudness, EbuR128, Error, Mode};
+
+const RATE: u32 = 48_000;
+
+/// Noise with a level that changes over 30 dB, so that many blocks are close to the gates.
+fn signal() -> Vec<f32> {
+    let mut state = 0x1234_5678_u32;
+    (0..120 * RATE as usize)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            let db = -30.0 * (i as f32 / RATE as f32 / 11.0).sin().abs();
+            noise * 10f32.powf(db / 20.0)
+        })
+        .collect()
+}
+
+fn analyze_samples(mode: Mode, history: Option<u32>, samples: &[f32]) -> EbuR128 {
+    let mut ebu = EbuR128::new(1, RATE, mode).unwrap();
+    if let Some(history) = history {
+        ebu.set_max_history(history).unwrap();
+    }
+    ebu.add_frames_f32(samples).unwrap();
+    ebu
+}
+
+fn analyze(mode: Mode) -> EbuR128 {
+    analyze_samples(mode, None, &signal())
+}
+
+#[test]
+fn histogram_matches_queue_with_custom_gate() {
+    let queue = analyze(Mode::I);
+    let histogram = analyze(Mode::I | Mode::HISTOGRAM);
+
+    assert_eq!(
+        queue.loudness_global_with_gate(-10.0),
+        queue.loudness_global()
+    );
+    assert_eq!(
+        histogram.loudness_global_with_gate(-10.0),
+        histogram.loudness_global()
+    );
+
+    for gate in [-8.0, -10.0, -20.0, -f64::INFINITY] {
+        let queue_loudness = queue.loudness_global_with_gate(gate).unwrap();
+        let histogram_loudness = histogram.loudness_global_with_gate(gate).unwrap();
+        assert!(
+            (queue_loudness - histogram_loudness).abs() < 0.05,
+            "{} {} {}",
+            gate,
+            queue_loudness,
+            histogram_loudness
+        );
+    }
+
+    // A higher gate excludes more quiet blocks
+    assert!(
+        queue.loudness_global_with_gate(-8.0).unwrap()
+            > queue.loudness_global_with_gate(-10.0).unwrap()
+    );
+}
+
+#[test]
+fn nan_gate() {
+    let ebu = analyze(Mode::I);
+    assert_eq!(
+        ebu.loudness_global_with_gate(f64::NAN),
+        Err(Error::OutOfRange)
+    );
+
+    let ebu = EbuR128::new(1, RATE, Mode::M).unwrap();
+    assert_eq!(ebu.loudness_global_with_gate(-8.0), Err(Error::InvalidMode));
+}
+
+#[test]
+fn queue_gating_matches_all_blocks() {
+    let ebu = analyze(Mode::I);
+
+    for gate in [-8.0, -10.0, -20.0] {
+        let energies = ebu.gating_blocks_iter().unwrap().collect::<Vec<_>>();
+        let threshold =
+            energies.iter().sum::<f64>() / energies.len() as f64 * 10f64.powf(gate / 10.0);
+        let gated = energies
+            .iter()
+            .filter(|energy| **energy >= threshold)
+            .collect::<Vec<_>>();
+        let expected = energy_to_loudness(gated.iter().copied().sum::<f64>() / gated.len() as f64);
+
+        let loudness = ebu.loudness_global_with_gate(gate).unwrap();
+        assert!(
+            (loudness - expected).abs() < 1e-9,
+            "{} {}",
+            loudness,
+            expected
+        );
+    }
+}
+
+#[test]
+fn queue_gating_is_exact() {
+    let samples = signal();
+    let (first, second) = samples.split_at(samples.len() / 2);
+
+    // The same blocks give the same result, independent of their order
+    let a = analyze_samples(Mode::I, None, first);
+    let b = analyze_samples(Mode::I, None, second);
+    let mut ab = a.clone();
+    ab.merge(&b).unwrap();
+    let mut ba = b.clone();
+    ba.merge(&a).unwrap();
+    for gate in [-8.0, -10.0, -20.0] {
+        assert_eq!(
+            ab.loudness_global_with_gate(gate),
+            ba.loudness_global_with_gate(gate)
+        );
+    }
+
+    // and independent of blocks that were dropped from the history before
+    let a = analyze_samples(Mode::I, Some(20_000), first);
+    let b = analyze_samples(Mode::I, Some(20_000), second);
+    let mut ab = a.clone();
+    ab.merge(&b).unwrap();
+    assert!(ab
+        .gating_blocks_iter()
+        .unwrap()
+        .eq(b.gating_blocks_iter().unwrap()));
+    for gate in [-8.0, -10.0, -20.0] {
+        assert_eq!(
+            ab.loudness_global_with_gate(gate),
+            b.loudness_global_with_gate(gate)
+        );
+    }
+}
+
+/// Neumaier summation in chronological order, like the sums over the gating blocks.
+fn compensated_sum<'a>(energies: impl Iterator<Item = &'a f64>) -> f64 {
+    let (sum, compensation) = energies.fold((0.0f64, 0.0f64), |(sum, compensation), value| {
+        let new_sum = sum + value;
+        let compensation = if sum.abs() >= value.abs() {
+            compensation + ((sum - new_sum) + value)
+        } else {
+            compensation + ((value - new_sum) + sum)
+        };
+        (new_sum, compensation)
+    });
+    sum + compensation
+}
+
+#[test]
+fn queue_gating_equals_chronological_sums() {
+    // Noise with different dynamics and lengths up to an hour, also with a limited history from
+    // which blocks were dropped and after merging
+    let mut instances = Vec::new();
+    let mut state = 0x8765_4321_u32;
+    for &(minutes, range, history) in &[
+        (2, 30.0, None),
+        (1, 10.0, None),
+        (10, 40.0, None),
+        (10, 60.0, Some(120_000)),
+        (60, 25.0, None),
+        (60, 50.0, Some(1_800_000)),
+    ] {
+        let rate = 8_000;
+        let samples = (0..minutes * 60 * rate)
+            .map(|i| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+                let db = -range * (i as f32 / rate as f32 / 13.0).sin().abs();
+                noise * 10f32.powf(db / 20.0)
+            })
+            .collect::<Vec<_>>();
+        let mut ebu = EbuR128::new(1, rate as u32, Mode::I).unwrap();
+        if let Some(history) = history {
+            ebu.set_max_history(history).unwrap();
+        }
+        ebu.add_frames_f32(&samples).unwrap();
+        instances.push(ebu);
+    }
+    let mut merged = instances[0].clone();
+    merged.merge(&instances[2]).unwrap();
+    instances.push(merged);
+
+    for ebu in &instances {
+        let energies = ebu.gating_blocks_iter().unwrap().collect::<Vec<_>>();
+        let mean = compensated_sum(energies.iter()) / energies.len() as f64;
+        assert_eq!(
+            ebu.relative_threshold().unwrap(),
+            energy_to_loudness(mean * 10f64.powf(-10.0 / 10.0))
+        );
+
+        for gate in [-8.0, -10.0, -20.0] {
+            let threshold = mean * 10f64.powf(gate / 10.0);
+            let gated = energies
+                .iter()
+                .filter(|energy| **energy >= threshold)
+                .collect::<Vec<_>>();
+            let expected =
+                energy_to_loudness(compensated_sum(gated.iter().copied()) / gated.len() as f64);
+            assert_eq!(ebu.loudness_global_with_gate(gate).unwrap(), expected);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use ebur128::{EbuR128, Error, HistoryBackend, Mode};
+
+const RATE: u32 = 48_000;
+
+/// Stereo noise with a level that slowly changes over 30 dB.
+fn noise(seconds: usize, seed: u32) -> Vec<f32> {
+    let mut state = seed;
+    (0..2 * seconds * RATE as usize)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            let db = -30.0 * (i as f32 / RATE as f32 / 11.0).sin().abs();
+            noise * 10f32.powf(db / 20.0)
+        })
+        .collect()
+}
+
+fn assert_close(a: Result<f64, Error>, b: Result<f64, Error>, tolerance: f64) {
+    let (a, b) = (a.unwrap(), b.unwrap());
+    assert!((a - b).abs() <= tolerance, "{} {}", a, b);
+}
+
+#[test]
+fn queue_converted_to_histogram() {
+    let mode = Mode::M | Mode::S | Mode::I | Mode::LRA | Mode::SAMPLE_PEAK | Mode::TRUE_PEAK;
+    let [before, after] = [noise(40, 1), noise(30, 2)];
+
+    for &bins_per_lu in &[10, 100] {
+        let mut ebu = EbuR128::new(2, RATE, mode).unwrap();
+        ebu.set_histogram_resolution(bins_per_lu).unwrap();
+        let mut histogram = EbuR128::new(2, RATE, mode | Mode::HISTOGRAM).unwrap();
+        histogram.set_histogram_resolution(bins_per_lu).unwrap();
+        ebu.add_frames_f32(&before).unwrap();
+        histogram.add_frames_f32(&before).unwrap();
+
+        let queue = ebu.clone();
+        ebu.set_history_backend(HistoryBackend::Histogram).unwrap();
+        assert_eq!(ebu.history_backend(), HistoryBackend::Histogram);
+        assert!(ebu.mode().contains(Mode::HISTOGRAM));
+
+        // The converted instance is the same as one that used the histogram from the start
+        assert_eq!(ebu, histogram);
+        assert_eq!(ebu.summary(), histogram.summary());
+
+        // and differs from the queue only by the quantization to the bins
+        let bin = 1.0 / f64::from(bins_per_lu);
+        assert_close(ebu.loudness_global(), queue.loudness_global(), bin);
+        assert_close(ebu.loudness_range(), queue.loudness_range(), 2.0 * bin);
+        assert_eq!(ebu.loudness_momentary(), queue.loudness_momentary());
+        assert_eq!(ebu.loudness_shortterm(), queue.loudness_shortterm());
+        for channel in 0..2 {
+            assert_eq!(ebu.sample_peak(channel), queue.sample_peak(channel));
+            assert_eq!(ebu.true_peak(channel), queue.true_peak(channel));
+        }
+
+        // Converting again changes nothing, but the blocks can't be restored
+        ebu.set_history_backend(HistoryBackend::Histogram).unwrap();
+        assert_eq!(
+            ebu.set_history_backend(HistoryBackend::Queue),
+            Err(Error::InvalidMode)
+        );
+        assert_eq!(ebu.history_backend(), HistoryBackend::Histogram);
+
+        // Both continue the same
+        for chunk in after.chunks(2 * 4801) {
+            ebu.add_frames_f32(chunk).unwrap();
+            histogram.add_frames_f32(chunk).unwrap();
+            assert_eq!(ebu.loudness_momentary(), histogram.loudness_momentary());
+            assert_eq!(ebu.loudness_shortterm(), histogram.loudness_shortterm());
+        }
+        assert_eq!(ebu, histogram);
+        assert_eq!(ebu.summary(), histogram.summary());
+    }
+}
+
+#[test]
+fn backend_can_be_chosen_before_adding_frames() {
+    let mut ebu = EbuR128::new(2, RATE, Mode::I | Mode::LRA | Mode::HISTOGRAM).unwrap();
+    ebu.set_history_backend(HistoryBackend::Queue).unwrap();
+    assert_eq!(ebu.history_backend(), HistoryBackend::Queue);
+
+    let samples = noise(20, 3);
+    ebu.add_frames_f32(&samples).unwrap();
+    let mut queue = EbuR128::new(2, RATE, Mode::I | Mode::LRA).unwrap();
+    queue.add_frames_f32(&samples).unwrap();
+    assert_eq!(ebu, queue);
+    assert!(ebu
+        .gating_blocks_iter()
+        .unwrap()
+        .eq(queue.gating_blocks_iter().unwrap()));
+}
+
+#[test]
+fn only_retained_blocks_are_converted() {
+    // The last 10s are 20 dB quieter
+    let quiet = noise(10, 5).iter().map(|s| s * 0.1).collect::<Vec<_>>();
+    let samples = [noise(30, 4), quiet].concat();
+    let mut ebu = EbuR128::new(2, RATE, Mode::I | Mode::LRA).unwrap();
+    ebu.set_max_history(10_000).unwrap();
+    ebu.add_frames_f32(&samples).unwrap();
+    let queue = ebu.clone();
+    let mut all = EbuR128::new(2, RATE, Mode::I | Mode::LRA | Mode::HISTOGRAM).unwrap();
+    all.add_frames_f32(&samples).unwrap();
+
+    // The histogram continues with the last 10s instead of the whole programme
+    ebu.set_history_backend(HistoryBackend::Histogram).unwrap();
+    assert_close(ebu.loudness_global(), queue.loudness_global(), 0.1);
+    assert!(
+        ebu.loudness_global().unwrap() + 10.0 < all.loudness_global().unwrap(),
+        "{} {}",
+        ebu.loudness_global().unwrap(),
+        all.loudness_global().unwrap()
+    );
+}
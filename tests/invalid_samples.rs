@@ -0,0 +1,126 @@
+use ebur128::{EbuR128, Error, InvalidSamplePolicy, Mode};
+
+const RATE: u32 = 48_000;
+
+fn noise(seconds: usize, seed: u32) -> Vec<f32> {
+    let mut state = seed;
+    (0..2 * seconds * RATE as usize)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            noise * (0.05 + 0.5 * (i as f32 / RATE as f32 / 7.0).sin().abs())
+        })
+        .collect()
+}
+
+fn analyzer(policy: InvalidSamplePolicy) -> EbuR128 {
+    let mode = Mode::I | Mode::LRA | Mode::S | Mode::SAMPLE_PEAK | Mode::TRUE_PEAK;
+    let mut ebu = EbuR128::new(2, RATE, mode).unwrap();
+    ebu.set_invalid_sample_policy(policy);
+    ebu
+}
+
+fn assert_same(a: &EbuR128, b: &EbuR128) {
+    assert!(a
+        .gating_blocks_iter()
+        .unwrap()
+        .eq(b.gating_blocks_iter().unwrap()));
+    assert_eq!(a.frames_processed(), b.frames_processed());
+    assert_eq!(a.loudness_global(), b.loudness_global());
+    assert_eq!(a.loudness_momentary(), b.loudness_momentary());
+    assert_eq!(a.loudness_shortterm(), b.loudness_shortterm());
+    assert_eq!(a.loudness_range(), b.loudness_range());
+    for channel in 0..2 {
+        assert_eq!(a.sample_peak(channel), b.sample_peak(channel));
+        assert_eq!(a.true_peak(channel), b.true_peak(channel));
+    }
+    assert!(a.loudness_global().unwrap().is_finite());
+    assert!(a.loudness_shortterm().unwrap().is_finite());
+}
+
+/// Before, a buffer with the invalid samples and after.
+fn program(invalid: f32) -> [Vec<f32>; 3] {
+    let mut broken = noise(5, 2);
+    broken[2 * 12_345 + 1] = invalid;
+    broken[2 * 54_321] = invalid;
+    [noise(10, 1), broken, noise(20, 3)]
+}
+
+#[test]
+fn error_leaves_the_state_untouched() {
+    for invalid in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        let [before, broken, after] = program(invalid);
+
+        let mut ebu = analyzer(InvalidSamplePolicy::Error);
+        let mut reference = analyzer(InvalidSamplePolicy::Error);
+        ebu.add_frames_f32(&before).unwrap();
+        reference.add_frames_f32(&before).unwrap();
+
+        assert_eq!(
+            ebu.add_frames_f32(&broken),
+            Err(Error::InvalidSample {
+                frame_index: 12_345,
+                channel: 1
+            })
+        );
+        let broken_f64 = broken.iter().map(|s| f64::from(*s)).collect::<Vec<_>>();
+        assert!(ebu.add_frames_f64(&broken_f64).is_err());
+        let (left, right): (Vec<f32>, Vec<f32>) =
+            broken.chunks_exact(2).map(|f| (f[0], f[1])).unzip();
+        assert!(ebu.add_frames_planar_f32(&[&left, &right]).is_err());
+        assert_same(&ebu, &reference);
+
+        // Continuing gives the same results as if the broken buffer never happened
+        ebu.add_frames_f32(&after).unwrap();
+        reference.add_frames_f32(&after).unwrap();
+        assert_same(&ebu, &reference);
+        assert_eq!(ebu.sanitized_samples(), 0);
+    }
+}
+
+#[test]
+fn clamp_to_zero_measures_silence_instead() {
+    for invalid in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        let [before, broken, after] = program(invalid);
+        let silenced = broken
+            .iter()
+            .map(|s| if s.is_finite() { *s } else { 0.0 })
+            .collect::<Vec<_>>();
+
+        let mut ebu = analyzer(InvalidSamplePolicy::ClampToZero);
+        let mut reference = analyzer(InvalidSamplePolicy::Ignore);
+        for samples in [&before, &broken, &after] {
+            ebu.add_frames_f32(samples).unwrap();
+        }
+        for samples in [&before, &silenced, &after] {
+            reference.add_frames_f32(samples).unwrap();
+        }
+        assert_same(&ebu, &reference);
+        assert_eq!(ebu.sanitized_samples(), 2);
+
+        ebu.reset();
+        assert_eq!(ebu.sanitized_samples(), 0);
+    }
+}
+
+#[test]
+fn ignore_is_recovered_by_reset() {
+    let [before, broken, after] = program(f32::NAN);
+
+    // Without checks the NaN gets into the filter and all following values are NaN
+    let mut ebu = analyzer(InvalidSamplePolicy::Ignore);
+    for samples in [&before, &broken, &after] {
+        ebu.add_frames_f32(samples).unwrap();
+    }
+    assert!(ebu.loudness_shortterm().unwrap().is_nan());
+    assert!(ebu.loudness_global().unwrap().is_nan());
+
+    // but nothing of it survives a reset
+    ebu.reset();
+    let mut reference = analyzer(InvalidSamplePolicy::Ignore);
+    for samples in [&before, &after] {
+        ebu.add_frames_f32(samples).unwrap();
+        reference.add_frames_f32(samples).unwrap();
+    }
+    assert_same(&ebu, &reference);
+}
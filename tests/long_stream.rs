@@ -0,0 +1,162 @@
+use ebur128::{energy_to_loudness, EbuR128, Mode};
+
+// A low sample rate keeps streams of many hours fast enough
+const RATE: u32 = 8_000;
+
+/// Endless mono noise at about -60 LUFS with a 1s burst about 20 dB louder every 15 minutes.
+struct Noise {
+    state: u32,
+    frame: u64,
+}
+
+impl Noise {
+    fn new() -> Self {
+        Noise {
+            state: 0x1234_5678,
+            frame: 0,
+        }
+    }
+
+    fn fill(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            self.state = self
+                .state
+                .wrapping_mul(1_664_525)
+                .wrapping_add(1_013_904_223);
+            let noise = (self.state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            let second = self.frame / u64::from(RATE);
+            let gain = if second % 900 == 450 { 0.03 } else { 0.003 };
+            *sample = noise * gain;
+            self.frame += 1;
+        }
+    }
+}
+
+#[test]
+#[ignore = "streams 24 hours of audio, run with --release"]
+fn no_drift_over_24_hours() {
+    const HOURS: usize = 24;
+
+    let mode = Mode::I;
+    let mut ebu = EbuR128::new(1, RATE, mode).unwrap();
+    let mut histogram = EbuR128::new(1, RATE, mode | Mode::HISTOGRAM).unwrap();
+    let mut hours = Vec::new();
+
+    let mut noise = Noise::new();
+    let mut buffer = vec![0.0; RATE as usize];
+    for _ in 0..HOURS {
+        // Every hour is also measured by a fresh instance
+        let mut hour = EbuR128::new(1, RATE, mode).unwrap();
+        for _ in 0..3600 {
+            noise.fill(&mut buffer);
+            ebu.add_frames_f32(&buffer).unwrap();
+            histogram.add_frames_f32(&buffer).unwrap();
+            hour.add_frames_f32(&buffer).unwrap();
+        }
+        hours.push(hour);
+    }
+
+    let loudness = ebu.loudness_global().unwrap();
+    assert!((-62.0..-55.0).contains(&loudness), "{}", loudness);
+
+    // Reference with the gating done on chunks of blocks with fresh accumulators
+    let energies = ebu.gating_blocks_iter().unwrap().collect::<Vec<_>>();
+    assert_eq!(energies.len(), HOURS * 36_000 - 3);
+    let sum = |energies: &[&f64]| {
+        energies
+            .chunks(1000)
+            .map(|chunk| chunk.iter().copied().sum::<f64>())
+            .sum::<f64>()
+    };
+    let all = energies.iter().collect::<Vec<_>>();
+    let threshold = sum(&all) / all.len() as f64 * 0.1;
+    let gated = energies
+        .iter()
+        .filter(|energy| **energy >= threshold)
+        .collect::<Vec<_>>();
+    // Both the bursts and the noise pass the relative gate
+    assert_eq!(gated.len(), all.len());
+    let expected = energy_to_loudness(sum(&gated) / gated.len() as f64);
+    assert!(
+        (loudness - expected).abs() < 0.01,
+        "{} {}",
+        loudness,
+        expected
+    );
+
+    // Fresh instances for every hour only miss the three blocks across each boundary
+    let expected = EbuR128::loudness_global_multiple(hours.iter()).unwrap();
+    assert!(
+        (loudness - expected).abs() < 0.01,
+        "{} {}",
+        loudness,
+        expected
+    );
+
+    let histogram = histogram.loudness_global().unwrap();
+    assert!(
+        (loudness - histogram).abs() < 0.05,
+        "{} {}",
+        loudness,
+        histogram
+    );
+}
+
+#[test]
+#[ignore = "streams 48 hours of audio, run with --release"]
+fn memory_usage_stays_flat_over_48_hours() {
+    const HOURS: usize = 48;
+
+    let mode = Mode::M | Mode::S | Mode::I | Mode::LRA | Mode::SAMPLE_PEAK;
+    let mut histogram = EbuR128::new(1, RATE, mode | Mode::HISTOGRAM).unwrap();
+    // The queues are bounded by the maximum history of one hour
+    let mut queue = EbuR128::new(1, RATE, mode).unwrap();
+    queue.set_max_history(3_600_000).unwrap();
+
+    let mut histogram_usage = Vec::new();
+    let mut queue_usage = Vec::new();
+    let mut noise = Noise::new();
+    let mut buffer = vec![0.0; RATE as usize];
+    for _ in 0..HOURS {
+        for _ in 0..3600 {
+            noise.fill(&mut buffer);
+            histogram.add_frames_f32(&buffer).unwrap();
+            queue.add_frames_f32(&buffer).unwrap();
+        }
+        histogram_usage.push(histogram.memory_usage());
+        queue_usage.push(queue.memory_usage());
+    }
+
+    // The histograms don't grow at all
+    assert!(histogram_usage
+        .iter()
+        .all(|usage| *usage == histogram_usage[0]));
+
+    // The queues are full after the first hour. The buffers of the histogram bins used for the
+    // gating still follow the loudness distribution, so they vary by a few hundred bytes but
+    // the second day doesn't use more than the first one.
+    let block_history = queue_usage
+        .iter()
+        .map(|usage| usage.block_history)
+        .collect::<Vec<_>>();
+    let first_day = *block_history[1..24].iter().max().unwrap();
+    for &bytes in &block_history[24..] {
+        assert!(
+            bytes * 100 <= first_day * 101 && bytes * 100 >= first_day * 99,
+            "{} {}",
+            bytes,
+            first_day
+        );
+    }
+    // The ring buffers keep up to twice 8 bytes per block and the bins up to four times 4 bytes,
+    // for one hour of gating blocks and 3s blocks plus the bins themselves
+    let blocks = 36_000 + 1_200;
+    assert!(first_day < 48 * blocks, "{}", first_day);
+    assert!(queue_usage
+        .iter()
+        .all(|usage| usage.audio_buffer == queue_usage[0].audio_buffer
+            && usage.other == queue_usage[0].other));
+
+    assert!(histogram.loudness_global().unwrap().is_finite());
+    assert!(queue.loudness_global().unwrap().is_finite());
+}
@@ -0,0 +1,148 @@
+use ebur128::{EbuR128, Error, Mode};
+
+const RATE: u32 = 48_000;
+
+/// Mono noise with a level that slowly changes over `span` dB.
+fn noise(span: f32) -> Vec<f32> {
+    let mut state = 0x1234_5678_u32;
+    (0..60 * RATE as usize)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            let db = -span * (i as f32 / RATE as f32 / 7.0).sin().abs();
+            noise * 10f32.powf(db / 20.0)
+        })
+        .collect()
+}
+
+fn analyze(mode: Mode) -> EbuR128 {
+    let mut ebu = EbuR128::new(1, RATE, mode).unwrap();
+    ebu.add_frames_f32(&noise(40.0)).unwrap();
+    ebu
+}
+
+#[test]
+fn default_percentiles_match_loudness_range() {
+    for mode in [Mode::LRA, Mode::LRA | Mode::HISTOGRAM] {
+        let ebu = analyze(mode);
+        assert_eq!(
+            ebu.loudness_range_with_percentiles(10.0, 95.0),
+            ebu.loudness_range()
+        );
+    }
+}
+
+#[test]
+fn nearby_percentiles_give_nearby_results() {
+    // A constant sine has the same loudness in all blocks
+    let samples = (0..5 * RATE as usize)
+        .flat_map(|i| {
+            let s = 0.1 * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / RATE as f32).sin();
+            [s, s]
+        })
+        .collect::<Vec<_>>();
+    let mut ebu = EbuR128::new(2, RATE, Mode::LRA).unwrap();
+    ebu.add_frames_f32(&samples).unwrap();
+    let range = ebu.loudness_range().unwrap();
+    assert!(range.abs() < 0.01, "{}", range);
+    assert_eq!(
+        ebu.loudness_range_with_percentiles(10.0, 95.000001),
+        Ok(range)
+    );
+    assert_eq!(
+        ebu.loudness_range_with_percentiles(9.999999, 95.0),
+        Ok(range)
+    );
+
+    for mode in [Mode::LRA, Mode::LRA | Mode::HISTOGRAM] {
+        let ebu = analyze(mode);
+        let range = ebu.loudness_range().unwrap();
+        for delta in [1e-9, 1e-6, 1e-3] {
+            for (low, high) in [(10.0 - delta, 95.0), (10.0, 95.0 + delta)] {
+                let nearby = ebu.loudness_range_with_percentiles(low, high).unwrap();
+                assert!((range - nearby).abs() < 0.2, "{} {}", range, nearby);
+            }
+        }
+
+        // Moving a percentile by 0.01 at most moves to the next block
+        let mut previous = ebu.loudness_range_with_percentiles(10.0, 90.0).unwrap();
+        for i in 1..=900 {
+            let high = 90.0 + i as f64 / 100.0;
+            let range = ebu.loudness_range_with_percentiles(10.0, high).unwrap();
+            assert!(
+                range >= previous && range - previous < 0.5,
+                "{} {} {}",
+                high,
+                previous,
+                range
+            );
+            previous = range;
+        }
+    }
+
+    // Both histories select the same percentiles
+    let queue = analyze(Mode::LRA);
+    let histogram = analyze(Mode::LRA | Mode::HISTOGRAM);
+    for (low, high) in [(1.0, 99.0), (5.0, 95.0), (10.0, 95.0), (25.0, 75.0)] {
+        let queue_range = queue.loudness_range_with_percentiles(low, high).unwrap();
+        let histogram_range = histogram
+            .loudness_range_with_percentiles(low, high)
+            .unwrap();
+        assert!(
+            (queue_range - histogram_range).abs() < 0.2,
+            "{} {}",
+            queue_range,
+            histogram_range
+        );
+    }
+}
+
+#[test]
+fn invalid_percentiles() {
+    let ebu = analyze(Mode::LRA);
+    for (low, high) in [(-1.0, 95.0), (10.0, 101.0), (50.0, 50.0), (f64::NAN, 95.0)] {
+        assert_eq!(
+            ebu.loudness_range_with_percentiles(low, high),
+            Err(Error::OutOfRange)
+        );
+    }
+}
+
+#[test]
+fn finer_bins_converge_to_queue() {
+    let resolutions = [1, 10, 100];
+    let mut max_errors = [0.0f64; 3];
+    for &span in &[7.3, 13.7, 22.1, 40.0] {
+        let samples = noise(span);
+        let mut queue = EbuR128::new(1, RATE, Mode::LRA).unwrap();
+        queue.add_frames_f32(&samples).unwrap();
+        let expected = queue.loudness_range().unwrap();
+
+        for (&bins_per_lu, max_error) in resolutions.iter().zip(&mut max_errors) {
+            let mut ebu = EbuR128::new(1, RATE, Mode::LRA | Mode::HISTOGRAM).unwrap();
+            ebu.set_histogram_resolution(bins_per_lu).unwrap();
+            ebu.add_frames_f32(&samples).unwrap();
+            let lra = ebu.loudness_range().unwrap();
+
+            // Both percentiles are quantized to the same bins, so the error is below one bin
+            let error = (lra - expected).abs();
+            assert!(
+                error < 1.0 / f64::from(bins_per_lu),
+                "{} {} {} {}",
+                span,
+                bins_per_lu,
+                lra,
+                expected
+            );
+            *max_error = max_error.max(error);
+        }
+    }
+
+    // and shrinks with finer bins
+    assert!(
+        max_errors[0] > max_errors[1] && max_errors[1] > max_errors[2],
+        "{:?}",
+        max_errors
+    );
+    assert!(max_errors[2] < 0.01, "{:?}", max_errors);
+}
@@ -0,0 +1,141 @@
+use ebur128::{Channel, EbuR128, Error, Mode};
+
+const RATE: u32 = 48_000;
+
+#[test]
+fn maxima_before_first_block() {
+    let mut ebu = EbuR128::new(1, RATE, Mode::S).unwrap();
+    assert_eq!(ebu.loudness_momentary_max(), Err(Error::NoChange));
+    assert_eq!(ebu.loudness_shortterm_max(), Err(Error::NoChange));
+
+    // One frame short of the first 400ms block
+    let samples = vec![0.1f32; 3 * RATE as usize];
+    ebu.add_frames_f32(&samples[..2 * RATE as usize / 5 - 1])
+        .unwrap();
+    assert_eq!(ebu.loudness_momentary_max(), Err(Error::NoChange));
+    assert_eq!(ebu.loudness_shortterm_max(), Err(Error::NoChange));
+
+    ebu.add_frames_f32(&[0.1]).unwrap();
+    let max = ebu.loudness_momentary_max().unwrap();
+    assert!((max - ebu.loudness_momentary().unwrap()).abs() < 1e-9);
+    assert_eq!(ebu.loudness_shortterm_max(), Err(Error::NoChange));
+
+    // One frame short of the first 3s block
+    ebu.add_frames_f32(&samples[2 * RATE as usize / 5 + 1..])
+        .unwrap();
+    assert_eq!(ebu.loudness_shortterm_max(), Err(Error::NoChange));
+
+    ebu.add_frames_f32(&[0.1]).unwrap();
+    let max = ebu.loudness_shortterm_max().unwrap();
+    assert!((max - ebu.loudness_shortterm().unwrap()).abs() < 1e-9);
+
+    ebu.reset();
+    assert_eq!(ebu.loudness_momentary_max(), Err(Error::NoChange));
+    assert_eq!(ebu.loudness_shortterm_max(), Err(Error::NoChange));
+
+    // Digital silence completes blocks, but has no loudness
+    ebu.add_frames_f32(&vec![0.0; 3 * RATE as usize]).unwrap();
+    assert_eq!(ebu.loudness_momentary_max(), Ok(-f64::INFINITY));
+    assert_eq!(ebu.loudness_shortterm_max(), Ok(-f64::INFINITY));
+
+    let mut ebu = EbuR128::new(1, RATE, Mode::S).unwrap();
+    ebu.add_frames_f32(&samples[..RATE as usize / 10]).unwrap();
+    let summary = ebu.summary().unwrap();
+    assert_eq!(summary.loudness_momentary_max, None);
+    assert_eq!(summary.loudness_shortterm_max, None);
+}
+
+#[test]
+fn changing_a_channel_resets_its_peaks() {
+    let mode = Mode::I | Mode::SAMPLE_PEAK | Mode::TRUE_PEAK;
+    let mut ebu = EbuR128::new(2, RATE, mode).unwrap();
+    let samples = [0.9f32, 0.2].repeat(2 * RATE as usize);
+    ebu.add_frames_f32(&samples).unwrap();
+    let global = ebu.loudness_global().unwrap();
+    let blocks = ebu.gating_blocks_iter().unwrap().count();
+
+    // Setting the same type keeps everything
+    ebu.set_channel(0, Channel::Left).unwrap();
+    assert!((ebu.sample_peak(0).unwrap() - 0.9).abs() < 1e-6);
+    assert!(ebu.true_peak(0).unwrap() >= 0.9 - 1e-6);
+
+    ebu.set_channel(0, Channel::Center).unwrap();
+    assert_eq!(ebu.sample_peak(0), Ok(0.0));
+    assert_eq!(ebu.true_peak(0), Ok(0.0));
+    assert!((ebu.sample_peak(1).unwrap() - 0.2).abs() < 1e-6);
+    assert!(ebu.true_peak(1).unwrap() >= 0.2 - 1e-6);
+
+    // The loudness history is kept
+    assert_eq!(ebu.loudness_global(), Ok(global));
+    assert_eq!(ebu.gating_blocks_iter().unwrap().count(), blocks);
+
+    ebu.add_frames_f32(&[0.3f32, 0.2].repeat(RATE as usize))
+        .unwrap();
+    assert!((ebu.sample_peak(0).unwrap() - 0.3).abs() < 1e-6);
+
+    // Changing the map resets the peaks of the changed channels only
+    ebu.set_channel_map(&[Channel::Center, Channel::Left])
+        .unwrap();
+    assert!((ebu.sample_peak(0).unwrap() - 0.3).abs() < 1e-6);
+    assert_eq!(ebu.sample_peak(1), Ok(0.0));
+}
+
+/// Stereo 1 kHz sine with the given `(dBFS, seconds)` segments, as used by the EBU Tech 3341
+/// test cases.
+fn tone(segments: &[(f64, f64)]) -> Vec<f32> {
+    let mut samples = Vec::new();
+    let mut n = 0;
+    for &(db, seconds) in segments {
+        let amplitude = 10f64.powf(db / 20.0);
+        for _ in 0..(seconds * RATE as f64).round() as usize {
+            let s =
+                amplitude * (2.0 * std::f64::consts::PI * 1000.0 * n as f64 / RATE as f64).sin();
+            samples.push(s as f32);
+            samples.push(s as f32);
+            n += 1;
+        }
+    }
+    samples
+}
+
+fn maxima(samples: &[f32]) -> (f64, f64) {
+    let mut ebu = EbuR128::new(2, RATE, Mode::M | Mode::S).unwrap();
+    // Feed in odd chunks so that blocks complete in the middle of calls
+    for chunk in samples.chunks(2 * 1237) {
+        ebu.add_frames_f32(chunk).unwrap();
+    }
+    (
+        ebu.loudness_momentary_max().unwrap(),
+        ebu.loudness_shortterm_max().unwrap(),
+    )
+}
+
+#[test]
+fn ebu_tech_3341_maxima() {
+    // Cases 1 and 2: constant -23 and -33 dBFS sines read -23 and -33 LUFS
+    for &level in &[-23.0, -33.0] {
+        let (momentary, shortterm) = maxima(&tone(&[(level, 20.0)]));
+        assert!((momentary - level).abs() <= 0.1, "{}", momentary);
+        assert!((shortterm - level).abs() <= 0.1, "{}", shortterm);
+    }
+
+    // Case 9: 1.34s at -20 dBFS alternating with 1.66s at -30 dBFS reads a constant -23 LUFS
+    // short-term loudness once the first 3s have passed
+    let samples = tone(&[(-20.0, 1.34), (-30.0, 1.66)].repeat(20));
+    let (_, shortterm) = maxima(&samples);
+    assert!((shortterm + 23.0).abs() <= 0.1, "{}", shortterm);
+
+    // Case 12: 0.18s at -20 dBFS alternating with 0.22s at -30 dBFS reads a constant -23 LUFS
+    // momentary loudness
+    let samples = tone(&[(-20.0, 0.18), (-30.0, 0.22)].repeat(20));
+    let (momentary, _) = maxima(&samples);
+    assert!((momentary + 23.0).abs() <= 0.1, "{}", momentary);
+
+    // Single 3s bursts at -23 LUFS after varying amounts of silence have a maximum short-term
+    // loudness of -23 LUFS, independent of their alignment to the 100ms blocks
+    for i in 0..20 {
+        let silence = 1.0 + i as f64 * 0.005;
+        let (_, shortterm) = maxima(&tone(&[(-120.0, silence), (-23.0, 3.0), (-120.0, 1.0)]));
+        assert!((shortterm + 23.0).abs() <= 0.1, "{} {}", silence, shortterm);
+    }
+}
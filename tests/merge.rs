@@ -0,0 +1,101 @@
+use ebur128::{EbuR128, Error, Mode};
+
+const RATE: u32 = 48_000;
+
+/// Stereo noise with a level that changes every second, so that the loudness range and the
+/// relative gate are not trivial.
+fn signal(seconds: usize) -> Vec<f32> {
+    let mut state = 0x1234_5678_u32;
+    (0..seconds * RATE as usize * 2)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            let second = i / (RATE as usize * 2);
+            noise * [0.8, 0.1, 0.4, 0.02, 0.6][second % 5]
+        })
+        .collect()
+}
+
+fn analyze(mode: Mode, samples: &[f32]) -> EbuR128 {
+    let mut ebu = EbuR128::new(2, RATE, mode).unwrap();
+    ebu.add_frames_f32(samples).unwrap();
+    ebu
+}
+
+#[test]
+fn merge_loses_blocks_at_the_boundary() {
+    let mode = Mode::I | Mode::LRA | Mode::SAMPLE_PEAK;
+    let samples = signal(20);
+    let (first, second) = samples.split_at(samples.len() / 2);
+
+    let single = analyze(mode, &samples);
+    let mut merged = analyze(mode, first);
+    merged.merge(&analyze(mode, second)).unwrap();
+
+    // The three 400ms blocks overlapping the boundary are missing
+    let (single_blocks, _) = single.gating_block_count_and_energy().unwrap();
+    let (merged_blocks, _) = merged.gating_block_count_and_energy().unwrap();
+    assert_eq!(single_blocks - merged_blocks, 3);
+
+    let global = (single.loudness_global().unwrap() - merged.loudness_global().unwrap()).abs();
+    assert!(global > 0.0 && global < 0.1, "{}", global);
+    let threshold =
+        (single.relative_threshold().unwrap() - merged.relative_threshold().unwrap()).abs();
+    assert!(threshold < 0.1, "{}", threshold);
+    let range = (single.loudness_range().unwrap() - merged.loudness_range().unwrap()).abs();
+    assert!(range < 0.5, "{}", range);
+
+    assert_eq!(
+        single.sample_peak(0).unwrap(),
+        merged.sample_peak(0).unwrap()
+    );
+}
+
+#[test]
+fn merge_rejects_different_configurations() {
+    let mut ebu = EbuR128::new(2, RATE, Mode::I).unwrap();
+
+    let mut other = EbuR128::new(2, RATE, Mode::I).unwrap();
+    other.set_max_window(5000).unwrap();
+    assert_eq!(ebu.merge(&other), Err(Error::IncompatibleState));
+
+    let mut other = EbuR128::new(2, RATE, Mode::I).unwrap();
+    other.set_max_history(60_000).unwrap();
+    assert_eq!(ebu.merge(&other), Err(Error::IncompatibleState));
+
+    let other = EbuR128::new(2, 44_100, Mode::I).unwrap();
+    assert_eq!(ebu.merge(&other), Err(Error::IncompatibleState));
+
+    let other = EbuR128::new(2, RATE, Mode::I).unwrap();
+    assert_eq!(ebu.merge(&other), Ok(()));
+}
+
+#[test]
+fn merge_halves_with_overlap() {
+    // Overlapping the halves by 400ms and seeding the filter with the first 100ms of them
+    // measures every gating block exactly once, see `EbuR128::loudness_global_multiple`
+    let samples = signal(20);
+    let frames_100ms = RATE as usize / 10 * 2;
+    let half = samples.len() / 2;
+    let first = &samples[..half];
+    let seed = &samples[half - 4 * frames_100ms..half - 3 * frames_100ms];
+    let second = &samples[half - 3 * frames_100ms..];
+
+    for mode in [Mode::I, Mode::I | Mode::HISTOGRAM] {
+        let single = analyze(mode, &samples);
+
+        let mut merged = analyze(mode, first);
+        let mut other = EbuR128::new(2, RATE, mode).unwrap();
+        other.seed_frames_f32(seed).unwrap();
+        other.add_frames_f32(second).unwrap();
+        merged.merge(&other).unwrap();
+
+        assert_eq!(
+            single.gating_block_count_and_energy().unwrap().0,
+            merged.gating_block_count_and_energy().unwrap().0
+        );
+        let single = single.loudness_global().unwrap();
+        let merged = merged.loudness_global().unwrap();
+        assert!((single - merged).abs() < 1e-6, "{} {}", single, merged);
+    }
+}
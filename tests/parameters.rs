@@ -0,0 +1,217 @@
+use ebur128::{Channel, EbuR128, Error, Mode};
+
+const RATE: u32 = 48_000;
+
+/// Noise with the given number of channels and gain.
+fn noise(seconds: usize, channels: usize, gain: f32, seed: u32) -> Vec<f32> {
+    let mut state = seed;
+    (0..seconds * RATE as usize * channels)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            gain * ((state >> 8) as f32 / (1 << 24) as f32 - 0.5)
+        })
+        .collect()
+}
+
+fn analyze(channels: u32, samples: &[f32]) -> EbuR128 {
+    let mut ebu = EbuR128::new(channels, RATE, Mode::I | Mode::LRA).unwrap();
+    ebu.add_frames_f32(samples).unwrap();
+    ebu
+}
+
+#[test]
+fn channel_switch_mid_program() {
+    // A quiet stereo segment followed by a louder 5.1 segment
+    let stereo = noise(20, 2, 0.3, 0x1234_5678);
+    let surround = noise(30, 6, 0.4, 0x8765_4321);
+
+    let mut ebu = analyze(2, &stereo);
+    ebu.change_parameters(6, RATE).unwrap();
+    ebu.add_frames_f32(&surround).unwrap();
+    let loudness = ebu.loudness_global().unwrap();
+
+    let stereo = analyze(2, &stereo);
+    let surround = analyze(6, &surround);
+    let stereo_loudness = stereo.loudness_global().unwrap();
+    let surround_loudness = surround.loudness_global().unwrap();
+
+    // Both segments are above the relative gate of each other, so both count
+    assert!(surround_loudness - stereo_loudness < 10.0);
+    assert!(
+        stereo_loudness + 0.5 < loudness && loudness + 0.5 < surround_loudness,
+        "{} {} {}",
+        stereo_loudness,
+        loudness,
+        surround_loudness
+    );
+
+    let expected = EbuR128::loudness_global_multiple([&stereo, &surround].iter().copied()).unwrap();
+    assert!(
+        (loudness - expected).abs() < 1e-9,
+        "{} {}",
+        loudness,
+        expected
+    );
+
+    let expected = EbuR128::loudness_range_multiple([&stereo, &surround].iter().copied()).unwrap();
+    let lra = ebu.loudness_range().unwrap();
+    assert!((lra - expected).abs() < 1e-9, "{} {}", lra, expected);
+}
+
+#[test]
+fn dual_mono_pair_measures_like_mono() {
+    let sine = (0..20 * RATE as usize)
+        .map(|i| 0.1 * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / RATE as f32).sin())
+        .collect::<Vec<_>>();
+
+    let mut mono = EbuR128::new(1, RATE, Mode::I).unwrap();
+    mono.set_channel(0, Channel::Center).unwrap();
+    mono.add_frames_f32(&sine).unwrap();
+
+    let mut dual_mono = EbuR128::new(2, RATE, Mode::I).unwrap();
+    dual_mono
+        .set_channel_map(&[Channel::DualMonoLeft, Channel::DualMonoRight])
+        .unwrap();
+    let stereo = sine.iter().flat_map(|s| [*s, *s]).collect::<Vec<_>>();
+    dual_mono.add_frames_f32(&stereo).unwrap();
+
+    let expected = mono.loudness_global().unwrap();
+    let global = dual_mono.loudness_global().unwrap();
+    assert!((global - expected).abs() < 1e-9, "{} {}", global, expected);
+
+    // As plain stereo the same signal is 3 LU louder
+    let stereo = analyze(2, &stereo).loudness_global().unwrap();
+    assert!((stereo - expected - 3.01).abs() < 0.01, "{}", stereo);
+}
+
+#[test]
+fn dual_mono_pairs_must_be_complete() {
+    let mut ebu = EbuR128::new(3, RATE, Mode::I).unwrap();
+    for map in [
+        [Channel::DualMonoLeft, Channel::Right, Channel::Center],
+        [Channel::Left, Channel::Right, Channel::DualMonoRight],
+        [
+            Channel::DualMonoLeft,
+            Channel::DualMonoRight,
+            Channel::DualMonoRight,
+        ],
+        [
+            Channel::DualMonoLeft,
+            Channel::DualMonoLeft,
+            Channel::DualMonoRight,
+        ],
+        [Channel::DualMono, Channel::Right, Channel::Center],
+    ] {
+        assert_eq!(ebu.set_channel_map(&map), Err(Error::InvalidChannelIndex));
+    }
+    assert_eq!(ebu.channel_map()[0], Channel::Left);
+
+    // Pairs can only be set or removed at once
+    assert_eq!(
+        ebu.set_channel(0, Channel::DualMonoLeft),
+        Err(Error::InvalidChannelIndex)
+    );
+    ebu.set_channel_map(&[
+        Channel::DualMonoRight,
+        Channel::Center,
+        Channel::DualMonoLeft,
+    ])
+    .unwrap();
+    assert_eq!(
+        ebu.set_channel(0, Channel::Right),
+        Err(Error::InvalidChannelIndex)
+    );
+    assert_eq!(
+        ebu.set_channel(1, Channel::DualMonoLeft),
+        Err(Error::InvalidChannelIndex)
+    );
+    // Other channels can still be set one by one
+    ebu.set_channel(1, Channel::Left).unwrap();
+    ebu.set_channel_map(&[Channel::Left, Channel::Right, Channel::Center])
+        .unwrap();
+}
+
+/// Independent pink noise on each channel, filtered with Paul Kellet's economy filter.
+fn pink_noise(seconds: usize, channels: usize) -> Vec<f32> {
+    let mut state = 0x2468_ace0_u32;
+    let mut filters = vec![[0.0f32; 3]; channels];
+    (0..seconds * RATE as usize * channels)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let white = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            let b = &mut filters[i % channels];
+            b[0] = 0.99765 * b[0] + white * 0.0990460;
+            b[1] = 0.96300 * b[1] + white * 0.2965164;
+            b[2] = 0.57000 * b[2] + white * 1.0526913;
+            0.1 * (b[0] + b[1] + b[2] + white * 0.1848)
+        })
+        .collect()
+}
+
+#[test]
+fn height_channels_of_7_1_4() {
+    let samples = pink_noise(10, 12);
+    let bed = [
+        Channel::Left,
+        Channel::Right,
+        Channel::Center,
+        Channel::Unused,
+        Channel::Mp090,
+        Channel::Mm090,
+        Channel::Mp135,
+        Channel::Mm135,
+    ];
+    let measure = |heights: [Channel; 4], with_bed: bool| {
+        let mut ebu = EbuR128::new(12, RATE, Mode::I).unwrap();
+        let map = bed
+            .iter()
+            .map(|c| if with_bed { *c } else { Channel::Unused })
+            .chain(heights.iter().copied())
+            .collect::<Vec<_>>();
+        ebu.set_channel_map(&map).unwrap();
+        ebu.add_frames_f32(&samples).unwrap();
+        ebu.loudness_global().unwrap()
+    };
+    let energy = |loudness: f64| 10f64.powf((loudness + 0.691) / 10.0);
+
+    let heights = [
+        Channel::Up045,
+        Channel::Um045,
+        Channel::Up135,
+        Channel::Um135,
+    ];
+    let loudness = measure(heights, true);
+
+    // The upper layer is weighted with 0 dB, just like Left and Right
+    let as_front = [Channel::Left, Channel::Right, Channel::Left, Channel::Right];
+    assert!((loudness - measure(as_front, true)).abs() < 1e-9);
+
+    // The height channels add their unweighted energy to the bed
+    let bed_only = energy(measure([Channel::Unused; 4], true));
+    let heights_only = energy(measure(heights, false));
+    let expected = energy(loudness);
+    assert!(
+        ((bed_only + heights_only) / expected - 1.0).abs() < 1e-6,
+        "{} {} {}",
+        bed_only,
+        heights_only,
+        expected
+    );
+
+    // Compared to the +1.5 dB of middle layer side channels
+    let as_sides = [
+        Channel::Mp090,
+        Channel::Mm090,
+        Channel::LeftSurround,
+        Channel::RightSurround,
+    ];
+    let difference = measure(as_sides, true) - loudness;
+    let expected = 10.0 * ((bed_only + 1.41 * heights_only) / (bed_only + heights_only)).log10();
+    assert!(difference > 0.3, "{}", difference);
+    assert!(
+        (difference - expected).abs() < 1e-6,
+        "{} {}",
+        difference,
+        expected
+    );
+}
@@ -0,0 +1,295 @@
+use ebur128::{EbuR128, Mode};
+
+const RATE: u32 = 48_000;
+const CASES: u32 = 24;
+
+/// Random numbers from a fixed seed, so that failures are reproducible.
+struct Rng(u32);
+
+impl Rng {
+    fn next(&mut self) -> f64 {
+        self.0 = self.0.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (self.0 >> 8) as f64 / (1 << 24) as f64
+    }
+
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next()
+    }
+}
+
+/// Parameters of a random multi-channel signal.
+#[derive(Debug, Clone, Copy)]
+enum Signal {
+    /// Sum of two sinusoids with an amplitude envelope
+    Sines {
+        channels: u32,
+        seconds: f64,
+        frequencies: [f64; 2],
+        amplitudes: [f64; 2],
+        phase: f64,
+        envelope: f64,
+    },
+    Dc(f64),
+    FullScaleSine(f64),
+    Zeros,
+}
+
+impl Signal {
+    fn random(rng: &mut Rng) -> Self {
+        match (rng.next() * 10.0) as u32 {
+            0 => Signal::Dc(rng.range(-1.0, 1.0)),
+            1 => Signal::FullScaleSine(rng.range(20.0, 20_000.0)),
+            2 => Signal::Zeros,
+            _ => Signal::Sines {
+                channels: 1 + (rng.next() * 3.0) as u32,
+                seconds: rng.range(0.5, 8.0),
+                frequencies: [rng.range(20.0, 20_000.0), rng.range(20.0, 2_000.0)],
+                amplitudes: [rng.range(0.0, 0.7), rng.range(0.0, 0.3)],
+                phase: rng.range(0.0, 2.0 * std::f64::consts::PI),
+                envelope: rng.range(0.0, 1.0),
+            },
+        }
+    }
+
+    fn channels(&self) -> u32 {
+        match *self {
+            Signal::Sines { channels, .. } => channels,
+            _ => 2,
+        }
+    }
+
+    /// Interleaved samples with the amplitude scaled by `gain`, faded in and out over 10ms so that
+    /// the K-weighting filter doesn't ring at the ends.
+    fn samples(&self, gain: f64) -> Vec<f32> {
+        let seconds = match *self {
+            Signal::Sines { seconds, .. } => seconds,
+            _ => 3.0,
+        };
+        let frames = (seconds * RATE as f64) as usize;
+        let fade = RATE as usize / 100;
+        let channels = self.channels() as usize;
+        (0..frames)
+            .flat_map(|i| {
+                let t = i as f64 / RATE as f64;
+                let s = match *self {
+                    Signal::Sines {
+                        frequencies,
+                        amplitudes,
+                        phase,
+                        envelope,
+                        ..
+                    } => {
+                        let level = 1.0 - envelope * (0.5 + 0.5 * (t * 1.3).sin());
+                        level
+                            * (amplitudes[0]
+                                * (2.0 * std::f64::consts::PI * frequencies[0] * t + phase).sin()
+                                + amplitudes[1]
+                                    * (2.0 * std::f64::consts::PI * frequencies[1] * t).sin())
+                    }
+                    Signal::Dc(value) => value,
+                    Signal::FullScaleSine(frequency) => {
+                        (2.0 * std::f64::consts::PI * frequency * t).sin()
+                    }
+                    Signal::Zeros => 0.0,
+                };
+                let fade = (i.min(frames - 1 - i) as f64 / fade as f64).min(1.0);
+                (0..channels).map(move |c| (gain * fade * s * (1.0 - 0.2 * c as f64)) as f32)
+            })
+            .collect()
+    }
+}
+
+fn analyze(signal: &Signal, mode: Mode, samples: &[f32]) -> EbuR128 {
+    let mut ebu = EbuR128::new(signal.channels(), RATE, mode).unwrap();
+    ebu.add_frames_f32(samples).unwrap();
+    ebu
+}
+
+/// Runs `property` on random signals, including each edge case at least once.
+fn check(seed: u32, property: impl Fn(&Signal)) {
+    let mut rng = Rng(seed);
+    let edge_cases = [
+        Signal::Dc(0.5),
+        Signal::Dc(-1.0),
+        Signal::FullScaleSine(997.0),
+        Signal::Zeros,
+    ];
+    for signal in edge_cases
+        .iter()
+        .copied()
+        .chain((0..CASES).map(|_| Signal::random(&mut rng)))
+    {
+        property(&signal);
+    }
+}
+
+fn assert_close(a: f64, b: f64, tolerance: f64, signal: &Signal) {
+    assert!(
+        a == b || (a - b).abs() <= tolerance,
+        "{} {} {:?}",
+        a,
+        b,
+        signal
+    );
+}
+
+#[test]
+fn doubling_the_amplitude_adds_6_lu() {
+    let lu = 20.0 * 2f64.log10();
+    check(1, |signal| {
+        let mode = Mode::I;
+        let ebu = analyze(signal, mode, &signal.samples(1.0));
+        let doubled = analyze(signal, mode, &signal.samples(2.0));
+
+        // Silence stays silent, everything else gets exactly 6.02 LU louder
+        let global = ebu.loudness_global().unwrap();
+        assert_close(
+            global + lu,
+            doubled.loudness_global().unwrap(),
+            1e-3,
+            signal,
+        );
+        let momentary = ebu.loudness_momentary().unwrap();
+        assert_close(
+            momentary + lu,
+            doubled.loudness_momentary().unwrap(),
+            1e-3,
+            signal,
+        );
+    });
+}
+
+#[test]
+fn silence_padding_does_not_raise_loudness() {
+    check(2, |signal| {
+        // Blocks at the ends only partially overlap the signal, so padding gives more weight to
+        // the edges. This can raise the loudness if they are louder than the rest, so keep the
+        // level constant.
+        let signal = match *signal {
+            // The K-weighting removes DC, so all that's measured is the filter ringing at the
+            // fades, and without padding the ringing after the end isn't measured at all
+            Signal::Dc(_) => return,
+            Signal::Sines {
+                channels,
+                seconds,
+                frequencies,
+                amplitudes,
+                phase,
+                ..
+            } => Signal::Sines {
+                channels,
+                seconds,
+                frequencies,
+                amplitudes,
+                phase,
+                envelope: 0.0,
+            },
+            signal => signal,
+        };
+        let signal = &signal;
+
+        let samples = signal.samples(1.0);
+        let channels = signal.channels() as usize;
+        let silence = vec![0.0; channels * RATE as usize * 2];
+        let padded = [&silence[..], &samples, &silence].concat();
+
+        let mode = Mode::I | Mode::LRA;
+        let global = analyze(signal, mode, &samples).loudness_global().unwrap();
+        let padded = analyze(signal, mode, &padded).loudness_global().unwrap();
+        assert!(
+            padded <= global + 1e-9,
+            "{} {} {:?}",
+            padded,
+            global,
+            signal
+        );
+    });
+}
+
+#[test]
+fn loudness_range_is_not_negative() {
+    check(3, |signal| {
+        let samples = signal.samples(1.0);
+        let mode = Mode::LRA;
+        for mode in [mode, mode | Mode::HISTOGRAM] {
+            let lra = analyze(signal, mode, &samples).loudness_range().unwrap();
+            assert!(lra >= 0.0 && lra.is_finite(), "{} {:?}", lra, signal);
+        }
+    });
+}
+
+#[test]
+fn chunking_does_not_change_results() {
+    check(4, |signal| {
+        let samples = signal.samples(1.0);
+        let channels = signal.channels() as usize;
+        let mode = Mode::I | Mode::LRA | Mode::SAMPLE_PEAK | Mode::TRUE_PEAK;
+        let single = analyze(signal, mode, &samples);
+
+        // Random chunk sizes between one frame and 200ms
+        let mut chunked = EbuR128::new(signal.channels(), RATE, mode).unwrap();
+        let mut rest = &samples[..];
+        let mut sizes = Rng(samples.len() as u32);
+        while !rest.is_empty() {
+            let frames = 1 + (sizes.next() * 9_600.0) as usize;
+            let (chunk, tail) = rest.split_at((frames * channels).min(rest.len()));
+            chunked.add_frames_f32(chunk).unwrap();
+            rest = tail;
+        }
+
+        assert_eq!(single.loudness_global(), chunked.loudness_global());
+        assert_eq!(single.loudness_momentary(), chunked.loudness_momentary());
+        assert_eq!(single.loudness_range(), chunked.loudness_range());
+        for c in 0..signal.channels() {
+            assert_eq!(single.sample_peak(c), chunked.sample_peak(c));
+            assert_eq!(single.true_peak(c), chunked.true_peak(c));
+        }
+    });
+}
+
+#[test]
+fn merge_matches_multiple() {
+    check(5, |signal| {
+        let samples = signal.samples(1.0);
+        let channels = signal.channels() as usize;
+        let split = samples.len() / channels / 3 * channels;
+        let (first, second) = samples.split_at(split);
+
+        let mode = Mode::I | Mode::LRA;
+        let first = analyze(signal, mode, first);
+        let second = analyze(signal, mode, second);
+        let mut merged = first.clone();
+        merged.merge(&second).unwrap();
+        let mut reversed = second.clone();
+        reversed.merge(&first).unwrap();
+
+        let global = EbuR128::loudness_global_multiple([&first, &second].iter().copied()).unwrap();
+        assert_close(merged.loudness_global().unwrap(), global, 1e-9, signal);
+        assert_close(reversed.loudness_global().unwrap(), global, 1e-9, signal);
+        let range = EbuR128::loudness_range_multiple([&first, &second].iter().copied()).unwrap();
+        assert_close(merged.loudness_range().unwrap(), range, 1e-9, signal);
+        assert_close(reversed.loudness_range().unwrap(), range, 1e-9, signal);
+    });
+}
+
+#[test]
+fn histogram_agrees_with_queue() {
+    check(6, |signal| {
+        let samples = signal.samples(1.0);
+        let mode = Mode::I | Mode::LRA;
+        let queue = analyze(signal, mode, &samples);
+        let histogram = analyze(signal, mode | Mode::HISTOGRAM, &samples);
+
+        let global = queue.loudness_global().unwrap();
+        // Blocks below the lowest histogram bin at -70 LUFS are ignored, like by the absolute gate
+        if global > -69.0 || global == -f64::INFINITY {
+            assert_close(global, histogram.loudness_global().unwrap(), 0.05, signal);
+        }
+        assert_close(
+            queue.loudness_range().unwrap(),
+            histogram.loudness_range().unwrap(),
+            0.2,
+            signal,
+        );
+    });
+}
@@ -0,0 +1,74 @@
+use ebur128::{EbuR128, Error, Mode};
+
+const RATE: u32 = 48_000;
+
+fn noise(seconds: usize) -> Vec<f32> {
+    let mut state = 0x1234_5678_u32;
+    (0..2 * seconds * RATE as usize)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            noise * (0.05 + 0.5 * (i as f32 / RATE as f32 / 3.0).sin().abs())
+        })
+        .collect()
+}
+
+fn assert_same(a: &EbuR128, b: &EbuR128) {
+    assert_eq!(a.loudness_global(), b.loudness_global());
+    assert_eq!(a.loudness_momentary(), b.loudness_momentary());
+    assert_eq!(a.loudness_shortterm(), b.loudness_shortterm());
+    assert_eq!(a.loudness_momentary_max(), b.loudness_momentary_max());
+    assert_eq!(a.loudness_shortterm_max(), b.loudness_shortterm_max());
+    assert_eq!(a.loudness_range(), b.loudness_range());
+    for channel in 0..2 {
+        assert_eq!(a.sample_peak(channel), b.sample_peak(channel));
+        assert_eq!(a.true_peak(channel), b.true_peak(channel));
+    }
+}
+
+#[test]
+fn one_frame_at_a_time() {
+    let samples = noise(10);
+    let mode = Mode::I | Mode::LRA | Mode::SAMPLE_PEAK | Mode::TRUE_PEAK;
+    for mode in [mode, mode | Mode::HISTOGRAM] {
+        let mut chunked = EbuR128::new(2, RATE, mode).unwrap();
+        for chunk in samples.chunks(2 * 4800) {
+            chunked.add_frames_f32(chunk).unwrap();
+        }
+
+        let mut ebu = EbuR128::new(2, RATE, mode).unwrap();
+        for (i, frame) in samples.chunks_exact(2).enumerate() {
+            ebu.push_frame_f32(frame).unwrap();
+
+            // Same results as a single call for everything up to here, once per second
+            if (i + 1) % RATE as usize == 0 {
+                let mut reference = EbuR128::new(2, RATE, mode).unwrap();
+                reference.add_frames_f32(&samples[..2 * (i + 1)]).unwrap();
+                assert_same(&ebu, &reference);
+            }
+        }
+        assert_eq!(ebu.frames_processed(), chunked.frames_processed());
+        assert_same(&ebu, &chunked);
+        assert!(ebu.loudness_global().unwrap().is_finite());
+
+        // Tiny buffers of a real-time callback
+        let mut small = EbuR128::new(2, RATE, mode).unwrap();
+        for chunk in samples.chunks(2 * 32) {
+            small.add_frames_f32(chunk).unwrap();
+        }
+        assert_same(&small, &chunked);
+    }
+}
+
+#[test]
+fn push_frame_needs_one_sample_per_channel() {
+    let mut ebu = EbuR128::new(2, RATE, Mode::I).unwrap();
+    assert_eq!(ebu.push_frame_f32(&[0.1]), Err(Error::InvalidChannelCount));
+    assert_eq!(
+        ebu.push_frame_f32(&[0.1, 0.1, 0.1]),
+        Err(Error::InvalidChannelCount)
+    );
+    assert_eq!(ebu.frames_processed(), 0);
+    ebu.push_frame_f32(&[0.1, 0.1]).unwrap();
+    assert_eq!(ebu.frames_processed(), 1);
+}
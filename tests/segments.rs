@@ -0,0 +1,91 @@
+use ebur128::{EbuR128, EbuR128Summary, Error, Mode};
+
+const RATE: u32 = 48_000;
+
+/// Stereo noise with a level that changes every few seconds, like speech with pauses.
+fn chapter(seconds: usize, gain: f32, seed: u32) -> Vec<f32> {
+    let mut state = seed;
+    (0..2 * seconds * RATE as usize)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            let second = i / (2 * RATE as usize);
+            noise * gain * [1.0, 0.5, 0.8, 0.05, 0.7, 0.3][second % 6]
+        })
+        .collect()
+}
+
+fn assert_close(a: Option<f64>, b: Option<f64>, tolerance: f64) {
+    let (a, b) = (a.unwrap(), b.unwrap());
+    assert!((a - b).abs() <= tolerance, "{} {}", a, b);
+}
+
+#[test]
+fn three_chapter_audiobook() {
+    let chapters = [
+        chapter(40, 0.3, 1),
+        chapter(25, 0.1, 2),
+        chapter(33, 0.5, 3),
+    ];
+    let mode = Mode::all() & !Mode::HISTOGRAM;
+
+    for mode in [mode, mode | Mode::HISTOGRAM] {
+        let mut ebu = EbuR128::new(2, RATE, mode).unwrap();
+        assert_eq!(ebu.end_segment(), Err(Error::NoChange));
+
+        let mut summaries: Vec<EbuR128Summary> = Vec::new();
+        for samples in &chapters {
+            ebu.begin_segment().unwrap();
+            assert_eq!(ebu.begin_segment(), Err(Error::IncompatibleState));
+            for chunk in samples.chunks(2 * 4801) {
+                ebu.add_frames_f32(chunk).unwrap();
+            }
+            summaries.push(ebu.end_segment().unwrap());
+        }
+        assert_eq!(ebu.end_segment(), Err(Error::NoChange));
+
+        // Each chapter is measured like by a separate instance, only the filters continue with
+        // the previous chapter
+        for (samples, summary) in chapters.iter().zip(&summaries) {
+            let mut single = EbuR128::new(2, RATE, mode).unwrap();
+            single.add_frames_f32(samples).unwrap();
+            let expected = single.summary().unwrap();
+
+            assert_close(summary.loudness_global, expected.loudness_global, 0.01);
+            assert_close(summary.loudness_range, expected.loudness_range, 0.1);
+            assert_close(
+                summary.loudness_momentary_max,
+                expected.loudness_momentary_max,
+                0.01,
+            );
+            assert_close(
+                summary.loudness_shortterm_max,
+                expected.loudness_shortterm_max,
+                0.01,
+            );
+            assert_eq!(summary.sample_peak_max, expected.sample_peak_max);
+            assert_close(summary.true_peak_max, expected.true_peak_max, 0.01);
+        }
+
+        // The chapters are clearly distinguished
+        let loudness = summaries
+            .iter()
+            .map(|summary| summary.loudness_global.unwrap())
+            .collect::<Vec<_>>();
+        assert!(loudness[1] + 5.0 < loudness[0] && loudness[0] + 3.0 < loudness[2]);
+
+        // and the whole programme isn't affected by them
+        let mut programme = EbuR128::new(2, RATE, mode).unwrap();
+        for samples in &chapters {
+            programme.add_frames_f32(samples).unwrap();
+        }
+        assert_eq!(ebu.summary(), programme.summary());
+        let global = ebu.loudness_global().unwrap();
+        assert!(loudness[1] < global && global < loudness[2], "{}", global);
+        let sample_peak = summaries
+            .iter()
+            .map(|summary| summary.sample_peak_max.unwrap())
+            .fold(-f64::INFINITY, f64::max);
+        assert_eq!(Some(sample_peak), ebu.summary().unwrap().sample_peak_max);
+    }
+}
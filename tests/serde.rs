@@ -0,0 +1,45 @@
+#![cfg(feature = "serde")]
+
+use ebur128::{EbuR128, Mode};
+
+const RATE: u32 = 48_000;
+
+fn signal(frames: usize) -> Vec<f32> {
+    let mut state = 0x1234_5678_u32;
+    (0..frames * 2)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            noise * (0.05 + 0.5 * (i as f32 / RATE as f32 / 3.0).sin().abs())
+        })
+        .collect()
+}
+
+#[test]
+fn resume_after_round_trip() {
+    // Not a multiple of the 100ms segments, so that partial blocks and filter states matter
+    let frames = 10 * RATE as usize + 1234;
+    let samples = signal(2 * frames);
+    let (first, second) = samples.split_at(samples.len() / 2);
+
+    for mode in [
+        Mode::all() - Mode::HISTOGRAM,
+        Mode::all(),
+        Mode::I | Mode::LRA | Mode::TRUE_PEAK,
+    ] {
+        let mut single = EbuR128::new(2, RATE, mode).unwrap();
+        single.add_frames_f32(&samples).unwrap();
+
+        let mut ebu = EbuR128::new(2, RATE, mode).unwrap();
+        ebu.add_frames_f32(first).unwrap();
+        let json = serde_json::to_string(&ebu).unwrap();
+        let mut resumed: EbuR128 = serde_json::from_str(&json).unwrap();
+        resumed.add_frames_f32(second).unwrap();
+
+        assert_eq!(resumed.loudness_global(), single.loudness_global());
+        assert_eq!(resumed.loudness_range(), single.loudness_range());
+        assert_eq!(resumed.loudness_shortterm(), single.loudness_shortterm());
+        assert_eq!(resumed.true_peak(0), single.true_peak(0));
+        assert_eq!(resumed.true_peak(1), single.true_peak(1));
+    }
+}
@@ -0,0 +1,68 @@
+// The SIMD implementations are switched for the whole process, so this is the only test in its
+// binary to not affect other tests running in parallel.
+
+use ebur128::{EbuR128, Mode};
+
+/// Deterministic random numbers in the range 0 to 1.
+struct Random(u32);
+
+impl Random {
+    fn next(&mut self) -> f32 {
+        self.0 = self.0.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() * n as f32) as usize % n
+    }
+}
+
+fn analyze(channels: u32, rate: u32, chunks: &[Vec<f32>]) -> (Vec<f64>, f64) {
+    let mut ebu = EbuR128::new(channels, rate, Mode::I | Mode::TRUE_PEAK).unwrap();
+    for chunk in chunks {
+        ebu.add_frames_f32(chunk).unwrap();
+    }
+
+    (
+        (0..channels).map(|c| ebu.true_peak(c).unwrap()).collect(),
+        ebu.loudness_global().unwrap(),
+    )
+}
+
+#[test]
+fn simd_matches_scalar() {
+    let mut random = Random(0x1234_5678);
+
+    for _ in 0..50 {
+        let channels = 1 + random.below(10);
+        let rate = [32_000, 44_100, 48_000, 96_000, 192_000][random.below(5)];
+        let gain = 2.0 * random.next();
+
+        // Chunks of random length, so that the interpolation crosses chunk boundaries anywhere
+        let chunks = (0..1 + random.below(8))
+            .map(|_| {
+                let frames = 1 + random.below(rate as usize / 2);
+                (0..frames * channels)
+                    .map(|_| gain * (random.next() - 0.5))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        ebur128::set_simd_enabled(true);
+        let (simd_peaks, simd_loudness) = analyze(channels as u32, rate, &chunks);
+        ebur128::set_simd_enabled(false);
+        let (scalar_peaks, scalar_loudness) = analyze(channels as u32, rate, &chunks);
+        ebur128::set_simd_enabled(true);
+
+        assert_eq!(
+            simd_peaks, scalar_peaks,
+            "{} channels, {} Hz",
+            channels, rate
+        );
+        assert_eq!(
+            simd_loudness, scalar_loudness,
+            "{} channels, {} Hz",
+            channels, rate
+        );
+    }
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "tokio")]
+
+use ebur128::{EbuR128, Error, Mode};
+
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+const RATE: u32 = 48_000;
+
+/// Simulates audio arriving in chunks, e.g. from the network, by yielding to the runtime before
+/// every chunk.
+struct Chunks {
+    chunks: std::vec::IntoIter<Vec<f32>>,
+    pending: bool,
+}
+
+impl Stream for Chunks {
+    type Item = Vec<f32>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Vec<f32>>> {
+        if !self.pending {
+            self.pending = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        self.pending = false;
+        Poll::Ready(self.chunks.next())
+    }
+}
+
+fn chunks(samples: &[f32], frames: usize) -> Chunks {
+    Chunks {
+        chunks: samples
+            .chunks(2 * frames)
+            .map(|chunk| chunk.to_vec())
+            .collect::<Vec<_>>()
+            .into_iter(),
+        pending: false,
+    }
+}
+
+fn noise(seconds: usize) -> Vec<f32> {
+    let mut state = 0x1234_5678_u32;
+    (0..2 * seconds * RATE as usize)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            noise * (0.05 + 0.5 * (i as f32 / RATE as f32 / 7.0).sin().abs())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn analyze_chunked_stream() {
+    let samples = noise(20);
+    let mode = Mode::I | Mode::LRA | Mode::TRUE_PEAK;
+    let mut expected = EbuR128::new(2, RATE, mode).unwrap();
+    expected.add_frames_f32(&samples).unwrap();
+
+    let ebu = EbuR128::new(2, RATE, mode).unwrap();
+    let (ebu, res) = ebu.add_frames_stream(chunks(&samples, 4801)).await;
+    res.unwrap();
+    assert_eq!(ebu.frames_processed(), expected.frames_processed());
+    assert_eq!(ebu.loudness_global(), expected.loudness_global());
+    assert_eq!(ebu.loudness_range(), expected.loudness_range());
+    assert_eq!(ebu.true_peak(1), expected.true_peak(1));
+
+    // The analyzer is handed back and can continue with another stream
+    let (ebu, res) = ebu.add_frames_stream(chunks(&samples, 1000)).await;
+    res.unwrap();
+    assert_eq!(ebu.frames_processed(), 2 * expected.frames_processed());
+}
+
+#[tokio::test]
+async fn stream_stops_at_the_first_error() {
+    // The second chunk has an incomplete frame
+    let stream = Chunks {
+        chunks: vec![vec![0.1; 2 * 4800], vec![0.1; 3], vec![0.1; 2 * 4800]].into_iter(),
+        pending: false,
+    };
+
+    let ebu = EbuR128::new(2, RATE, Mode::I).unwrap();
+    let (ebu, res) = ebu.add_frames_stream(stream).await;
+    assert_eq!(res, Err(Error::NoMem));
+    assert_eq!(ebu.frames_processed(), 4800);
+}
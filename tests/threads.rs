@@ -0,0 +1,50 @@
+use ebur128::{EbuR128, EbuR128Builder, Mode, TruePeak};
+
+const RATE: u32 = 48_000;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn analyzers_are_send_and_sync() {
+    assert_send_sync::<EbuR128>();
+    assert_send_sync::<EbuR128Builder>();
+    assert_send_sync::<TruePeak>();
+}
+
+#[test]
+fn analyze_on_another_thread() {
+    let samples = (0..10 * RATE as usize)
+        .flat_map(|i| {
+            let s = 0.1 * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / RATE as f32).sin();
+            [s, s]
+        })
+        .collect::<Vec<_>>();
+
+    let mut expected = EbuR128::new(2, RATE, Mode::I | Mode::TRUE_PEAK).unwrap();
+    expected.add_frames_f32(&samples).unwrap();
+
+    let ebu = EbuR128::new(2, RATE, Mode::I | Mode::TRUE_PEAK).unwrap();
+    let ebu = std::thread::spawn(move || {
+        let mut ebu = ebu;
+        for chunk in samples.chunks(2 * 4800) {
+            ebu.add_frames_f32(chunk).unwrap();
+        }
+        ebu
+    })
+    .join()
+    .unwrap();
+    assert_eq!(ebu.loudness_global(), expected.loudness_global());
+    assert_eq!(ebu.true_peak(0), expected.true_peak(0));
+
+    // Reading from multiple threads at once through shared references
+    let ebu = std::sync::Arc::new(ebu);
+    let handles = (0..4)
+        .map(|_| {
+            let ebu = ebu.clone();
+            std::thread::spawn(move || ebu.loudness_global().unwrap())
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        assert_eq!(Ok(handle.join().unwrap()), expected.loudness_global());
+    }
+}
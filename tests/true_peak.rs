@@ -0,0 +1,219 @@
+use ebur128::{EbuR128, Error, Mode, TruePeak};
+
+const RATE: u32 = 48_000;
+
+/// Samples of a full-scale 1kHz sine with a phase offset, so that the peaks fall between samples.
+fn sine(frames: usize) -> impl Iterator<Item = f64> {
+    (0..frames).map(|i| {
+        let t = i as f64 / RATE as f64;
+        (2.0 * std::f64::consts::PI * 1000.0 * t + 0.3).sin()
+    })
+}
+
+fn dbtp(true_peak: f64) -> f64 {
+    20.0 * true_peak.log10()
+}
+
+#[test]
+fn integer_full_scale_sine() {
+    let i16_samples = sine(RATE as usize)
+        .map(|s| (s * i16::MAX as f64).round() as i16)
+        .collect::<Vec<_>>();
+    let mut ebu = EbuR128::new(1, RATE, Mode::TRUE_PEAK).unwrap();
+    ebu.add_frames_i16(&i16_samples).unwrap();
+    let true_peak = dbtp(ebu.true_peak(0).unwrap());
+    assert!((-0.5..0.5).contains(&true_peak), "{}", true_peak);
+
+    let i24_samples = sine(RATE as usize)
+        .map(|s| (s * 8_388_607.0).round() as i32)
+        .collect::<Vec<_>>();
+    let mut ebu = EbuR128::new(1, RATE, Mode::TRUE_PEAK).unwrap();
+    ebu.add_frames_i24_unpacked(&i24_samples).unwrap();
+    let true_peak = dbtp(ebu.true_peak(0).unwrap());
+    assert!((-0.5..0.5).contains(&true_peak), "{}", true_peak);
+}
+
+#[test]
+fn oversampled_output_contains_the_true_peak() {
+    // Stereo sines with different levels and the peaks between the samples
+    let frames = sine(RATE as usize / 10)
+        .flat_map(|s| [(0.9 * s) as f32, (-0.4 * s) as f32])
+        .collect::<Vec<_>>();
+
+    let mut tp = TruePeak::new(RATE, 2).unwrap();
+    let mut out = Vec::new();
+    let mut max = [0.0f32; 2];
+    for chunk in frames.chunks(2 * 480) {
+        tp.process_with_output(chunk, &mut out).unwrap();
+        assert_eq!(out.len(), chunk.len() * tp.oversampling_factor());
+
+        for (c, max) in max.iter_mut().enumerate() {
+            *max = out
+                .iter()
+                .skip(c)
+                .step_by(2)
+                .fold(*max, |max, s| max.max(s.abs()));
+        }
+    }
+
+    for (c, max) in max.iter().enumerate() {
+        let true_peak = tp.true_peak(c as u32).unwrap();
+        assert_eq!(true_peak, f64::from(*max));
+        assert!(true_peak > [0.9, 0.4][c]);
+    }
+}
+
+#[test]
+fn distinct_peaks_of_24_channels() {
+    const CHANNELS: usize = 24;
+
+    let mut ebu = EbuR128::new(CHANNELS as u32, RATE, Mode::all()).unwrap();
+
+    // Each channel is a sine with its own level, from -1 dBTP down in 1 dB steps
+    let levels = (0..CHANNELS)
+        .map(|c| 10f64.powf(-(1.0 + c as f64) / 20.0))
+        .collect::<Vec<_>>();
+    let samples = sine(RATE as usize)
+        .flat_map(|s| levels.iter().map(move |level| (s * level) as f32))
+        .collect::<Vec<_>>();
+    ebu.add_frames_f32(&samples).unwrap();
+
+    for (c, level) in levels.iter().enumerate() {
+        let true_peak = ebu.true_peak(c as u32).unwrap();
+        let sample_peak = ebu.sample_peak(c as u32).unwrap();
+        assert!(
+            (dbtp(true_peak) - dbtp(*level)).abs() < 0.05,
+            "{} {} {}",
+            c,
+            true_peak,
+            level
+        );
+        assert!(true_peak >= sample_peak);
+    }
+
+    let max = ebu.true_peak_max_all_channels().unwrap();
+    assert!((max - dbtp(levels[0])).abs() < 0.05, "{}", max);
+}
+
+#[test]
+fn maximum_of_the_louder_right_channel() {
+    // The right channel overshoots full scale between the samples, the left one stays well below
+    let samples = sine(RATE as usize)
+        .flat_map(|s| [(0.5 * s) as f32, (1.2 * s) as f32])
+        .collect::<Vec<_>>();
+    let mut ebu = EbuR128::new(2, RATE, Mode::TRUE_PEAK).unwrap();
+    ebu.add_frames_f32(&samples).unwrap();
+
+    let left = ebu.true_peak(0).unwrap();
+    let right = ebu.true_peak(1).unwrap();
+    assert!(right > 1.0 && left < 0.6, "{} {}", left, right);
+    assert_eq!(ebu.true_peak_max_all_channels().unwrap(), dbtp(right));
+
+    // Same with the channels swapped
+    let swapped = samples
+        .chunks_exact(2)
+        .flat_map(|f| [f[1], f[0]])
+        .collect::<Vec<_>>();
+    let mut ebu = EbuR128::new(2, RATE, Mode::TRUE_PEAK).unwrap();
+    ebu.add_frames_f32(&swapped).unwrap();
+    assert_eq!(ebu.true_peak(0).unwrap(), right);
+    assert_eq!(ebu.true_peak_max_all_channels().unwrap(), dbtp(right));
+}
+
+#[test]
+fn gapless_tracks_have_no_peak_at_the_boundary() {
+    // Split close to a maximum of the sine, so that a fresh interpolator would overshoot
+    let samples = sine(RATE as usize)
+        .map(|s| (0.5 * s) as f32)
+        .collect::<Vec<_>>();
+    let split = samples
+        .iter()
+        .enumerate()
+        .skip(RATE as usize / 2)
+        .find(|(_, s)| **s > 0.49)
+        .unwrap()
+        .0;
+    let (first, second) = samples.split_at(split);
+
+    let mut whole = EbuR128::new(1, RATE, Mode::TRUE_PEAK).unwrap();
+    whole.add_frames_f32(&samples).unwrap();
+    let whole_peak = whole.true_peak(0).unwrap();
+
+    let mut track = EbuR128::new(1, RATE, Mode::TRUE_PEAK).unwrap();
+    track.add_frames_f32(first).unwrap();
+    let mut next_track = track.fork_for_next_track().unwrap();
+    next_track.add_frames_f32(second).unwrap();
+    let first_peak = track.true_peak(0).unwrap();
+    let second_peak = next_track.true_peak(0).unwrap();
+
+    assert!(first_peak <= whole_peak, "{} {}", first_peak, whole_peak);
+    assert!(second_peak <= whole_peak, "{} {}", second_peak, whole_peak);
+    assert!(
+        (first_peak.max(second_peak) - whole_peak).abs() < 1e-9,
+        "{} {} {}",
+        first_peak,
+        second_peak,
+        whole_peak
+    );
+
+    // A separate instance starts interpolating from silence and sees a peak at the boundary
+    let mut separate = EbuR128::new(1, RATE, Mode::TRUE_PEAK).unwrap();
+    separate.add_frames_f32(second).unwrap();
+    assert!(separate.true_peak(0).unwrap() > whole_peak + 0.01);
+}
+
+#[test]
+fn precalculated_interpolation_coefficients() {
+    for &(factor, taps) in &[(2, 48), (4, 48), (8, 96)] {
+        let precalculated =
+            ebur128::precalculated_interp_filter_coefficients(factor, taps).unwrap();
+        let computed = ebur128::interp_filter_coefficients(factor, taps);
+        assert_eq!(precalculated.len(), taps);
+        for (i, (a, b)) in precalculated.iter().zip(&computed).enumerate() {
+            assert!((a - b).abs() <= 1e-7, "{}x tap {}: {} {}", factor, i, a, b);
+        }
+    }
+    assert_eq!(
+        ebur128::precalculated_interp_filter_coefficients(16, 192),
+        None
+    );
+}
+
+#[test]
+fn tone_burst_at_the_end() {
+    // 150ms of a 1kHz sine fading in, with its loudest peak between the last samples
+    let frames = RATE as usize * 3 / 20;
+    let burst = (0..frames)
+        .map(|i| {
+            let fade = (i + 1) as f64 / frames as f64;
+            let t = (i as f64 - (frames as f64 - 2.5)) / RATE as f64;
+            (fade * (2.0 * std::f64::consts::PI * 1000.0 * t).cos()) as f32
+        })
+        .collect::<Vec<_>>();
+    let mode = Mode::M | Mode::SAMPLE_PEAK | Mode::TRUE_PEAK;
+
+    let mut padded = EbuR128::new(1, RATE, mode).unwrap();
+    padded.add_frames_f32(&burst).unwrap();
+    padded.add_frames_f32(&[0.0; 1000]).unwrap();
+
+    let mut ebu = EbuR128::new(1, RATE, mode).unwrap();
+    ebu.add_frames_f32(&burst).unwrap();
+    // The interpolator still holds the end of the burst
+    let true_peak = ebu.true_peak(0).unwrap();
+    assert!(dbtp(true_peak) < dbtp(padded.true_peak(0).unwrap()) - 0.5);
+    // No 400ms block was completed yet
+    assert_eq!(ebu.loudness_momentary_max(), Err(Error::NoChange));
+
+    ebu.flush().unwrap();
+    assert_eq!(ebu.true_peak(0), padded.true_peak(0));
+    assert!(ebu.true_peak(0).unwrap() > ebu.sample_peak(0).unwrap());
+    let momentary = ebu.loudness_momentary_max().unwrap();
+    assert!(momentary.is_finite());
+    assert!((momentary - ebu.loudness_momentary().unwrap()).abs() < 1e-9);
+
+    // Adding more frames requires a reset
+    assert!(ebu.add_frames_f32(&burst).is_err());
+    ebu.reset();
+    ebu.add_frames_f32(&burst).unwrap();
+    assert_eq!(ebu.true_peak(0), Ok(true_peak));
+}
@@ -0,0 +1,142 @@
+#![cfg(feature = "hound")]
+
+use ebur128::{EbuR128, Error, Mode};
+use std::io::{Cursor, Read};
+
+const RATE: u32 = 48_000;
+
+fn wav(samples: &[i16]) -> Vec<u8> {
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut data = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut data, spec).unwrap();
+    for sample in samples {
+        writer.write_sample(*sample).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    data.into_inner()
+}
+
+/// Stereo WAV file with one frame of 64 bit floating point samples.
+fn float64_wav() -> Vec<u8> {
+    const IEEE_FLOAT: [u8; 16] = [
+        0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b,
+        0x71,
+    ];
+
+    let mut data = Vec::new();
+    data.extend_from_slice(b"RIFF");
+    data.extend_from_slice(&(4 + 8 + 40 + 8 + 16u32).to_le_bytes());
+    data.extend_from_slice(b"WAVEfmt ");
+    data.extend_from_slice(&40u32.to_le_bytes());
+    data.extend_from_slice(&0xfffeu16.to_le_bytes());
+    data.extend_from_slice(&2u16.to_le_bytes());
+    data.extend_from_slice(&RATE.to_le_bytes());
+    data.extend_from_slice(&(RATE * 16).to_le_bytes());
+    data.extend_from_slice(&16u16.to_le_bytes());
+    data.extend_from_slice(&64u16.to_le_bytes());
+    data.extend_from_slice(&22u16.to_le_bytes());
+    data.extend_from_slice(&64u16.to_le_bytes());
+    data.extend_from_slice(&3u32.to_le_bytes());
+    data.extend_from_slice(&IEEE_FLOAT);
+    data.extend_from_slice(b"data");
+    data.extend_from_slice(&16u32.to_le_bytes());
+    data.extend_from_slice(&[0; 16]);
+
+    data
+}
+
+/// Reader that fails after the first `len` bytes.
+struct FailingReader<'a> {
+    data: &'a [u8],
+    len: usize,
+}
+
+impl Read for FailingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.len == 0 {
+            return Err(std::io::Error::other("failed"));
+        }
+
+        let n = buf.len().min(self.len).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        self.len -= n;
+        Ok(n)
+    }
+}
+
+#[test]
+fn analysis_is_flushed() {
+    // 1.25s, so that the last block is incomplete and the sine ends at its peak
+    let samples = (0..RATE as usize * 5 / 4)
+        .flat_map(|i| {
+            let s =
+                (2.0 * std::f64::consts::PI * 1000.0 * (i + 1) as f64 / RATE as f64 + 0.3).sin();
+            let s = (s * 16_000.0) as i16;
+            [s, s / 2]
+        })
+        .collect::<Vec<_>>();
+    let data = wav(&samples);
+
+    let mode = Mode::I | Mode::S | Mode::TRUE_PEAK;
+    let analysis = ebur128::analyze_wav(hound::WavReader::new(&data[..]).unwrap(), mode).unwrap();
+
+    let mut ebu = EbuR128::new(2, RATE, mode).unwrap();
+    ebu.add_frames_i16(&samples).unwrap();
+    ebu.flush().unwrap();
+
+    assert_eq!(analysis.summary, ebu.summary().unwrap());
+    assert_eq!(
+        analysis.true_peak.unwrap(),
+        ebu.true_peak_per_channel()
+            .unwrap()
+            .iter()
+            .map(|peak| 20.0 * peak.log10())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn errors() {
+    let samples = vec![0; RATE as usize * 2];
+    let data = wav(&samples);
+
+    // Failing after the header was read
+    let reader = hound::WavReader::new(FailingReader {
+        data: &data,
+        len: 100,
+    })
+    .unwrap();
+    assert_eq!(
+        ebur128::analyze_wav(reader, Mode::I).unwrap_err(),
+        Error::Io
+    );
+
+    // Truncated file
+    let reader = hound::WavReader::new(&data[..data.len() - 1]).unwrap();
+    assert_eq!(
+        ebur128::analyze_wav(reader, Mode::I).unwrap_err(),
+        Error::Io
+    );
+
+    // 64 bit floating point samples are not supported
+    let float64 = float64_wav();
+    let reader = hound::WavReader::new(&float64[..]).unwrap();
+    assert_eq!(
+        ebur128::analyze_wav(reader, Mode::I).unwrap_err(),
+        Error::Format
+    );
+
+    let empty = wav(&[]);
+    let reader = hound::WavReader::new(&empty[..]).unwrap();
+    assert_eq!(
+        ebur128::analyze_wav(reader, Mode::I).unwrap_err(),
+        Error::NoChange
+    );
+}
@@ -0,0 +1,116 @@
+use ebur128::{EbuR128, Error, Mode};
+
+const RATE: u32 = 48_000;
+
+fn noise(seconds: usize) -> Vec<f32> {
+    let mut state = 0x1234_5678_u32;
+    (0..seconds * RATE as usize)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            noise * (0.1 + 0.4 * (i as f32 / RATE as f32 / 5.0).sin().abs())
+        })
+        .collect()
+}
+
+#[test]
+fn window_slider() {
+    let samples = noise(60);
+
+    let mut ebu = EbuR128::new(1, RATE, Mode::I).unwrap();
+    ebu.set_max_history(30_000).unwrap();
+    ebu.add_frames_f32(&samples).unwrap();
+    let global = ebu.loudness_global().unwrap();
+
+    // Dragging a slider over all windows doesn't change any state
+    let mut previous = None;
+    for window in (100..=30_000).step_by(100) {
+        let loudness = ebu.loudness_window(window).unwrap();
+        assert!(loudness.is_finite());
+        assert_ne!(previous, Some(loudness));
+        previous = Some(loudness);
+    }
+    assert_eq!(
+        ebu.loudness_window(30_100),
+        Err(Error::RequestedWindowTooLarge {
+            available_ms: 30_000,
+            requested_ms: 30_100,
+        })
+    );
+    assert_eq!(ebu.loudness_global().unwrap(), global);
+
+    // Windows longer than the audio buffer are calculated from the 100ms segments, which gives
+    // the same result as from the buffered audio at the segment boundaries
+    let mut buffered = EbuR128::new(1, RATE, Mode::I).unwrap();
+    buffered.set_max_window(20_000).unwrap();
+    buffered.add_frames_f32(&samples).unwrap();
+    let from_segments = ebu.loudness_window(20_000).unwrap();
+    let from_buffer = buffered.loudness_window(20_000).unwrap();
+    assert!(
+        (from_segments - from_buffer).abs() < 1e-9,
+        "{} {}",
+        from_segments,
+        from_buffer
+    );
+}
+
+#[test]
+fn default_history_only_retains_the_window() {
+    let samples = noise(10);
+
+    for mode in [Mode::M | Mode::S, Mode::M | Mode::S | Mode::HISTOGRAM] {
+        let mut ebu = EbuR128::new(1, RATE, mode).unwrap();
+        ebu.add_frames_f32(&samples).unwrap();
+        let memory = ebu.memory_usage();
+
+        for _ in 0..6 {
+            ebu.add_frames_f32(&samples).unwrap();
+        }
+        assert_eq!(ebu.memory_usage(), memory);
+
+        assert!(ebu.loudness_window(3000).is_ok());
+        assert_eq!(
+            ebu.loudness_window(3100),
+            Err(Error::RequestedWindowTooLarge {
+                available_ms: 3000,
+                requested_ms: 3100,
+            })
+        );
+
+        // The histories only cover the retained window
+        assert_eq!(ebu.loudness_shortterm_history().unwrap().len(), 1);
+        assert_eq!(ebu.loudness_momentary_history().unwrap().len(), 27);
+    }
+}
+
+#[test]
+fn forked_track_starts_with_empty_histories() {
+    let mode = Mode::I | Mode::LRA | Mode::TRUE_PEAK;
+    let mut ebu = EbuR128::new(1, RATE, mode).unwrap();
+    ebu.add_frames_f32(&noise(60)).unwrap();
+    assert!(ebu.gating_blocks_iter().unwrap().count() > 500);
+
+    let next = ebu.fork_for_next_track().unwrap();
+    assert_eq!(next.gating_blocks_iter().unwrap().count(), 0);
+    assert_eq!(
+        next.memory_usage().block_history,
+        EbuR128::new(1, RATE, mode)
+            .unwrap()
+            .memory_usage()
+            .block_history
+    );
+}
+
+#[test]
+fn window_shorter_than_a_segment() {
+    let mut ebu = EbuR128::new(1, RATE, Mode::I).unwrap();
+    for window in [0, 1, 50, 99] {
+        assert_eq!(ebu.loudness_window(window), Err(Error::OutOfRange));
+    }
+
+    ebu.add_frames_f32(&noise(1)).unwrap();
+    for window in [0, 1, 50, 99] {
+        assert_eq!(ebu.loudness_window(window), Err(Error::OutOfRange));
+    }
+    assert!(ebu.loudness_window(100).unwrap().is_finite());
+}